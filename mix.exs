@@ -29,6 +29,82 @@ defmodule GitFoil.MixProject do
         chacha20poly1305_nif: [
           path: "native/chacha20poly1305_nif",
           mode: rustc_mode(Mix.env())
+        ],
+        sha2_nif: [
+          path: "native/sha2_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        checksum_nif: [
+          path: "native/checksum_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        buzhash_nif: [
+          path: "native/buzhash_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        keycache_nif: [
+          path: "native/keycache_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        aes_gcm_nif: [
+          path: "native/aes_gcm_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        aad_nif: [
+          path: "native/aad_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        threefish_nif: [
+          path: "native/threefish_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        kuznyechik_mgm_nif: [
+          path: "native/kuznyechik_mgm_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        xoodyak_nif: [
+          path: "native/xoodyak_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        scanner_nif: [
+          path: "native/scanner_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        plaintext_fd_nif: [
+          path: "native/plaintext_fd_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        sm4_gcm_nif: [
+          path: "native/sm4_gcm_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        kdf_nif: [
+          path: "native/kdf_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        blake3_nif: [
+          path: "native/blake3_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        sha3_nif: [
+          path: "native/sha3_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        armor_nif: [
+          path: "native/armor_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        blake2_nif: [
+          path: "native/blake2_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        merkle_nif: [
+          path: "native/merkle_nif",
+          mode: rustc_mode(Mix.env())
+        ],
+        git_object_nif: [
+          path: "native/git_object_nif",
+          mode: rustc_mode(Mix.env())
         ]
       ],
       # CLI escript configuration
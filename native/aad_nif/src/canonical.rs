@@ -0,0 +1,300 @@
+//! Canonical AAD encoding
+//!
+//! Serializes a metadata map to a single byte string so the same map always
+//! authenticates the same way, regardless of the order fields were built in
+//! on the Elixir side.
+//!
+//! **Format (version 1, all integers big-endian):**
+//!
+//!   version:u8 | count:u32 | count x (key_len:u16 | key | value_len:u32 | value)
+//!
+//! Entries are sorted by key bytes, keys are non-empty and unique. The
+//! decoder accepts only this canonical form: unsorted or duplicate keys,
+//! trailing bytes and unknown versions are rejected, so every map has
+//! exactly one valid encoding. New fields are just new keys; old blobs keep
+//! authenticating because their encoding does not change.
+
+/// Current encoding version
+pub const VERSION: u8 = 1;
+
+/// Longest key accepted (bytes)
+pub const MAX_KEY_LEN: usize = u16::MAX as usize;
+
+/// Longest value accepted (bytes)
+pub const MAX_VALUE_LEN: usize = u32::MAX as usize;
+
+/// One key/value pair, borrowed
+pub type Entry<'a> = (&'a [u8], &'a [u8]);
+
+/// Why an encode or decode was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanonicalError {
+    /// Leading version byte is not one we understand
+    UnsupportedVersion,
+    /// Input ends in the middle of a field
+    Truncated,
+    /// Bytes left over after the last entry
+    TrailingBytes,
+    /// Keys are not in strictly ascending byte order
+    NotCanonical,
+    /// Same key appears twice
+    DuplicateKey,
+    /// Zero-length key
+    EmptyKey,
+    /// Key or value exceeds its length prefix
+    TooLong,
+}
+
+/// Encode entries canonically
+///
+/// Entries may be given in any order; they are sorted before encoding.
+pub fn encode(entries: &[Entry<'_>]) -> Result<Vec<u8>, CanonicalError> {
+    let mut sorted: Vec<Entry<'_>> = entries.to_vec();
+    sorted.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+    let count = u32::try_from(sorted.len()).map_err(|_| CanonicalError::TooLong)?;
+    let mut size = 1 + 4;
+
+    for (i, (key, value)) in sorted.iter().enumerate() {
+        if key.is_empty() {
+            return Err(CanonicalError::EmptyKey);
+        }
+        if key.len() > MAX_KEY_LEN || value.len() > MAX_VALUE_LEN {
+            return Err(CanonicalError::TooLong);
+        }
+        if i > 0 && sorted[i - 1].0 == *key {
+            return Err(CanonicalError::DuplicateKey);
+        }
+        size += 2 + key.len() + 4 + value.len();
+    }
+
+    let mut out = Vec::with_capacity(size);
+    out.push(VERSION);
+    out.extend_from_slice(&count.to_be_bytes());
+    for (key, value) in sorted {
+        out.extend_from_slice(&(key.len() as u16).to_be_bytes());
+        out.extend_from_slice(key);
+        out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        out.extend_from_slice(value);
+    }
+
+    Ok(out)
+}
+
+/// Cursor over the encoded bytes
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CanonicalError> {
+        if self.data.len() < n {
+            return Err(CanonicalError::Truncated);
+        }
+        let (head, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> Result<u16, CanonicalError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, CanonicalError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+/// Decode a canonical encoding
+///
+/// Returns entries in key order, borrowing from `data`.
+pub fn decode(data: &[u8]) -> Result<Vec<Entry<'_>>, CanonicalError> {
+    let mut reader = Reader { data };
+
+    match reader.take(1)?[0] {
+        VERSION => {}
+        _ => return Err(CanonicalError::UnsupportedVersion),
+    }
+
+    let count = reader.u32()? as usize;
+
+    // Each entry takes at least 6 bytes of length prefixes, so a huge count
+    // in a short input is rejected before allocating for it
+    if count > reader.data.len() / 6 {
+        return Err(CanonicalError::Truncated);
+    }
+
+    let mut entries: Vec<Entry<'_>> = Vec::with_capacity(count);
+    for _ in 0..count {
+        let key_len = reader.u16()? as usize;
+        let key = reader.take(key_len)?;
+        let value_len = reader.u32()? as usize;
+        let value = reader.take(value_len)?;
+
+        if key.is_empty() {
+            return Err(CanonicalError::EmptyKey);
+        }
+        if let Some((prev, _)) = entries.last() {
+            match (*prev).cmp(key) {
+                std::cmp::Ordering::Less => {}
+                std::cmp::Ordering::Equal => return Err(CanonicalError::DuplicateKey),
+                std::cmp::Ordering::Greater => return Err(CanonicalError::NotCanonical),
+            }
+        }
+        entries.push((key, value));
+    }
+
+    if !reader.data.is_empty() {
+        return Err(CanonicalError::TrailingBytes);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_map() {
+        let encoded = encode(&[]).unwrap();
+        assert_eq!(encoded, vec![1, 0, 0, 0, 0]);
+        assert!(decode(&encoded).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_known_encoding() {
+        let encoded = encode(&[(b"path", b"a.txt"), (b"alg", b"\x01")]).unwrap();
+        let expected: Vec<u8> = [
+            &[1u8, 0, 0, 0, 2][..],
+            &[0, 3],
+            b"alg",
+            &[0, 0, 0, 1, 1],
+            &[0, 4],
+            b"path",
+            &[0, 0, 0, 5],
+            b"a.txt",
+        ]
+        .concat();
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_order_independent() {
+        let a = encode(&[(b"a", b"1"), (b"b", b"2"), (b"c", b"")]).unwrap();
+        let b = encode(&[(b"c", b""), (b"a", b"1"), (b"b", b"2")]).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let value = vec![0xAAu8; 70_000];
+        let entries: Vec<(&[u8], &[u8])> = vec![
+            (b"a", b""),
+            (b"ab", b"\x00\x01"),
+            (b"b", &value),
+            (b"\xff", b"x"),
+        ];
+        let encoded = encode(&entries).unwrap();
+        assert_eq!(decode(&encoded).unwrap(), entries);
+    }
+
+    #[test]
+    fn test_prefix_keys_sort_shorter_first() {
+        let encoded = encode(&[(b"ab", b"2"), (b"a", b"1")]).unwrap();
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!(decoded[0].0, b"a");
+        assert_eq!(decoded[1].0, b"ab");
+    }
+
+    #[test]
+    fn test_encode_rejects_bad_keys() {
+        assert_eq!(encode(&[(b"", b"x")]), Err(CanonicalError::EmptyKey));
+        assert_eq!(
+            encode(&[(b"k", b"1"), (b"k", b"2")]),
+            Err(CanonicalError::DuplicateKey)
+        );
+
+        let long_key = vec![b'k'; MAX_KEY_LEN + 1];
+        assert_eq!(encode(&[(&long_key, b"")]), Err(CanonicalError::TooLong));
+
+        let max_key = vec![b'k'; MAX_KEY_LEN];
+        assert!(encode(&[(&max_key, b"")]).is_ok());
+    }
+
+    #[test]
+    fn test_decode_rejects_versions() {
+        for version in [0u8, 2, 0xFF] {
+            assert_eq!(
+                decode(&[version, 0, 0, 0, 0]),
+                Err(CanonicalError::UnsupportedVersion)
+            );
+        }
+        assert_eq!(decode(&[]), Err(CanonicalError::Truncated));
+    }
+
+    #[test]
+    fn test_decode_rejects_every_truncation() {
+        let encoded = encode(&[(b"key", b"value"), (b"other", b"v")]).unwrap();
+        for len in 1..encoded.len() {
+            assert_eq!(
+                decode(&encoded[..len]),
+                Err(CanonicalError::Truncated),
+                "prefix of {} bytes",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_trailing_bytes() {
+        let mut encoded = encode(&[(b"k", b"v")]).unwrap();
+        encoded.push(0);
+        assert_eq!(decode(&encoded), Err(CanonicalError::TrailingBytes));
+    }
+
+    #[test]
+    fn test_decode_rejects_non_canonical() {
+        // Valid framing, but keys in descending order
+        let unsorted: Vec<u8> = [
+            &[1u8, 0, 0, 0, 2][..],
+            &[0, 1, b'b', 0, 0, 0, 0],
+            &[0, 1, b'a', 0, 0, 0, 0],
+        ]
+        .concat();
+        assert_eq!(decode(&unsorted), Err(CanonicalError::NotCanonical));
+
+        let duplicate: Vec<u8> = [
+            &[1u8, 0, 0, 0, 2][..],
+            &[0, 1, b'a', 0, 0, 0, 0],
+            &[0, 1, b'a', 0, 0, 0, 0],
+        ]
+        .concat();
+        assert_eq!(decode(&duplicate), Err(CanonicalError::DuplicateKey));
+
+        let empty_key: Vec<u8> = [&[1u8, 0, 0, 0, 1][..], &[0, 0, 0, 0, 0, 0]].concat();
+        assert_eq!(decode(&empty_key), Err(CanonicalError::EmptyKey));
+    }
+
+    #[test]
+    fn test_decode_rejects_huge_count() {
+        assert_eq!(
+            decode(&[1, 0xFF, 0xFF, 0xFF, 0xFF]),
+            Err(CanonicalError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_single_bit_flips_never_decode_to_same_map() {
+        let entries: Vec<(&[u8], &[u8])> = vec![(b"alg", b"aegis"), (b"path", b"x")];
+        let encoded = encode(&entries).unwrap();
+
+        for i in 0..encoded.len() * 8 {
+            let mut flipped = encoded.clone();
+            flipped[i / 8] ^= 1 << (i % 8);
+            if let Ok(decoded) = decode(&flipped) {
+                assert_ne!(decoded, entries, "bit {} flip decoded to same map", i);
+            }
+        }
+    }
+}
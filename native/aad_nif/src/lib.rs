@@ -0,0 +1,100 @@
+//! Canonical AAD encoding NIF for GitFoil
+//!
+//! Turns an AAD metadata map into one deterministic byte string to feed the
+//! AEADs as associated data, and back again.
+//!
+//! **Encoding:** versioned, length-prefixed, keys sorted by bytes
+//! (see `canonical.rs` for the exact layout)
+//!
+//! Because every map has exactly one encoding, adding a new AAD field only
+//! adds a new key: blobs written before the field existed still encode, and
+//! therefore authenticate, exactly as they did.
+
+mod canonical;
+
+use canonical::CanonicalError;
+use rustler::types::map::MapIterator;
+use rustler::{Atom, Binary, Encoder, Env, Error, OwnedBinary, Term};
+
+rustler::init!("Elixir.GitFoil.Native.AadNif");
+
+mod atoms {
+    rustler::atoms! {
+        unsupported_version,
+        truncated,
+        trailing_bytes,
+        not_canonical,
+        duplicate_key,
+        empty_key,
+        too_long,
+    }
+}
+
+fn error_atom(error: CanonicalError) -> Atom {
+    match error {
+        CanonicalError::UnsupportedVersion => atoms::unsupported_version(),
+        CanonicalError::Truncated => atoms::truncated(),
+        CanonicalError::TrailingBytes => atoms::trailing_bytes(),
+        CanonicalError::NotCanonical => atoms::not_canonical(),
+        CanonicalError::DuplicateKey => atoms::duplicate_key(),
+        CanonicalError::EmptyKey => atoms::empty_key(),
+        CanonicalError::TooLong => atoms::too_long(),
+    }
+}
+
+fn copy_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env)
+}
+
+/// Encode an AAD map canonically
+///
+/// ## Parameters
+/// - map: Map with binary keys and binary values
+///
+/// ## Returns
+/// - Ok(encoded): Canonical encoding (version 1)
+/// - Err: Not a map, a non-binary key or value, or an empty or oversized key
+#[rustler::nif]
+fn encode<'a>(env: Env<'a>, map: Term<'a>) -> Result<Binary<'a>, Error> {
+    let iter = MapIterator::new(map).ok_or(Error::BadArg)?;
+
+    let mut entries: Vec<(Binary, Binary)> = Vec::new();
+    for (key, value) in iter {
+        entries.push((key.decode()?, value.decode()?));
+    }
+
+    let slices: Vec<canonical::Entry> = entries
+        .iter()
+        .map(|(k, v)| (k.as_slice(), v.as_slice()))
+        .collect();
+
+    let encoded = canonical::encode(&slices).map_err(|_| Error::BadArg)?;
+    Ok(copy_binary(env, &encoded))
+}
+
+/// Decode a canonical AAD encoding
+///
+/// Only the canonical form is accepted, so a successful decode followed by
+/// `encode/1` always reproduces the input exactly.
+///
+/// ## Parameters
+/// - encoded: Output of `encode/1`
+///
+/// ## Returns
+/// - {:ok, map}
+/// - {:error, reason}: :unsupported_version, :truncated, :trailing_bytes,
+///   :not_canonical, :duplicate_key or :empty_key
+#[rustler::nif]
+fn decode<'a>(env: Env<'a>, encoded: Binary<'a>) -> Result<Term<'a>, Atom> {
+    let entries = canonical::decode(encoded.as_slice()).map_err(error_atom)?;
+
+    let mut map = Term::map_new(env);
+    for (key, value) in entries {
+        map = map
+            .map_put(copy_binary(env, key).encode(env), copy_binary(env, value).encode(env))
+            .unwrap();
+    }
+    Ok(map)
+}
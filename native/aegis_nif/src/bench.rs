@@ -0,0 +1,68 @@
+//! Benchmark subjects for `benchmark/2` and the criterion benches
+//!
+//! The AEGIS-256 variants the NIF exposes are listed here under a fixed
+//! all-zero key and nonce; only the speed matters, not the ciphertext.
+//! The measuring itself is `nif_support::bench`.
+
+use aegis::aegis256::Aegis256;
+use aegis::aegis256x2::Aegis256X2;
+use aegis::aegis256x4::Aegis256X4;
+use nif_support::bench::Subject;
+
+// AEGIS is keyed together with the nonce, so each message builds a fresh
+// state, as the NIFs do
+
+fn aegis256() -> Subject {
+    Subject::new("aegis_256", 256, |buffer| {
+        let _tag = Aegis256::<32>::new(&[0; 32], &[0; 32]).encrypt_in_place(buffer, b"");
+    })
+}
+
+fn aegis256x2() -> Subject {
+    Subject::new("aegis_256x2", 256, |buffer| {
+        let _tag = Aegis256X2::<32>::new(&[0; 32], &[0; 32]).encrypt_in_place(buffer, b"");
+    })
+}
+
+fn aegis256x4() -> Subject {
+    Subject::new("aegis_256x4", 256, |buffer| {
+        let _tag = Aegis256X4::<32>::new(&[0; 32], &[0; 32]).encrypt_in_place(buffer, b"");
+    })
+}
+
+/// Every AEAD in this library
+pub fn subjects() -> Vec<Subject> {
+    vec![aegis256(), aegis256x2(), aegis256x4()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nif_support::bench::{fastest, throughput};
+    use std::time::Duration;
+
+    #[test]
+    fn test_every_subject_measures() {
+        for subject in subjects() {
+            let mbps = throughput(&subject, 1000, Duration::from_millis(1));
+            assert!(mbps > 0.0, "{} measured {}", subject.name, mbps);
+        }
+    }
+
+    #[test]
+    fn test_seal_encrypts_in_place() {
+        let subject = &subjects()[0];
+        let mut buffer = vec![0u8; 100];
+        subject.seal(&mut buffer);
+        assert!(buffer.iter().any(|&byte| byte != 0));
+    }
+
+    #[test]
+    fn test_fastest_filters_and_ranks() {
+        let ranked = fastest(subjects, 256);
+        assert!(!ranked.is_empty());
+        assert!(ranked.iter().all(|measurement| measurement.security >= 256));
+        assert!(ranked.windows(2).all(|pair| pair[0].mbps >= pair[1].mbps));
+        assert!(fastest(subjects, 512).is_empty());
+    }
+}
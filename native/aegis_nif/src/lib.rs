@@ -1,8 +1,93 @@
-use rustler::{Env, Binary, Error, OwnedBinary};
+pub mod bench;
+
+use nif_support::cipher_nifs::{to_binary, BackendInfo};
+use nif_support::memory;
+use nif_support::stream::SegmentCipher;
+use rustler::{Binary, Env, Error};
 
 rustler::init!("Elixir.GitFoil.Native.AegisNif");
 
-/// AEGIS-256 Encryption
+mod atoms {
+    rustler::atoms! {
+        aegis256,
+        aegis256x2,
+        aegis256x4,
+        aes,
+        vaes_avx2,
+        vaes_avx512,
+        aes_ni,
+        armv8_crypto,
+        soft,
+    }
+}
+
+/// CPU features relevant to choosing an AEGIS variant
+#[derive(rustler::NifMap)]
+struct Capabilities {
+    aes: bool,
+    vaes_avx2: bool,
+    vaes_avx512: bool,
+    preferred: rustler::Atom,
+}
+
+/// Validate key and nonce sizes shared by every AEGIS-256 variant
+fn key_nonce<'b>(
+    key: &'b Binary,
+    nonce: &'b Binary,
+) -> Result<(&'b [u8; 32], &'b [u8; 32]), Error> {
+    let key_array: &[u8; 32] = key.as_slice().try_into().map_err(|_| Error::BadArg)?;
+    let nonce_array: &[u8; 32] = nonce.as_slice().try_into().map_err(|_| Error::BadArg)?;
+    Ok((key_array, nonce_array))
+}
+
+/// Seal `buffer` in place with AEGIS-256X2, returning the tag
+fn seal_x2(key: &[u8; 32], nonce: &[u8; 32], buffer: &mut [u8], aad: &[u8]) -> [u8; 32] {
+    use aegis::aegis256x2::Aegis256X2;
+
+    Aegis256X2::<32>::new(key, nonce).encrypt_in_place(buffer, aad)
+}
+
+/// Open `buffer` in place with AEGIS-256X2; false if the tag doesn't verify
+fn open_x2(
+    key: &[u8; 32],
+    nonce: &[u8; 32],
+    buffer: &mut [u8],
+    tag: &[u8; 32],
+    aad: &[u8],
+) -> bool {
+    use aegis::aegis256x2::Aegis256X2;
+
+    Aegis256X2::<32>::new(key, nonce)
+        .decrypt_in_place(buffer, tag, aad)
+        .is_ok()
+}
+
+/// Seal `buffer` in place with AEGIS-256X4, returning the tag
+fn seal_x4(key: &[u8; 32], nonce: &[u8; 32], buffer: &mut [u8], aad: &[u8]) -> [u8; 32] {
+    use aegis::aegis256x4::Aegis256X4;
+
+    Aegis256X4::<32>::new(key, nonce).encrypt_in_place(buffer, aad)
+}
+
+/// Open `buffer` in place with AEGIS-256X4; false if the tag doesn't verify
+fn open_x4(
+    key: &[u8; 32],
+    nonce: &[u8; 32],
+    buffer: &mut [u8],
+    tag: &[u8; 32],
+    aad: &[u8],
+) -> bool {
+    use aegis::aegis256x4::Aegis256X4;
+
+    Aegis256X4::<32>::new(key, nonce)
+        .decrypt_in_place(buffer, tag, aad)
+        .is_ok()
+}
+
+/// AEGIS-256X2 Encryption
+///
+/// Two AEGIS-256 lanes interleaved; fastest on CPUs with 256-bit VAES.
+/// Output is NOT interchangeable with `encrypt/4` or `encrypt_x4/4`.
 ///
 /// Parameters:
 /// - key: 32 bytes
@@ -13,53 +98,111 @@ rustler::init!("Elixir.GitFoil.Native.AegisNif");
 /// Returns:
 /// - Ok({ciphertext, tag}) where tag is 32 bytes
 /// - Err for errors
-#[rustler::nif]
-fn encrypt<'a>(
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_x2<'a>(
     env: Env<'a>,
     key: Binary,
     nonce: Binary,
     plaintext: Binary,
     aad: Binary,
 ) -> Result<(Binary<'a>, Binary<'a>), Error> {
-    // Validate key length (32 bytes = 256 bits)
-    if key.len() != 32 {
-        return Err(Error::BadArg);
-    }
-
-    // Validate nonce length (32 bytes = 256 bits)
-    if nonce.len() != 32 {
-        return Err(Error::BadArg);
-    }
+    let (key_array, nonce_array) = key_nonce(&key, &nonce)?;
+    let _reservation = memory::reserve(plaintext.len())?;
+    let mut ciphertext = memory::binary(plaintext.len())?;
+    ciphertext
+        .as_mut_slice()
+        .copy_from_slice(plaintext.as_slice());
+    let tag = seal_x2(
+        key_array,
+        nonce_array,
+        ciphertext.as_mut_slice(),
+        aad.as_slice(),
+    );
 
-    // Use the aegis crate's native API
-    use aegis::aegis256::Aegis256;
-
-    // Create key and nonce arrays
-    let key_array: &[u8; 32] = key.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
-    let nonce_array: &[u8; 32] = nonce.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
+    Ok((ciphertext.release(env), to_binary(env, &tag)))
+}
 
-    // Create cipher with key and nonce (32-byte tag)
-    let cipher: Aegis256<32> = Aegis256::new(key_array, nonce_array);
+/// AEGIS-256X2 Decryption
+///
+/// Parameters:
+/// - key: 32 bytes
+/// - nonce: 32 bytes
+/// - ciphertext: variable length
+/// - tag: 32 bytes (authentication tag)
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok(plaintext)
+/// - Err if authentication fails
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_x2<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    ciphertext: Binary,
+    tag: Binary,
+    aad: Binary,
+) -> Result<Binary<'a>, Error> {
+    let (key_array, nonce_array) = key_nonce(&key, &nonce)?;
+    let tag_array: &[u8; 32] = tag.as_slice().try_into().map_err(|_| Error::BadArg)?;
 
-    // Encrypt
-    let (ciphertext, tag) = cipher.encrypt(plaintext.as_slice(), aad.as_slice());
+    let _reservation = memory::reserve(ciphertext.len())?;
+    let mut plaintext = memory::binary(ciphertext.len())?;
+    plaintext
+        .as_mut_slice()
+        .copy_from_slice(ciphertext.as_slice());
+    if !open_x2(
+        key_array,
+        nonce_array,
+        plaintext.as_mut_slice(),
+        tag_array,
+        aad.as_slice(),
+    ) {
+        return Err(Error::RaiseTerm(Box::new("authentication failed")));
+    }
 
-    // Copy to Elixir binaries
-    let mut ciphertext_binary = OwnedBinary::new(ciphertext.len()).unwrap();
-    ciphertext_binary.as_mut_slice().copy_from_slice(&ciphertext);
+    Ok(plaintext.release(env))
+}
 
-    let mut tag_binary = OwnedBinary::new(tag.len()).unwrap();
-    tag_binary.as_mut_slice().copy_from_slice(&tag);
+/// AEGIS-256X4 Encryption
+///
+/// Four AEGIS-256 lanes interleaved; fastest on CPUs with AVX-512 VAES.
+/// Output is NOT interchangeable with `encrypt/4` or `encrypt_x2/4`.
+///
+/// Parameters:
+/// - key: 32 bytes
+/// - nonce: 32 bytes
+/// - plaintext: variable length
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok({ciphertext, tag}) where tag is 32 bytes
+/// - Err for errors
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_x4<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    plaintext: Binary,
+    aad: Binary,
+) -> Result<(Binary<'a>, Binary<'a>), Error> {
+    let (key_array, nonce_array) = key_nonce(&key, &nonce)?;
+    let _reservation = memory::reserve(plaintext.len())?;
+    let mut ciphertext = memory::binary(plaintext.len())?;
+    ciphertext
+        .as_mut_slice()
+        .copy_from_slice(plaintext.as_slice());
+    let tag = seal_x4(
+        key_array,
+        nonce_array,
+        ciphertext.as_mut_slice(),
+        aad.as_slice(),
+    );
 
-    Ok((
-        ciphertext_binary.release(env),
-        tag_binary.release(env),
-    ))
+    Ok((ciphertext.release(env), to_binary(env, &tag)))
 }
 
-/// AEGIS-256 Decryption
+/// AEGIS-256X4 Decryption
 ///
 /// Parameters:
 /// - key: 32 bytes
@@ -71,8 +214,8 @@ fn encrypt<'a>(
 /// Returns:
 /// - Ok(plaintext)
 /// - Err if authentication fails
-#[rustler::nif]
-fn decrypt<'a>(
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_x4<'a>(
     env: Env<'a>,
     key: Binary,
     nonce: Binary,
@@ -80,39 +223,359 @@ fn decrypt<'a>(
     tag: Binary,
     aad: Binary,
 ) -> Result<Binary<'a>, Error> {
-    // Validate input sizes
-    if key.len() != 32 {
-        return Err(Error::BadArg);
+    let (key_array, nonce_array) = key_nonce(&key, &nonce)?;
+    let tag_array: &[u8; 32] = tag.as_slice().try_into().map_err(|_| Error::BadArg)?;
+
+    let _reservation = memory::reserve(ciphertext.len())?;
+    let mut plaintext = memory::binary(ciphertext.len())?;
+    plaintext
+        .as_mut_slice()
+        .copy_from_slice(ciphertext.as_slice());
+    if !open_x4(
+        key_array,
+        nonce_array,
+        plaintext.as_mut_slice(),
+        tag_array,
+        aad.as_slice(),
+    ) {
+        return Err(Error::RaiseTerm(Box::new("authentication failed")));
+    }
+
+    Ok(plaintext.release(env))
+}
+
+/// AES, VAES+AVX2 and VAES+AVX-512 support on the running CPU
+fn cpu_features() -> (bool, bool, bool) {
+    #[cfg(target_arch = "x86_64")]
+    let features = (
+        std::is_x86_feature_detected!("aes"),
+        std::is_x86_feature_detected!("vaes") && std::is_x86_feature_detected!("avx2"),
+        std::is_x86_feature_detected!("vaes") && std::is_x86_feature_detected!("avx512f"),
+    );
+
+    #[cfg(target_arch = "aarch64")]
+    let features = (std::arch::is_aarch64_feature_detected!("aes"), false, false);
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    let features = (false, false, false);
+
+    features
+}
+
+/// An AEGIS-256 variant, by number of interleaved lanes
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Variant {
+    Aegis256,
+    Aegis256X2,
+    Aegis256X4,
+}
+
+impl Variant {
+    fn atom(self) -> rustler::Atom {
+        match self {
+            Variant::Aegis256 => atoms::aegis256(),
+            Variant::Aegis256X2 => atoms::aegis256x2(),
+            Variant::Aegis256X4 => atoms::aegis256x4(),
+        }
     }
-    if nonce.len() != 32 {
-        return Err(Error::BadArg);
+}
+
+/// The widest variant the CPU's VAES support can run at full width
+fn preferred(vaes_avx2: bool, vaes_avx512: bool) -> Variant {
+    if vaes_avx512 {
+        Variant::Aegis256X4
+    } else if vaes_avx2 {
+        Variant::Aegis256X2
+    } else {
+        Variant::Aegis256
+    }
+}
+
+/// Report which AEGIS variant suits the running CPU
+///
+/// All variants run everywhere; this only says which one is fastest. The
+/// variant is part of the ciphertext format, so callers should record the
+/// choice alongside the blob rather than re-query at decryption time.
+///
+/// Returns:
+/// - %{aes: bool, vaes_avx2: bool, vaes_avx512: bool, preferred: atom}
+///   where preferred is :aegis256, :aegis256x2 or :aegis256x4
+#[rustler::nif]
+fn capabilities() -> Capabilities {
+    let (aes, vaes_avx2, vaes_avx512) = cpu_features();
+
+    Capabilities {
+        aes,
+        vaes_avx2,
+        vaes_avx512,
+        preferred: preferred(vaes_avx2, vaes_avx512).atom(),
     }
-    if tag.len() != 32 {
-        return Err(Error::BadArg);
+}
+
+/// Report which hardware path AEGIS-256 takes on the running CPU
+///
+/// libaegis picks AES-NI (with VAES for the x2/x4 variants) or the ARMv8
+/// AES instructions at runtime, and otherwise runs a much slower software
+/// AES round. See `capabilities/0` for which variant to prefer.
+///
+/// Returns:
+/// - %{backend: atom, features: [atom]} where backend is :aes_ni,
+///   :armv8_crypto or :soft, and features lists the CPU features it uses
+#[rustler::nif]
+fn backend_info() -> BackendInfo {
+    let (aes, vaes_avx2, vaes_avx512) = cpu_features();
+    let backend = if !aes {
+        atoms::soft()
+    } else if cfg!(target_arch = "aarch64") {
+        atoms::armv8_crypto()
+    } else {
+        atoms::aes_ni()
+    };
+    let features = [
+        (aes, atoms::aes()),
+        (vaes_avx2, atoms::vaes_avx2()),
+        (vaes_avx512, atoms::vaes_avx512()),
+    ];
+
+    BackendInfo {
+        backend,
+        features: features
+            .iter()
+            .filter(|&&(present, _)| present)
+            .map(|&(_, name)| name)
+            .collect(),
     }
+}
+
+/// AEGIS-256-MAC tag of `data`
+fn mac_tag(key: &[u8; 32], nonce: &[u8; 32], data: &[u8]) -> [u8; 32] {
+    use aegis::aegis256::Aegis256Mac;
+
+    let mut state = Aegis256Mac::<32>::new_with_nonce(key, nonce);
+    state.update(data);
+    state.finalize()
+}
+
+/// Whether `tag` is the AEGIS-256-MAC of `data`, compared in constant time
+fn mac_matches(key: &[u8; 32], nonce: &[u8; 32], data: &[u8], tag: &[u8; 32]) -> bool {
+    use aegis::aegis256::Aegis256Mac;
+
+    let mut state = Aegis256Mac::<32>::new_with_nonce(key, nonce);
+    state.update(data);
+    state.verify(tag).is_ok()
+}
+
+/// AEGIS-256-MAC
+///
+/// Keyed integrity check without encryption, at AEGIS speed. Useful for
+/// manifests that must be tamper-evident but stay readable. A MAC key
+/// must not also be used for `encrypt/4`.
+///
+/// Parameters:
+/// - key: 32 bytes
+/// - nonce: 32 bytes (may be fixed per key; uniqueness is not required)
+/// - data: variable length
+///
+/// Returns:
+/// - Ok(tag) where tag is 32 bytes
+/// - Err for invalid parameters
+#[rustler::nif(schedule = "DirtyCpu")]
+fn mac<'a>(env: Env<'a>, key: Binary, nonce: Binary, data: Binary) -> Result<Binary<'a>, Error> {
+    let (key_array, nonce_array) = key_nonce(&key, &nonce)?;
 
-    // Use the aegis crate's native API
+    Ok(to_binary(
+        env,
+        &mac_tag(key_array, nonce_array, data.as_slice()),
+    ))
+}
+
+/// AEGIS-256-MAC verification
+///
+/// Parameters:
+/// - key: 32 bytes
+/// - nonce: 32 bytes
+/// - data: variable length
+/// - tag: 32 bytes
+///
+/// Returns:
+/// - Ok(true) if the tag matches (constant-time comparison), Ok(false) otherwise
+/// - Err for invalid parameters
+#[rustler::nif(schedule = "DirtyCpu")]
+fn mac_verify(key: Binary, nonce: Binary, data: Binary, tag: Binary) -> Result<bool, Error> {
+    let (key_array, nonce_array) = key_nonce(&key, &nonce)?;
+    let tag_array: &[u8; 32] = tag.as_slice().try_into().map_err(|_| Error::BadArg)?;
+
+    Ok(mac_matches(
+        key_array,
+        nonce_array,
+        data.as_slice(),
+        tag_array,
+    ))
+}
+
+/// AEGIS-256 under one key, the cipher behind the shared NIFs
+///
+/// AEGIS-256 is keyed together with the nonce, so there is no key schedule
+/// to cache; each message builds a fresh state from the stored key.
+struct Aegis256Key([u8; 32]);
+
+impl Aegis256Key {
+    fn new(key: &[u8]) -> Result<Self, Error> {
+        let key_array: [u8; 32] = key.try_into().map_err(|_| Error::BadArg)?;
+        Ok(Aegis256Key(key_array))
+    }
+}
+
+impl SegmentCipher for Aegis256Key {
+    const NONCE_SIZE: usize = 32;
+    const TAG_SIZE: usize = 32;
+
+    fn seal_in_place(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8]) -> Option<Vec<u8>> {
+        use aegis::aegis256::Aegis256;
+
+        let nonce_array: &[u8; 32] = nonce.try_into().ok()?;
+        let cipher: Aegis256<32> = Aegis256::new(&self.0, nonce_array);
+        Some(cipher.encrypt_in_place(buffer, aad).to_vec())
+    }
+
+    fn open_in_place(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8], tag: &[u8]) -> bool {
+        use aegis::aegis256::Aegis256;
+
+        let (Ok(nonce_array), Ok(tag_array)) = (nonce.try_into(), tag.try_into()) else {
+            return false;
+        };
+        let cipher: Aegis256<32> = Aegis256::new(&self.0, nonce_array);
+        cipher.decrypt_in_place(buffer, tag_array, aad).is_ok()
+    }
+}
+
+// encrypt/decrypt, the batch, blob, context and file NIFs, STREAM in all
+// its forms, benchmark/2 and fastest_algorithms/1
+nif_support::cipher_nifs! {
+    cipher: Aegis256Key,
+    name: "AEGIS-256",
+    new: Aegis256Key::new,
+    sizes: { key: 32, nonce: 32, tag: 32, prefix: 27 },
+    subjects: bench::subjects,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
     use aegis::aegis256::Aegis256;
 
-    // Create key and nonce arrays
-    let key_array: &[u8; 32] = key.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
-    let nonce_array: &[u8; 32] = nonce.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
+    const KEY: [u8; 32] = [0x10; 32];
+    const NONCE: [u8; 32] = [0x20; 32];
+    const MESSAGE: &[u8] = b"AEGIS-256X2 and X4 lanes interleave independent states";
 
-    // Create cipher with key and nonce (32-byte tag)
-    let cipher: Aegis256<32> = Aegis256::new(key_array, nonce_array);
+    type Seal = fn(&[u8; 32], &[u8; 32], &mut [u8], &[u8]) -> [u8; 32];
+    type Open = fn(&[u8; 32], &[u8; 32], &mut [u8], &[u8; 32], &[u8]) -> bool;
 
-    // Decrypt and verify
-    let tag_array: &[u8; 32] = tag.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
-    let plaintext = cipher
-        .decrypt(ciphertext.as_slice(), tag_array, aad.as_slice())
-        .map_err(|_| Error::RaiseTerm(Box::new("authentication failed")))?;
+    const VARIANTS: [(&str, Seal, Open); 2] = [("x2", seal_x2, open_x2), ("x4", seal_x4, open_x4)];
 
-    // Copy to Elixir binary
-    let mut plaintext_binary = OwnedBinary::new(plaintext.len()).unwrap();
-    plaintext_binary.as_mut_slice().copy_from_slice(&plaintext);
+    #[test]
+    fn test_wide_variants_roundtrip() {
+        for (name, seal, open) in VARIANTS {
+            let mut buffer = MESSAGE.to_vec();
+            let tag = seal(&KEY, &NONCE, &mut buffer, b"aad");
+            assert_ne!(buffer, MESSAGE, "{}", name);
 
-    Ok(plaintext_binary.release(env))
+            assert!(open(&KEY, &NONCE, &mut buffer, &tag, b"aad"), "{}", name);
+            assert_eq!(buffer, MESSAGE, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_wide_variants_reject_tampering() {
+        for (name, seal, open) in VARIANTS {
+            let mut sealed = MESSAGE.to_vec();
+            let tag = seal(&KEY, &NONCE, &mut sealed, b"aad");
+
+            let mut ciphertext = sealed.clone();
+            ciphertext[0] ^= 1;
+            assert!(
+                !open(&KEY, &NONCE, &mut ciphertext, &tag, b"aad"),
+                "{}",
+                name
+            );
+
+            let mut bad_tag = tag;
+            bad_tag[31] ^= 1;
+            assert!(
+                !open(&KEY, &NONCE, &mut sealed.clone(), &bad_tag, b"aad"),
+                "{}",
+                name
+            );
+
+            assert!(
+                !open(&KEY, &NONCE, &mut sealed.clone(), &tag, b"aaD"),
+                "{}",
+                name
+            );
+
+            let mut other_nonce = NONCE;
+            other_nonce[0] ^= 1;
+            assert!(
+                !open(&KEY, &other_nonce, &mut sealed, &tag, b"aad"),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_variants_not_interchangeable() {
+        let mut x1 = MESSAGE.to_vec();
+        let mut x2 = MESSAGE.to_vec();
+        let mut x4 = MESSAGE.to_vec();
+        let x1_tag = Aegis256::<32>::new(&KEY, &NONCE).encrypt_in_place(&mut x1, b"");
+        let x2_tag = seal_x2(&KEY, &NONCE, &mut x2, b"");
+        let x4_tag = seal_x4(&KEY, &NONCE, &mut x4, b"");
+
+        assert_ne!(x1_tag, x2_tag);
+        assert_ne!(x1_tag, x4_tag);
+        assert_ne!(x2_tag, x4_tag);
+        assert!(!open_x4(&KEY, &NONCE, &mut x2, &x2_tag, b""));
+    }
+
+    #[test]
+    fn test_preferred_variant() {
+        assert_eq!(preferred(false, false), Variant::Aegis256);
+        assert_eq!(preferred(true, false), Variant::Aegis256X2);
+        assert_eq!(preferred(true, true), Variant::Aegis256X4);
+        // AVX-512 VAES without the AVX2 flag still runs four lanes
+        assert_eq!(preferred(false, true), Variant::Aegis256X4);
+    }
+
+    #[test]
+    fn test_mac_verifies_its_own_tag() {
+        let tag = mac_tag(&KEY, &NONCE, MESSAGE);
+        assert!(mac_matches(&KEY, &NONCE, MESSAGE, &tag));
+        assert_eq!(mac_tag(&KEY, &NONCE, MESSAGE), tag);
+    }
+
+    #[test]
+    fn test_mac_verify_rejects_changed_tag() {
+        let tag = mac_tag(&KEY, &NONCE, MESSAGE);
+        for i in [0, 15, 31] {
+            let mut changed = tag;
+            changed[i] ^= 0x80;
+            assert!(!mac_matches(&KEY, &NONCE, MESSAGE, &changed), "byte {}", i);
+        }
+    }
+
+    #[test]
+    fn test_mac_verify_rejects_changed_message() {
+        let tag = mac_tag(&KEY, &NONCE, MESSAGE);
+
+        let mut flipped = MESSAGE.to_vec();
+        flipped[MESSAGE.len() - 1] ^= 1;
+        assert!(!mac_matches(&KEY, &NONCE, &flipped, &tag));
+        assert!(!mac_matches(&KEY, &NONCE, &MESSAGE[1..], &tag));
+        assert!(!mac_matches(&KEY, &NONCE, b"", &tag));
+
+        let mut other_key = KEY;
+        other_key[0] ^= 1;
+        assert!(!mac_matches(&other_key, &NONCE, MESSAGE, &tag));
+    }
 }
@@ -0,0 +1,65 @@
+//! Benchmark subjects for `benchmark/2` and the criterion benches
+//!
+//! The AES-GCM variants the NIF exposes are listed here under a fixed
+//! all-zero key and nonce; only the speed matters, not the ciphertext.
+//! The measuring itself is `nif_support::bench`.
+
+use crate::xaes;
+use aes_gcm::aead::{AeadInPlace, KeyInit};
+use aes_gcm::{Aes128Gcm, Aes256Gcm};
+use aes_gcm_siv::Aes256GcmSiv;
+use nif_support::bench::{aead, Subject};
+
+/// XAES-256-GCM, deriving the per-nonce key for every message as `encrypt_xaes/4` does
+fn xaes() -> Subject {
+    let key = [0u8; 32];
+    let nonce = [0u8; xaes::NONCE_SIZE];
+    Subject::new("xaes_256_gcm", 256, move |buffer| {
+        let (derived_key, derived_nonce) = xaes::derive(&key, &nonce);
+        Aes256Gcm::new(&derived_key.into())
+            .encrypt_in_place_detached(&derived_nonce.into(), b"", buffer)
+            .expect("message too long");
+    })
+}
+
+/// Every AEAD in this library
+pub fn subjects() -> Vec<Subject> {
+    vec![
+        aead::<Aes256Gcm>("aes_256_gcm", 256),
+        aead::<Aes128Gcm>("aes_128_gcm", 128),
+        aead::<Aes256GcmSiv>("aes_256_gcm_siv", 256),
+        xaes(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nif_support::bench::{fastest, throughput};
+    use std::time::Duration;
+
+    #[test]
+    fn test_every_subject_measures() {
+        for subject in subjects() {
+            let mbps = throughput(&subject, 1000, Duration::from_millis(1));
+            assert!(mbps > 0.0, "{} measured {}", subject.name, mbps);
+        }
+    }
+
+    #[test]
+    fn test_seal_encrypts_in_place() {
+        let subject = &subjects()[0];
+        let mut buffer = vec![0u8; 100];
+        subject.seal(&mut buffer);
+        assert!(buffer.iter().any(|&byte| byte != 0));
+    }
+
+    #[test]
+    fn test_fastest_filters_and_ranks() {
+        let ranked = fastest(subjects, 256);
+        assert!(!ranked.is_empty());
+        assert!(ranked.iter().all(|measurement| measurement.security >= 256));
+        assert!(ranked.windows(2).all(|pair| pair[0].mbps >= pair[1].mbps));
+        assert!(fastest(subjects, 512).is_empty());
+    }
+}
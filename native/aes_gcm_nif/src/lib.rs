@@ -0,0 +1,369 @@
+//! AES-GCM NIF for GitFoil
+//!
+//! **Modes:**
+//! - AES-256-GCM (NIST SP 800-38D): `encrypt/4`, `decrypt/5`
+//! - AES-128-GCM (NIST SP 800-38D): `encrypt_128/4`, `decrypt_128/5`
+//! - AES-256-GCM-SIV (RFC 8452): `encrypt_siv/4`, `decrypt_siv/5`
+//! - XAES-256-GCM (C2SP): `encrypt_xaes/4`, `decrypt_xaes/5`
+//!
+//! All modes use AES-NI + PCLMULQDQ (x86_64) or the ARMv8 Cryptography
+//! Extensions (aarch64) when the CPU supports them, with a constant-time
+//! software fallback otherwise. Detection happens at runtime.
+//!
+//! **Parameters (all modes):**
+//! - Key: 256 bits (32 bytes); 128 bits (16 bytes) for AES-128-GCM
+//! - Nonce: 96 bits (12 bytes); 192 bits (24 bytes) for XAES-256-GCM
+//! - Tag: 128 bits (16 bytes)
+//!
+//! **Streaming (AES-256-GCM):** `stream_encrypt_init/3`,
+//! `stream_decrypt_init/3`, `stream_update/2`, `stream_final/1` take input
+//! piece by piece and seal it in 64 KiB segments (see `stream`), for
+//! files too large to hold as one binary.
+
+pub mod bench;
+mod xaes;
+
+use aes_gcm::{Aes128Gcm, Aes256Gcm};
+use aes_gcm_siv::Aes256GcmSiv;
+use nif_support::cipher_nifs::{cipher, open, seal, BackendInfo};
+use nif_support::iodata::IoData;
+use rustler::{Binary, Env, Error};
+
+rustler::init!("Elixir.GitFoil.Native.AesGcmNif");
+
+mod atoms {
+    rustler::atoms! {
+        aes,
+        #[cfg(target_arch = "x86_64")]
+        pclmulqdq,
+        #[cfg(target_arch = "aarch64")]
+        pmull,
+        #[cfg(target_arch = "x86_64")]
+        aes_ni,
+        #[cfg(target_arch = "aarch64")]
+        armv8_crypto,
+        soft,
+    }
+}
+
+const KEY_SIZE: usize = 32;
+
+// encrypt/decrypt, the batch, blob, context and file NIFs, STREAM in all
+// its forms, benchmark/2 and fastest_algorithms/1
+nif_support::cipher_nifs! {
+    cipher: Aes256Gcm,
+    name: "AES-256-GCM",
+    new: cipher::<Aes256Gcm>,
+    sizes: { key: 32, nonce: 12, tag: 16, prefix: 7 },
+    subjects: bench::subjects,
+}
+
+/// AES-128-GCM Encryption
+///
+/// For deployments whose approval process only covers AES-128-GCM.
+/// Prefer the 256-bit modes everywhere else.
+///
+/// Parameters:
+/// - key: 16 bytes (128 bits)
+/// - nonce: 12 bytes (96 bits)
+/// - plaintext: variable length
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok({ciphertext, tag}) where tag is 16 bytes (128 bits)
+/// - Err for invalid parameters
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_128<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    plaintext: IoData,
+    aad: IoData,
+) -> Result<(Binary<'a>, Binary<'a>), Error> {
+    let cipher: Aes128Gcm = cipher(key.as_slice())?;
+    seal(env, &cipher, nonce.as_slice(), &plaintext, &aad.to_cow())
+}
+
+/// AES-128-GCM Decryption
+///
+/// Parameters:
+/// - key: 16 bytes (128 bits)
+/// - nonce: 12 bytes (96 bits)
+/// - ciphertext: variable length
+/// - tag: 16 bytes (128 bits) - authentication tag
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok(plaintext)
+/// - Err if authentication fails or parameters invalid
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_128<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    ciphertext: IoData,
+    tag: Binary,
+    aad: IoData,
+) -> Result<Binary<'a>, Error> {
+    let cipher: Aes128Gcm = cipher(key.as_slice())?;
+    open(
+        env,
+        &cipher,
+        nonce.as_slice(),
+        &ciphertext,
+        tag.as_slice(),
+        &aad.to_cow(),
+    )
+}
+
+/// AES-256-GCM-SIV Encryption
+///
+/// Nonce-misuse resistant: repeating a nonce only reveals whether two
+/// (plaintext, aad) pairs are identical, instead of leaking the keystream
+/// and the authentication key as plain GCM does. Use it where nonces may
+/// be derived deterministically from content.
+///
+/// Parameters:
+/// - key: 32 bytes (256 bits)
+/// - nonce: 12 bytes (96 bits)
+/// - plaintext: variable length
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok({ciphertext, tag}) where tag is 16 bytes (128 bits)
+/// - Err for invalid parameters
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_siv<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    plaintext: IoData,
+    aad: IoData,
+) -> Result<(Binary<'a>, Binary<'a>), Error> {
+    let cipher: Aes256GcmSiv = cipher(key.as_slice())?;
+    seal(env, &cipher, nonce.as_slice(), &plaintext, &aad.to_cow())
+}
+
+/// AES-256-GCM-SIV Decryption
+///
+/// Parameters:
+/// - key: 32 bytes (256 bits)
+/// - nonce: 12 bytes (96 bits)
+/// - ciphertext: variable length
+/// - tag: 16 bytes (128 bits) - synthetic IV / authentication tag
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok(plaintext)
+/// - Err if authentication fails or parameters invalid
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_siv<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    ciphertext: IoData,
+    tag: Binary,
+    aad: IoData,
+) -> Result<Binary<'a>, Error> {
+    let cipher: Aes256GcmSiv = cipher(key.as_slice())?;
+    open(
+        env,
+        &cipher,
+        nonce.as_slice(),
+        &ciphertext,
+        tag.as_slice(),
+        &aad.to_cow(),
+    )
+}
+
+/// XAES-256-GCM Encryption
+///
+/// AES-256-GCM under a key and nonce derived from a 192-bit nonce, so
+/// random nonces are safe for effectively unlimited messages per key.
+///
+/// Parameters:
+/// - key: 32 bytes (256 bits)
+/// - nonce: 24 bytes (192 bits) - random nonces are fine
+/// - plaintext: variable length
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok({ciphertext, tag}) where tag is 16 bytes (128 bits)
+/// - Err for invalid parameters
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_xaes<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    plaintext: IoData,
+    aad: IoData,
+) -> Result<(Binary<'a>, Binary<'a>), Error> {
+    if key.len() != KEY_SIZE || nonce.len() != xaes::NONCE_SIZE {
+        return Err(Error::BadArg);
+    }
+
+    let (derived_key, derived_nonce) = xaes::derive(key.as_slice(), nonce.as_slice());
+    let cipher: Aes256Gcm = cipher(&derived_key)?;
+    seal(env, &cipher, &derived_nonce, &plaintext, &aad.to_cow())
+}
+
+/// XAES-256-GCM Decryption
+///
+/// Parameters:
+/// - key: 32 bytes (256 bits)
+/// - nonce: 24 bytes (192 bits)
+/// - ciphertext: variable length
+/// - tag: 16 bytes (128 bits) - authentication tag
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok(plaintext)
+/// - Err if authentication fails or parameters invalid
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_xaes<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    ciphertext: IoData,
+    tag: Binary,
+    aad: IoData,
+) -> Result<Binary<'a>, Error> {
+    if key.len() != KEY_SIZE || nonce.len() != xaes::NONCE_SIZE {
+        return Err(Error::BadArg);
+    }
+
+    let (derived_key, derived_nonce) = xaes::derive(key.as_slice(), nonce.as_slice());
+    let cipher: Aes256Gcm = cipher(&derived_key)?;
+    open(
+        env,
+        &cipher,
+        &derived_nonce,
+        &ciphertext,
+        tag.as_slice(),
+        &aad.to_cow(),
+    )
+}
+
+/// Report which hardware path AES-256-GCM takes on the running CPU
+///
+/// The `aes` and `polyval` crates choose AES-NI with PCLMULQDQ, or the
+/// ARMv8 Crypto Extensions, at runtime and otherwise fall back to
+/// constant-time software. AES-GCM-SIV and XAES-256-GCM share the same
+/// backends.
+///
+/// Returns:
+/// - %{backend: atom, features: [atom]} where backend is :aes_ni,
+///   :armv8_crypto or :soft, and features lists the CPU features it uses
+#[rustler::nif]
+fn backend_info() -> BackendInfo {
+    #[cfg(target_arch = "x86_64")]
+    let (features, accelerated) = (
+        [
+            (std::is_x86_feature_detected!("aes"), atoms::aes()),
+            (
+                std::is_x86_feature_detected!("pclmulqdq"),
+                atoms::pclmulqdq(),
+            ),
+        ],
+        atoms::aes_ni(),
+    );
+
+    #[cfg(target_arch = "aarch64")]
+    let (features, accelerated) = (
+        [
+            (std::arch::is_aarch64_feature_detected!("aes"), atoms::aes()),
+            (
+                std::arch::is_aarch64_feature_detected!("pmull"),
+                atoms::pmull(),
+            ),
+        ],
+        atoms::armv8_crypto(),
+    );
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    let (features, accelerated) = ([(false, atoms::aes())], atoms::soft());
+
+    // AES and GHASH/POLYVAL are accelerated independently; report the
+    // hardware backend only when both are
+    let backend = if features.iter().all(|&(present, _)| present) {
+        accelerated
+    } else {
+        atoms::soft()
+    };
+    BackendInfo {
+        backend,
+        features: features
+            .iter()
+            .filter(|&&(present, _)| present)
+            .map(|&(_, name)| name)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes_gcm::aead::{Aead, KeyInit, Payload};
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn seal_128(key: &str, nonce: &str, plaintext: &str, aad: &str) -> Vec<u8> {
+        let cipher = Aes128Gcm::new_from_slice(&unhex(key)).unwrap();
+        let payload = Payload {
+            msg: &unhex(plaintext),
+            aad: &unhex(aad),
+        };
+        cipher
+            .encrypt(unhex(nonce).as_slice().into(), payload)
+            .unwrap()
+    }
+
+    /// McGrew-Viega GCM specification, test cases 1, 2 and 4 (AES-128)
+    #[test]
+    fn test_aes_128_gcm_vectors() {
+        assert_eq!(
+            seal_128(
+                "00000000000000000000000000000000",
+                "000000000000000000000000",
+                "",
+                ""
+            ),
+            unhex("58e2fccefa7e3061367f1d57a4e7455a")
+        );
+        assert_eq!(
+            seal_128(
+                "00000000000000000000000000000000",
+                "000000000000000000000000",
+                "00000000000000000000000000000000",
+                ""
+            ),
+            unhex("0388dace60b6a392f328c2b971b2fe78ab6e47d42cec13bdf53a67b21257bddf")
+        );
+        assert_eq!(
+            seal_128(
+                "feffe9928665731c6d6a8f9467308308",
+                "cafebabefacedbaddecaf888",
+                "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+                 1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+                "feedfacedeadbeeffeedfacedeadbeefabaddad2"
+            ),
+            unhex(
+                "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+                 21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091\
+                 5bc94fbc3221a5db94fae95ae7121a47"
+            )
+        );
+    }
+
+    #[test]
+    fn test_cipher_rejects_wrong_key_size() {
+        assert!(cipher::<Aes128Gcm>(&[0u8; 32]).is_err());
+        assert!(cipher::<Aes256Gcm>(&[0u8; 16]).is_err());
+        assert!(cipher::<Aes128Gcm>(&[0u8; 16]).is_ok());
+    }
+}
@@ -0,0 +1,95 @@
+//! XAES-256-GCM key and nonce derivation (C2SP xaes-256-gcm)
+//!
+//! Extends AES-256-GCM to a 192-bit nonce, so nonces can be picked at
+//! random without worrying about the 96-bit birthday bound:
+//!
+//!   L  = AES-256_K(0^128),  K1 = L << 1 (CMAC subkey, with 0x87 reduction)
+//!   M1 = 0x00 || 0x01 || "X" || 0x00 || N[0..12]
+//!   M2 = 0x00 || 0x02 || "X" || 0x00 || N[0..12]
+//!   Kx = AES-256_K(M1 ^ K1) || AES-256_K(M2 ^ K1)
+//!   Nx = N[12..24]
+//!
+//! and then runs plain AES-256-GCM with (Kx, Nx).
+
+use aes::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use aes::Aes256;
+
+/// XAES-256-GCM nonce size in bytes
+pub const NONCE_SIZE: usize = 24;
+
+/// Derive the per-nonce AES-256-GCM key and 96-bit nonce
+///
+/// Callers must pass a 32-byte key and a 24-byte nonce.
+pub fn derive(key: &[u8], nonce: &[u8]) -> ([u8; 32], [u8; 12]) {
+    assert_eq!(
+        nonce.len(),
+        NONCE_SIZE,
+        "XAES-256-GCM nonce must be 24 bytes"
+    );
+    let cipher = Aes256::new_from_slice(key).expect("32-byte key");
+
+    let mut l = GenericArray::from([0u8; 16]);
+    cipher.encrypt_block(&mut l);
+
+    // K1 = L << 1, reduced by the GF(2^128) polynomial if the top bit was set
+    let msb = l[0] >> 7;
+    let mut k1 = [0u8; 16];
+    for i in 0..15 {
+        k1[i] = (l[i] << 1) | (l[i + 1] >> 7);
+    }
+    k1[15] = (l[15] << 1) ^ (0x87 & 0u8.wrapping_sub(msb));
+
+    let mut derived = [0u8; 32];
+    for (counter, half) in derived.chunks_exact_mut(16).enumerate() {
+        let mut m = [0u8; 16];
+        m[1] = counter as u8 + 1;
+        m[2] = b'X';
+        m[4..].copy_from_slice(&nonce[..12]);
+
+        let mut block = GenericArray::from(m);
+        for (b, k) in block.iter_mut().zip(&k1) {
+            *b ^= k;
+        }
+        cipher.encrypt_block(&mut block);
+        half.copy_from_slice(&block);
+    }
+
+    let mut gcm_nonce = [0u8; 12];
+    gcm_nonce.copy_from_slice(&nonce[12..]);
+    (derived, gcm_nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes_gcm::aead::{Aead, KeyInit as _, Payload};
+    use aes_gcm::Aes256Gcm;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// C2SP xaes-256-gcm, first test vector
+    #[test]
+    fn test_c2sp_vector() {
+        let key = [0x01u8; 32];
+        let nonce = b"ABCDEFGHIJKLMNOPQRSTUVWX";
+
+        let (derived_key, derived_nonce) = derive(&key, nonce);
+        let cipher = Aes256Gcm::new_from_slice(&derived_key).unwrap();
+        let ciphertext = cipher
+            .encrypt(
+                (&derived_nonce).into(),
+                Payload {
+                    msg: b"XAES-256-GCM",
+                    aad: b"",
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            hex(&ciphertext),
+            "ce546ef63c9cc60765923609b33a9a1974e96e52daf2fcf7075e2271"
+        );
+    }
+}
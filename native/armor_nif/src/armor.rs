@@ -0,0 +1,439 @@
+//! Streaming ASCII armor
+//!
+//! PEM-style framing around standard base64, wrapped at 64 columns:
+//!
+//! ```text
+//! -----BEGIN GITFOIL ENCRYPTED DATA-----
+//! <base64, 64 characters per line>
+//! -----END GITFOIL ENCRYPTED DATA-----
+//! ```
+//!
+//! `Encoder` and `Decoder` accept input in pieces of any size and carry at
+//! most a partial base64 quantum or one header line between calls, so
+//! streams of any length go through in constant memory.
+//!
+//! The decoder accepts CRLF line endings, blank lines before the header
+//! and trailing whitespace, and ignores how the body is wrapped.
+
+pub const BEGIN_LINE: &[u8] = b"-----BEGIN GITFOIL ENCRYPTED DATA-----";
+pub const END_LINE: &[u8] = b"-----END GITFOIL ENCRYPTED DATA-----";
+
+/// Base64 characters per body line
+const LINE_WIDTH: usize = 64;
+
+/// Longest header or footer line buffered before giving up
+const MAX_MARKER_LINE: usize = 128;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Incremental armor encoder
+pub struct Encoder {
+    pending: [u8; 3],
+    pending_len: usize,
+    column: usize,
+    started: bool,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Encoder {
+            pending: [0; 3],
+            pending_len: 0,
+            column: 0,
+            started: false,
+        }
+    }
+
+    fn start(&mut self, out: &mut Vec<u8>) {
+        if !self.started {
+            out.extend_from_slice(BEGIN_LINE);
+            out.push(b'\n');
+            self.started = true;
+        }
+    }
+
+    fn push_chars(&mut self, chars: &[u8], out: &mut Vec<u8>) {
+        for &c in chars {
+            out.push(c);
+            self.column += 1;
+            if self.column == LINE_WIDTH {
+                out.push(b'\n');
+                self.column = 0;
+            }
+        }
+    }
+
+    fn encode_group(&mut self, group: &[u8; 3], out: &mut Vec<u8>) {
+        let n = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        let chars = [
+            ALPHABET[(n >> 18) as usize & 63],
+            ALPHABET[(n >> 12) as usize & 63],
+            ALPHABET[(n >> 6) as usize & 63],
+            ALPHABET[n as usize & 63],
+        ];
+        self.push_chars(&chars, out);
+    }
+
+    /// Encode the next piece of input, returning the armor produced so far
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut out =
+            Vec::with_capacity(data.len() / 3 * 4 + data.len() / 48 + BEGIN_LINE.len() + 8);
+        self.start(&mut out);
+
+        let mut rest = data;
+        // Complete a group left over from the last call
+        while self.pending_len > 0 && self.pending_len < 3 && !rest.is_empty() {
+            self.pending[self.pending_len] = rest[0];
+            self.pending_len += 1;
+            rest = &rest[1..];
+        }
+        if self.pending_len == 3 {
+            let group = self.pending;
+            self.encode_group(&group, &mut out);
+            self.pending_len = 0;
+        }
+
+        let mut groups = rest.chunks_exact(3);
+        for group in &mut groups {
+            self.encode_group(group.try_into().unwrap(), &mut out);
+        }
+        let tail = groups.remainder();
+        self.pending[..tail.len()].copy_from_slice(tail);
+        self.pending_len += tail.len();
+
+        out
+    }
+
+    /// Flush the last partial group and write the footer
+    pub fn finish(&mut self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(BEGIN_LINE.len() + END_LINE.len() + 8);
+        self.start(&mut out);
+
+        if self.pending_len > 0 {
+            let mut group = [0u8; 3];
+            group[..self.pending_len].copy_from_slice(&self.pending[..self.pending_len]);
+            let n = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+            let mut chars = [b'='; 4];
+            for (i, c) in chars.iter_mut().enumerate().take(self.pending_len + 1) {
+                *c = ALPHABET[(n >> (18 - 6 * i)) as usize & 63];
+            }
+            self.push_chars(&chars, &mut out);
+            self.pending_len = 0;
+        }
+        if self.column > 0 {
+            out.push(b'\n');
+            self.column = 0;
+        }
+        out.extend_from_slice(END_LINE);
+        out.push(b'\n');
+        out
+    }
+}
+
+/// Why a stream failed to dearmor
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The first non-blank line is not the BEGIN line
+    MissingHeader,
+    /// A character outside base64, misplaced padding, or a bad END line
+    InvalidData,
+    /// The stream ended before the END line, or mid-quantum
+    Truncated,
+    /// Something other than whitespace after the END line
+    TrailingData,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Header,
+    Body,
+    Footer,
+    Done,
+}
+
+/// Incremental armor decoder
+pub struct Decoder {
+    stage: Stage,
+    /// Header or footer line collected so far
+    line: Vec<u8>,
+    quad: [u8; 4],
+    quad_len: usize,
+    /// `=` characters in the current quantum
+    padding: usize,
+    /// A padded quantum has been seen; only the footer may follow
+    body_ended: bool,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn decode_char(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+fn strip_cr(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder {
+            stage: Stage::Header,
+            line: Vec::with_capacity(MAX_MARKER_LINE),
+            quad: [0; 4],
+            quad_len: 0,
+            padding: 0,
+            body_ended: false,
+        }
+    }
+
+    /// Collect a marker line; `Some(line)` once it is complete
+    fn collect_line(&mut self, c: u8) -> Result<Option<Vec<u8>>, ()> {
+        if c == b'\n' {
+            return Ok(Some(std::mem::take(&mut self.line)));
+        }
+        if self.line.len() == MAX_MARKER_LINE {
+            return Err(());
+        }
+        self.line.push(c);
+        Ok(None)
+    }
+
+    fn body_char(&mut self, c: u8, out: &mut Vec<u8>) -> Result<(), DecodeError> {
+        let value = if c == b'=' {
+            // Padding can only fill the last one or two places of a quantum
+            if self.quad_len < 2 {
+                return Err(DecodeError::InvalidData);
+            }
+            self.padding += 1;
+            0
+        } else {
+            let value = decode_char(c).ok_or(DecodeError::InvalidData)?;
+            if self.padding > 0 || self.body_ended {
+                return Err(DecodeError::InvalidData);
+            }
+            value
+        };
+
+        self.quad[self.quad_len] = value;
+        self.quad_len += 1;
+        if self.quad_len == 4 {
+            let n = self.quad.iter().fold(0u32, |acc, &v| (acc << 6) | v as u32);
+            let bytes = n.to_be_bytes();
+            out.extend_from_slice(&bytes[1..4 - self.padding]);
+            self.body_ended = self.padding > 0;
+            self.quad_len = 0;
+            self.padding = 0;
+        }
+        Ok(())
+    }
+
+    /// Decode the next piece of armor, returning the bytes recovered so far
+    pub fn update(&mut self, data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        let mut out = Vec::with_capacity(data.len() / 4 * 3 + 3);
+
+        for &c in data {
+            match self.stage {
+                Stage::Header => match self.collect_line(c) {
+                    Ok(Some(line)) => {
+                        let line = strip_cr(&line);
+                        if line == BEGIN_LINE {
+                            self.stage = Stage::Body;
+                        } else if !line.iter().all(u8::is_ascii_whitespace) {
+                            return Err(DecodeError::MissingHeader);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(()) => return Err(DecodeError::MissingHeader),
+                },
+                Stage::Body => match c {
+                    b'\n' | b'\r' | b' ' | b'\t' => {}
+                    b'-' => {
+                        if self.quad_len != 0 {
+                            return Err(DecodeError::Truncated);
+                        }
+                        self.line.push(c);
+                        self.stage = Stage::Footer;
+                    }
+                    _ => self.body_char(c, &mut out)?,
+                },
+                Stage::Footer => match self.collect_line(c) {
+                    Ok(Some(line)) => {
+                        if strip_cr(&line).trim_ascii_end() != END_LINE {
+                            return Err(DecodeError::InvalidData);
+                        }
+                        self.stage = Stage::Done;
+                    }
+                    Ok(None) => {}
+                    Err(()) => return Err(DecodeError::InvalidData),
+                },
+                Stage::Done => {
+                    if !c.is_ascii_whitespace() {
+                        return Err(DecodeError::TrailingData);
+                    }
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Check that the stream ended cleanly after the END line
+    pub fn finish(&mut self) -> Result<(), DecodeError> {
+        // The END line may lack its newline
+        if self.stage == Stage::Footer {
+            let line = std::mem::take(&mut self.line);
+            if strip_cr(&line).trim_ascii_end() != END_LINE {
+                return Err(DecodeError::Truncated);
+            }
+            self.stage = Stage::Done;
+        }
+
+        match self.stage {
+            Stage::Done => Ok(()),
+            Stage::Header
+                if self.line.iter().all(u8::is_ascii_whitespace)
+                    || strip_cr(&self.line) == BEGIN_LINE =>
+            {
+                Err(DecodeError::Truncated)
+            }
+            Stage::Header => Err(DecodeError::MissingHeader),
+            _ => Err(DecodeError::Truncated),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn armor(data: &[u8], piece: usize) -> Vec<u8> {
+        let mut encoder = Encoder::new();
+        let mut out = Vec::new();
+        for chunk in data.chunks(piece.max(1)) {
+            out.extend(encoder.update(chunk));
+        }
+        out.extend(encoder.finish());
+        out
+    }
+
+    fn dearmor(text: &[u8], piece: usize) -> Result<Vec<u8>, DecodeError> {
+        let mut decoder = Decoder::new();
+        let mut out = Vec::new();
+        for chunk in text.chunks(piece.max(1)) {
+            out.extend(decoder.update(chunk)?);
+        }
+        decoder.finish()?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_encode_layout() {
+        let text = armor(b"foobar!", 7);
+        assert_eq!(
+            text,
+            b"-----BEGIN GITFOIL ENCRYPTED DATA-----\n\
+              Zm9vYmFyIQ==\n\
+              -----END GITFOIL ENCRYPTED DATA-----\n"
+        );
+
+        let empty = armor(b"", 1);
+        assert_eq!(
+            empty,
+            b"-----BEGIN GITFOIL ENCRYPTED DATA-----\n\
+              -----END GITFOIL ENCRYPTED DATA-----\n"
+        );
+    }
+
+    #[test]
+    fn test_lines_wrap_at_64() {
+        let data = vec![0xA5u8; 48 * 3 + 1];
+        let text = armor(&data, 1000);
+        let lines: Vec<&[u8]> = text.split(|&c| c == b'\n').collect();
+        // BEGIN, three full lines, one short line, END, trailing empty
+        assert_eq!(lines.len(), 7);
+        for line in &lines[1..4] {
+            assert_eq!(line.len(), 64);
+        }
+        assert_eq!(lines[4], b"pQ==");
+    }
+
+    #[test]
+    fn test_piece_size_does_not_matter() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 + 3) as u8).collect();
+        let reference = armor(&data, data.len());
+        for piece in [1usize, 2, 3, 4, 5, 47, 48, 49, 999] {
+            assert_eq!(armor(&data, piece), reference);
+            assert_eq!(dearmor(&reference, piece).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_all_tail_lengths() {
+        for len in 0..10 {
+            let data: Vec<u8> = (0..len as u8).collect();
+            assert_eq!(dearmor(&armor(&data, 2), 3).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_decoder_tolerates_crlf_and_rewrapping() {
+        let text = b"\r\n-----BEGIN GITFOIL ENCRYPTED DATA-----\r\n\
+                     Zm9v\r\nYmFy IQ==\r\n\
+                     -----END GITFOIL ENCRYPTED DATA-----  \r\n\n";
+        assert_eq!(dearmor(text, 5).unwrap(), b"foobar!");
+
+        let no_final_newline = b"-----BEGIN GITFOIL ENCRYPTED DATA-----\n\
+                                 Zm9v\n\
+                                 -----END GITFOIL ENCRYPTED DATA-----";
+        assert_eq!(dearmor(no_final_newline, 4).unwrap(), b"foo");
+    }
+
+    #[test]
+    fn test_decoder_errors() {
+        let begin = "-----BEGIN GITFOIL ENCRYPTED DATA-----\n";
+        let end = "-----END GITFOIL ENCRYPTED DATA-----\n";
+        let case = |body: &str| format!("{}{}{}", begin, body, end).into_bytes();
+
+        assert_eq!(dearmor(b"hello\n", 3), Err(DecodeError::MissingHeader));
+        assert_eq!(dearmor(b"", 3), Err(DecodeError::Truncated));
+        assert_eq!(dearmor(&case("Zm9*\n"), 3), Err(DecodeError::InvalidData));
+        assert_eq!(
+            dearmor(&case("Zg==Zm9v\n"), 3),
+            Err(DecodeError::InvalidData)
+        );
+        assert_eq!(dearmor(&case("Z===\n"), 3), Err(DecodeError::InvalidData));
+        assert_eq!(dearmor(&case("Zm9\n"), 3), Err(DecodeError::Truncated));
+        assert_eq!(
+            dearmor(format!("{}Zm9v\n", begin).as_bytes(), 3),
+            Err(DecodeError::Truncated)
+        );
+        assert_eq!(
+            dearmor(
+                format!("{}Zm9v\n-----END OTHER-----\n", begin).as_bytes(),
+                3
+            ),
+            Err(DecodeError::InvalidData)
+        );
+        assert_eq!(
+            dearmor(format!("{}{}more", begin, end).as_bytes(), 3),
+            Err(DecodeError::TrailingData)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! ASCII armor NIF for GitFoil
+//!
+//! Streaming armor/dearmor so encrypted output can go through text-only
+//! channels (shell pipelines, email, CI logs) without buffering the whole
+//! stream.
+//!
+//! **Behaviour:**
+//! - `encoder_new/0`, `encode_update/2`, `encode_final/1` turn ciphertext
+//!   into armor piece by piece; concatenating every returned binary gives
+//!   the complete armored text
+//! - `decoder_new/0`, `decode_update/2`, `decode_final/1` do the reverse
+//! - A finished or failed stream can't be reused; calling it again raises
+//!
+//! See `armor` for the format.
+
+mod armor;
+
+use armor::{DecodeError, Decoder, Encoder};
+use rustler::{Atom, Binary, Encoder as _, Env, Error, OwnedBinary, Resource, ResourceArc, Term};
+use std::sync::Mutex;
+
+rustler::init!("Elixir.GitFoil.Native.ArmorNif");
+
+mod atoms {
+    rustler::atoms! {
+        ok,
+        error,
+        missing_header,
+        invalid_data,
+        truncated,
+        trailing_data,
+    }
+}
+
+/// Streaming encoder resource; `None` once finished
+struct EncoderResource {
+    state: Mutex<Option<Encoder>>,
+}
+
+#[rustler::resource_impl]
+impl Resource for EncoderResource {}
+
+/// Streaming decoder resource; `None` once finished or failed
+struct DecoderResource {
+    state: Mutex<Option<Decoder>>,
+}
+
+#[rustler::resource_impl]
+impl Resource for DecoderResource {}
+
+/// Copy bytes into an Elixir binary
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env)
+}
+
+fn error_atom(e: DecodeError) -> Atom {
+    match e {
+        DecodeError::MissingHeader => atoms::missing_header(),
+        DecodeError::InvalidData => atoms::invalid_data(),
+        DecodeError::Truncated => atoms::truncated(),
+        DecodeError::TrailingData => atoms::trailing_data(),
+    }
+}
+
+fn finished() -> Error {
+    Error::RaiseTerm(Box::new("armor stream finished"))
+}
+
+/// Start an armor encoder
+///
+/// ## Returns
+/// - Encoder resource to pass to `encode_update/2` and `encode_final/1`
+#[rustler::nif]
+fn encoder_new() -> ResourceArc<EncoderResource> {
+    ResourceArc::new(EncoderResource {
+        state: Mutex::new(Some(Encoder::new())),
+    })
+}
+
+/// Armor the next piece of input
+///
+/// ## Parameters
+/// - encoder: Resource from `encoder_new/0`
+/// - data: Next piece of ciphertext (any size)
+///
+/// ## Returns
+/// - Ok(armor): Armored text produced so far (may be empty)
+/// - Err: The encoder was already finished
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encode_update<'a>(
+    env: Env<'a>,
+    encoder: ResourceArc<EncoderResource>,
+    data: Binary,
+) -> Result<Binary<'a>, Error> {
+    let mut guard = encoder.state.lock().unwrap();
+    let state = guard.as_mut().ok_or_else(finished)?;
+    Ok(to_binary(env, &state.update(data.as_slice())))
+}
+
+/// Finish an armor stream
+///
+/// ## Parameters
+/// - encoder: Resource from `encoder_new/0`
+///
+/// ## Returns
+/// - Ok(armor): Last base64 line and the END line
+/// - Err: The encoder was already finished
+#[rustler::nif]
+fn encode_final<'a>(
+    env: Env<'a>,
+    encoder: ResourceArc<EncoderResource>,
+) -> Result<Binary<'a>, Error> {
+    let mut state = encoder.state.lock().unwrap().take().ok_or_else(finished)?;
+    Ok(to_binary(env, &state.finish()))
+}
+
+/// Start an armor decoder
+///
+/// ## Returns
+/// - Decoder resource to pass to `decode_update/2` and `decode_final/1`
+#[rustler::nif]
+fn decoder_new() -> ResourceArc<DecoderResource> {
+    ResourceArc::new(DecoderResource {
+        state: Mutex::new(Some(Decoder::new())),
+    })
+}
+
+/// Dearmor the next piece of input
+///
+/// ## Parameters
+/// - decoder: Resource from `decoder_new/0`
+/// - armor: Next piece of armored text (any size, split anywhere)
+///
+/// ## Returns
+/// - {:ok, data}: Bytes recovered so far (may be empty)
+/// - {:error, :missing_header | :invalid_data | :trailing_data | :truncated}:
+///   The stream is malformed; the decoder can't be used again
+/// - Err: The decoder was already finished or failed
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decode_update<'a>(
+    env: Env<'a>,
+    decoder: ResourceArc<DecoderResource>,
+    armor: Binary,
+) -> Result<Result<Binary<'a>, Atom>, Error> {
+    let mut guard = decoder.state.lock().unwrap();
+    let state = guard.as_mut().ok_or_else(finished)?;
+    match state.update(armor.as_slice()) {
+        Ok(data) => Ok(Ok(to_binary(env, &data))),
+        Err(e) => {
+            guard.take();
+            Ok(Err(error_atom(e)))
+        }
+    }
+}
+
+/// Finish a dearmor stream
+///
+/// ## Parameters
+/// - decoder: Resource from `decoder_new/0`
+///
+/// ## Returns
+/// - :ok: The END line was seen and nothing but whitespace followed
+/// - {:error, :missing_header | :truncated}: The stream ended early
+/// - Err: The decoder was already finished or failed
+#[rustler::nif]
+fn decode_final<'a>(
+    env: Env<'a>,
+    decoder: ResourceArc<DecoderResource>,
+) -> Result<Term<'a>, Error> {
+    let mut state = decoder.state.lock().unwrap().take().ok_or_else(finished)?;
+    Ok(match state.finish() {
+        Ok(()) => atoms::ok().encode(env),
+        Err(e) => (atoms::error(), error_atom(e)).encode(env),
+    })
+}
@@ -0,0 +1,143 @@
+//! Ascon-Hash256 and Ascon-XOF128 (NIST SP 800-232)
+//!
+//! Sponge over the Ascon-p[12] permutation with a 64-bit rate. As in the
+//! rest of SP 800-232, words are little-endian: the competition-era
+//! Ascon-Hash/Ascon-Xof (big-endian) give different outputs.
+
+/// Initial values (first state word; the others start at zero)
+const IV_HASH256: u64 = 0x0000_0801_00cc_0002;
+const IV_XOF128: u64 = 0x0000_0800_00cc_0003;
+
+const RATE: usize = 8;
+
+/// Ascon-p[12] round constants
+const ROUND_CONSTANTS: [u64; 12] = [
+    0xf0, 0xe1, 0xd2, 0xc3, 0xb4, 0xa5, 0x96, 0x87, 0x78, 0x69, 0x5a, 0x4b,
+];
+
+/// Ascon-p[12]
+fn permute(s: &mut [u64; 5]) {
+    for &c in ROUND_CONSTANTS.iter() {
+        // Constant addition
+        s[2] ^= c;
+
+        // Substitution layer (bitsliced 5-bit S-box)
+        s[0] ^= s[4];
+        s[4] ^= s[3];
+        s[2] ^= s[1];
+        let t0 = !s[0] & s[1];
+        let t1 = !s[1] & s[2];
+        let t2 = !s[2] & s[3];
+        let t3 = !s[3] & s[4];
+        let t4 = !s[4] & s[0];
+        s[0] ^= t1;
+        s[1] ^= t2;
+        s[2] ^= t3;
+        s[3] ^= t4;
+        s[4] ^= t0;
+        s[1] ^= s[0];
+        s[0] ^= s[4];
+        s[3] ^= s[2];
+        s[2] = !s[2];
+
+        // Linear diffusion layer
+        s[0] ^= s[0].rotate_right(19) ^ s[0].rotate_right(28);
+        s[1] ^= s[1].rotate_right(61) ^ s[1].rotate_right(39);
+        s[2] ^= s[2].rotate_right(1) ^ s[2].rotate_right(6);
+        s[3] ^= s[3].rotate_right(10) ^ s[3].rotate_right(17);
+        s[4] ^= s[4].rotate_right(7) ^ s[4].rotate_right(41);
+    }
+}
+
+/// Load up to 8 bytes as a little-endian word
+fn load(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
+
+/// Absorb `data` (with padding) into a freshly initialized state and
+/// squeeze `out.len()` bytes
+fn sponge(iv: u64, data: &[u8], out: &mut [u8]) {
+    let mut s = [iv, 0, 0, 0, 0];
+    permute(&mut s);
+
+    let mut blocks = data.chunks_exact(RATE);
+    for block in &mut blocks {
+        s[0] ^= load(block);
+        permute(&mut s);
+    }
+    let last = blocks.remainder();
+    s[0] ^= load(last) ^ (1u64 << (8 * last.len()));
+    permute(&mut s);
+
+    let mut chunks = out.chunks_mut(RATE).peekable();
+    while let Some(chunk) = chunks.next() {
+        chunk.copy_from_slice(&s[0].to_le_bytes()[..chunk.len()]);
+        if chunks.peek().is_some() {
+            permute(&mut s);
+        }
+    }
+}
+
+/// Ascon-Hash256 (32-byte digest)
+pub fn hash256(data: &[u8]) -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    sponge(IV_HASH256, data, &mut digest);
+    digest
+}
+
+/// Ascon-XOF128 with `out_len` bytes of output
+pub fn xof128(data: &[u8], out_len: usize) -> Vec<u8> {
+    let mut output = vec![0u8; out_len];
+    sponge(IV_XOF128, data, &mut output);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    /// SP 800-232 KAT, Ascon-Hash256, empty message
+    #[test]
+    fn test_hash256_kat_empty() {
+        assert_eq!(
+            hex(&hash256(b"")),
+            "0B3BE5850F2F6B98CAF29F8FDEA89B64A1FA70AA249B8F839BD53BAA304D92B2"
+        );
+    }
+
+    /// SP 800-232 KAT, Ascon-XOF128, empty message, 32-byte output
+    #[test]
+    fn test_xof128_kat_empty() {
+        assert_eq!(
+            hex(&xof128(b"", 32)),
+            "473D5E6164F58B39DFD84AACDB8AE42EC2D91FED33388EE0D960D9B3993295C6"
+        );
+    }
+
+    #[test]
+    fn test_xof128_prefix_consistent() {
+        let long = xof128(b"derive me", 100);
+        for len in [0usize, 1, 7, 8, 9, 32, 64] {
+            assert_eq!(xof128(b"derive me", len), long[..len]);
+        }
+    }
+
+    #[test]
+    fn test_block_boundaries_are_distinct() {
+        let data = [0x01u8; 17];
+        let mut digests: Vec<[u8; 32]> = (0..=17).map(|len| hash256(&data[..len])).collect();
+        digests.push(hash256(&[
+            0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x00,
+        ]));
+        let count = digests.len();
+        digests.sort();
+        digests.dedup();
+        assert_eq!(digests.len(), count);
+    }
+}
@@ -1,22 +1,61 @@
-//! Ascon-128a NIF for GitFoil
+//! Ascon NIF for GitFoil
 //!
-//! Provides Ascon-128a AEAD encryption/decryption via Rustler NIF.
+//! Provides Ascon AEAD encryption/decryption via Rustler NIF.
 //!
-//! **Algorithm:** Ascon-128a (NIST Lightweight Crypto winner)
-//! - Key size: 128 bits (16 bytes)
+//! **Algorithms:** Ascon (NIST Lightweight Crypto winner)
+//! - Ascon-128a: `encrypt/4`, `decrypt/5` (default; 128-bit rate, 8-round processing)
+//! - Ascon-128: `encrypt_128/4`, `decrypt_128/5` (64-bit rate, 6-round processing)
+//! - Ascon-80pq: `encrypt_80pq/4`, `decrypt_80pq/5` (Ascon-128 with a 160-bit key)
+//! - Ascon-AEAD128 (NIST SP 800-232): `encrypt_aead128/4`, `decrypt_aead128/5`
+//!   (recommended for new repositories)
+//! - Key size: 128 bits (16 bytes); 160 bits (20 bytes) for Ascon-80pq
 //! - Nonce size: 128 bits (16 bytes)
 //! - Tag size: 128 bits (16 bytes)
 //!
+//! **Hashing (NIST SP 800-232):**
+//! - Ascon-Hash256: `hash/1` (32-byte digest)
+//! - Ascon-XOF128: `xof/2` (any output length up to 64 KiB)
+//!
+//! **Streaming (Ascon-128a):** `stream_encrypt_init/3`,
+//! `stream_decrypt_init/3`, `stream_update/2`, `stream_final/1` take input
+//! piece by piece and seal it in 64 KiB segments (see `stream`).
+//!
+//! The variants share sizes but produce different ciphertexts; a blob must
+//! be decrypted with the variant that encrypted it. In particular the
+//! standardized Ascon-AEAD128 is NOT byte-compatible with competition-era
+//! Ascon-128a (different byte order and domain separation), so existing
+//! blobs keep using `decrypt/5`.
+//!
+//! **Self-test:** debug builds and builds with the `verified` feature check
+//! Ascon-AEAD128, Ascon-Hash256 and Ascon-XOF128 against the SP 800-232
+//! KATs and Ascon-128, Ascon-128a and Ascon-80pq against the LWC KATs at
+//! load. On a mismatch they print the failing vector to stderr and refuse
+//! to load. The AEAD vectors are the empty Count = 1 ones, so message and
+//! AD processing is not covered yet.
+//!
 //! **Security:**
 //! - Post-quantum resistant design
 //! - Authenticated encryption with associated data (AEAD)
 //! - Constant-time operations (no timing leaks)
 
-use ascon_aead::{
-    aead::{Aead, KeyInit, Payload},
-    Ascon128a,
-};
-use rustler::{Binary, Env, Error, OwnedBinary};
+use ascon_aead::{Ascon128, Ascon128a, Ascon80pq};
+use ascon_aead128::AsconAead128;
+use nif_support::cipher_nifs::{cipher, open, seal, to_binary, BackendInfo};
+use nif_support::iodata::IoData;
+use rustler::{Binary, Env, Error, Term};
+
+mod ascon_hash;
+pub mod bench;
+mod selftest;
+
+mod atoms {
+    rustler::atoms! {
+        soft,
+    }
+}
+
+/// Largest Ascon-XOF128 output accepted (bytes)
+const MAX_XOF_OUTPUT: usize = 1 << 16;
 
 /// Initialize the NIF module
 #[rustler::nif]
@@ -24,133 +63,249 @@ fn init() -> &'static str {
     "Ascon-128a NIF initialized"
 }
 
-/// Encrypts plaintext using Ascon-128a AEAD
+// encrypt/decrypt, the batch, blob, context and file NIFs, STREAM in all
+// its forms, benchmark/2 and fastest_algorithms/1, all on Ascon-128a
+nif_support::cipher_nifs! {
+    cipher: Ascon128a,
+    name: "Ascon-128a",
+    new: cipher::<Ascon128a>,
+    sizes: { key: 16, nonce: 16, tag: 16, prefix: 11 },
+    subjects: bench::subjects,
+}
+
+/// Encrypts plaintext using Ascon-128 AEAD
+///
+/// The original lower-rate variant, for interop with tooling that
+/// standardized on Ascon-128 rather than Ascon-128a.
 ///
 /// ## Parameters
 /// - key: 16-byte encryption key
 /// - nonce: 16-byte nonce (must be unique per encryption)
 /// - plaintext: Data to encrypt
-/// - aad: Additional authenticated data (file path)
+/// - aad: Additional authenticated data
 ///
 /// ## Returns
 /// - Ok((ciphertext, tag)): Encrypted data + 16-byte authentication tag
 /// - Err: Encryption failed
-#[rustler::nif]
-fn encrypt<'a>(
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_128<'a>(
     env: Env<'a>,
     key: Binary,
     nonce: Binary,
-    plaintext: Binary,
-    aad: Binary,
+    plaintext: IoData,
+    aad: IoData,
 ) -> Result<(Binary<'a>, Binary<'a>), Error> {
-    // Validate input sizes
-    if key.len() != 16 {
-        return Err(Error::BadArg);
-    }
-    if nonce.len() != 16 {
-        return Err(Error::BadArg);
-    }
-
-    // Convert inputs to Ascon types (16-byte arrays)
-    let key_array: &ascon_aead::aead::generic_array::GenericArray<u8, ascon_aead::aead::consts::U16> =
-        ascon_aead::aead::generic_array::GenericArray::from_slice(key.as_slice());
-    let nonce_array: &ascon_aead::aead::generic_array::GenericArray<u8, ascon_aead::aead::consts::U16> =
-        ascon_aead::aead::generic_array::GenericArray::from_slice(nonce.as_slice());
-
-    // Create cipher instance
-    let cipher = Ascon128a::new(key_array);
-
-    // Create payload with AAD
-    let payload = Payload {
-        msg: plaintext.as_slice(),
-        aad: aad.as_slice(),
-    };
-
-    // Encrypt
-    let ciphertext_with_tag = cipher
-        .encrypt(nonce_array, payload)
-        .map_err(|_| Error::RaiseTerm(Box::new("encryption failed")))?;
-
-    // Split ciphertext and tag (last 16 bytes)
-    let tag_start = ciphertext_with_tag.len() - 16;
-    let ciphertext = &ciphertext_with_tag[..tag_start];
-    let tag = &ciphertext_with_tag[tag_start..];
-
-    // Copy to Elixir binaries
-    let mut ciphertext_binary = OwnedBinary::new(ciphertext.len()).unwrap();
-    ciphertext_binary.as_mut_slice().copy_from_slice(ciphertext);
-
-    let mut tag_binary = OwnedBinary::new(16).unwrap();
-    tag_binary.as_mut_slice().copy_from_slice(tag);
-
-    Ok((
-        ciphertext_binary.release(env),
-        tag_binary.release(env),
-    ))
+    let cipher: Ascon128 = cipher(key.as_slice())?;
+    seal(env, &cipher, nonce.as_slice(), &plaintext, &aad.to_cow())
 }
 
-/// Decrypts ciphertext using Ascon-128a AEAD
+/// Decrypts ciphertext using Ascon-128 AEAD
 ///
 /// ## Parameters
 /// - key: 16-byte encryption key
 /// - nonce: 16-byte nonce (same as encryption)
 /// - ciphertext: Encrypted data
 /// - tag: 16-byte authentication tag
-/// - aad: Additional authenticated data (file path, same as encryption)
+/// - aad: Additional authenticated data (same as encryption)
 ///
 /// ## Returns
 /// - Ok(plaintext): Decrypted data (if authentication succeeds)
 /// - Err: Decryption or authentication failed
-#[rustler::nif]
-fn decrypt<'a>(
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_128<'a>(
     env: Env<'a>,
     key: Binary,
     nonce: Binary,
-    ciphertext: Binary,
+    ciphertext: IoData,
     tag: Binary,
-    aad: Binary,
+    aad: IoData,
 ) -> Result<Binary<'a>, Error> {
-    // Validate input sizes
-    if key.len() != 16 {
-        return Err(Error::BadArg);
-    }
-    if nonce.len() != 16 {
-        return Err(Error::BadArg);
-    }
-    if tag.len() != 16 {
-        return Err(Error::BadArg);
-    }
+    let cipher: Ascon128 = cipher(key.as_slice())?;
+    open(
+        env,
+        &cipher,
+        nonce.as_slice(),
+        &ciphertext,
+        tag.as_slice(),
+        &aad.to_cow(),
+    )
+}
 
-    // Convert inputs to Ascon types (16-byte arrays)
-    let key_array: &ascon_aead::aead::generic_array::GenericArray<u8, ascon_aead::aead::consts::U16> =
-        ascon_aead::aead::generic_array::GenericArray::from_slice(key.as_slice());
-    let nonce_array: &ascon_aead::aead::generic_array::GenericArray<u8, ascon_aead::aead::consts::U16> =
-        ascon_aead::aead::generic_array::GenericArray::from_slice(nonce.as_slice());
+/// Encrypts plaintext using Ascon-80pq AEAD
+///
+/// Same construction as Ascon-128 with a 160-bit key, giving extra margin
+/// against key search with Grover's algorithm.
+///
+/// ## Parameters
+/// - key: 20-byte encryption key
+/// - nonce: 16-byte nonce (must be unique per encryption)
+/// - plaintext: Data to encrypt
+/// - aad: Additional authenticated data
+///
+/// ## Returns
+/// - Ok((ciphertext, tag)): Encrypted data + 16-byte authentication tag
+/// - Err: Encryption failed
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_80pq<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    plaintext: IoData,
+    aad: IoData,
+) -> Result<(Binary<'a>, Binary<'a>), Error> {
+    let cipher: Ascon80pq = cipher(key.as_slice())?;
+    seal(env, &cipher, nonce.as_slice(), &plaintext, &aad.to_cow())
+}
 
-    // Reconstruct ciphertext with tag (Ascon library expects them together)
-    let mut ciphertext_with_tag = Vec::with_capacity(ciphertext.len() + 16);
-    ciphertext_with_tag.extend_from_slice(ciphertext.as_slice());
-    ciphertext_with_tag.extend_from_slice(tag.as_slice());
+/// Decrypts ciphertext using Ascon-80pq AEAD
+///
+/// ## Parameters
+/// - key: 20-byte encryption key
+/// - nonce: 16-byte nonce (same as encryption)
+/// - ciphertext: Encrypted data
+/// - tag: 16-byte authentication tag
+/// - aad: Additional authenticated data (same as encryption)
+///
+/// ## Returns
+/// - Ok(plaintext): Decrypted data (if authentication succeeds)
+/// - Err: Decryption or authentication failed
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_80pq<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    ciphertext: IoData,
+    tag: Binary,
+    aad: IoData,
+) -> Result<Binary<'a>, Error> {
+    let cipher: Ascon80pq = cipher(key.as_slice())?;
+    open(
+        env,
+        &cipher,
+        nonce.as_slice(),
+        &ciphertext,
+        tag.as_slice(),
+        &aad.to_cow(),
+    )
+}
+
+/// Encrypts plaintext using NIST SP 800-232 Ascon-AEAD128
+///
+/// ## Parameters
+/// - key: 16-byte encryption key
+/// - nonce: 16-byte nonce (must be unique per encryption)
+/// - plaintext: Data to encrypt
+/// - aad: Additional authenticated data
+///
+/// ## Returns
+/// - Ok((ciphertext, tag)): Encrypted data + 16-byte authentication tag
+/// - Err: Encryption failed
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_aead128<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    plaintext: IoData,
+    aad: IoData,
+) -> Result<(Binary<'a>, Binary<'a>), Error> {
+    let cipher: AsconAead128 = cipher(key.as_slice())?;
+    seal(env, &cipher, nonce.as_slice(), &plaintext, &aad.to_cow())
+}
 
-    // Create cipher instance
-    let cipher = Ascon128a::new(key_array);
+/// Decrypts ciphertext using NIST SP 800-232 Ascon-AEAD128
+///
+/// ## Parameters
+/// - key: 16-byte encryption key
+/// - nonce: 16-byte nonce (same as encryption)
+/// - ciphertext: Encrypted data
+/// - tag: 16-byte authentication tag
+/// - aad: Additional authenticated data (same as encryption)
+///
+/// ## Returns
+/// - Ok(plaintext): Decrypted data (if authentication succeeds)
+/// - Err: Decryption or authentication failed
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_aead128<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    ciphertext: IoData,
+    tag: Binary,
+    aad: IoData,
+) -> Result<Binary<'a>, Error> {
+    let cipher: AsconAead128 = cipher(key.as_slice())?;
+    open(
+        env,
+        &cipher,
+        nonce.as_slice(),
+        &ciphertext,
+        tag.as_slice(),
+        &aad.to_cow(),
+    )
+}
 
-    // Create payload with AAD
-    let payload = Payload {
-        msg: &ciphertext_with_tag,
-        aad: aad.as_slice(),
-    };
+/// Ascon-Hash256
+///
+/// Parameters:
+/// - data: variable length
+///
+/// Returns:
+/// - 32-byte digest
+#[rustler::nif(schedule = "DirtyCpu")]
+fn hash<'a>(env: Env<'a>, data: Binary) -> Binary<'a> {
+    to_binary(env, &ascon_hash::hash256(data.as_slice()))
+}
 
-    // Decrypt and verify
-    let plaintext = cipher
-        .decrypt(nonce_array, payload)
-        .map_err(|_| Error::RaiseTerm(Box::new("authentication failed")))?;
+/// Ascon-XOF128
+///
+/// Parameters:
+/// - data: variable length
+/// - out_len: output length in bytes (1..=65536)
+///
+/// Returns:
+/// - Ok(output) of `out_len` bytes
+/// - Err for an invalid output length
+#[rustler::nif(schedule = "DirtyCpu")]
+fn xof<'a>(env: Env<'a>, data: Binary, out_len: usize) -> Result<Binary<'a>, Error> {
+    if out_len == 0 || out_len > MAX_XOF_OUTPUT {
+        return Err(Error::BadArg);
+    }
+    Ok(to_binary(
+        env,
+        &ascon_hash::xof128(data.as_slice(), out_len),
+    ))
+}
 
-    // Copy to Elixir binary
-    let mut plaintext_binary = OwnedBinary::new(plaintext.len()).unwrap();
-    plaintext_binary.as_mut_slice().copy_from_slice(&plaintext);
+/// Report which hardware path Ascon-128a takes on the running CPU
+///
+/// Ascon is built from 64-bit boolean operations and rotations, which
+/// run at full speed in portable code; there is no hardware path.
+///
+/// ## Returns
+/// - %{backend: :soft, features: []}
+#[rustler::nif]
+fn backend_info() -> BackendInfo {
+    BackendInfo {
+        backend: atoms::soft(),
+        features: vec![],
+    }
+}
 
-    Ok(plaintext_binary.release(env))
+/// Refuse to load if Ascon no longer matches its KATs
+///
+/// The VM only sees a non-zero `load` status, so the vector that no
+/// longer matches goes to stderr.
+fn load(_env: Env, _info: Term) -> bool {
+    if !cfg!(any(debug_assertions, feature = "verified")) {
+        return true;
+    }
+    match selftest::run() {
+        Ok(()) => true,
+        Err(failure) => {
+            eprintln!("ascon_nif: self-test failed: {}", failure);
+            false
+        }
+    }
 }
 
-rustler::init!("Elixir.GitFoil.Native.AsconNif");
+rustler::init!("Elixir.GitFoil.Native.AsconNif", load = load);
@@ -0,0 +1,109 @@
+//! Load-time self-test against NIST SP 800-232
+//!
+//! Checks Ascon-AEAD128 (from the linked ascon-aead crate) and the local
+//! Ascon-Hash256 / Ascon-XOF128 against the final-spec KATs, and the
+//! legacy Ascon v1.2 ciphers still served from ascon-aead 0.4 against the
+//! LWC KATs, so a dependency update that changes their output is caught
+//! before anything is encrypted with it.
+
+use crate::ascon_hash;
+use ascon_aead::{aead as aead_v12, Ascon128, Ascon128a, Ascon80pq};
+use ascon_aead128::{
+    aead::{Aead, KeyInit, Payload},
+    AsconAead128,
+};
+
+/// Ascon-AEAD128 KAT, Count = 1: key and nonce 00..0f, empty message and AD
+const AEAD128_EMPTY_TAG: &str = "4427D64B8E1E1451FC445960F0839BB0";
+
+/// Ascon v1.2 LWC KATs, Count = 1: key 00..0f (00..13 for Ascon-80pq),
+/// nonce 00..0f, empty message and AD
+const ASCON128_EMPTY_TAG: &str = "E355159F292911F794CB1432A0103A8A";
+const ASCON128A_EMPTY_TAG: &str = "7A834E6F09210957067B10FD831F0078";
+const ASCON80PQ_EMPTY_TAG: &str = "ABB688EFA0B9D56B33277A2C97D2146B";
+
+/// Ascon-Hash256 KAT, empty message
+const HASH256_EMPTY: &str = "0B3BE5850F2F6B98CAF29F8FDEA89B64A1FA70AA249B8F839BD53BAA304D92B2";
+
+/// Ascon-XOF128 KAT, empty message, 32-byte output
+const XOF128_EMPTY: &str = "473D5E6164F58B39DFD84AACDB8AE42EC2D91FED33388EE0D960D9B3993295C6";
+
+fn unhex(text: &str) -> Vec<u8> {
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Seal and open the empty Count = 1 vector with an Ascon v1.2 cipher
+fn check_v12<A>(key_len: u8, tag: &str, mismatch: &'static str) -> Result<(), &'static str>
+where
+    A: aead_v12::Aead + aead_v12::KeyInit,
+{
+    let key: Vec<u8> = (0..key_len).collect();
+    let nonce: Vec<u8> = (0..16).collect();
+    let expected = unhex(tag);
+
+    let cipher = A::new_from_slice(&key).map_err(|_| "key rejected")?;
+    let sealed = cipher
+        .encrypt(
+            nonce.as_slice().into(),
+            aead_v12::Payload { msg: b"", aad: b"" },
+        )
+        .map_err(|_| "encryption failed")?;
+    if sealed != expected {
+        return Err(mismatch);
+    }
+    let payload = aead_v12::Payload {
+        msg: &expected,
+        aad: b"",
+    };
+    cipher
+        .decrypt(nonce.as_slice().into(), payload)
+        .map(|_| ())
+        .map_err(|_| mismatch)
+}
+
+/// Run every vector
+pub fn run() -> Result<(), &'static str> {
+    let key: Vec<u8> = (0..16).collect();
+    let nonce: Vec<u8> = (0..16).collect();
+    let expected = unhex(AEAD128_EMPTY_TAG);
+
+    let cipher = AsconAead128::new_from_slice(&key).map_err(|_| "key rejected")?;
+    let sealed = cipher
+        .encrypt(nonce.as_slice().into(), Payload { msg: b"", aad: b"" })
+        .map_err(|_| "encryption failed")?;
+    if sealed != expected {
+        return Err("Ascon-AEAD128 tag mismatch");
+    }
+
+    let payload = Payload {
+        msg: &expected,
+        aad: b"",
+    };
+    if cipher.decrypt(nonce.as_slice().into(), payload).is_err() {
+        return Err("Ascon-AEAD128 vector failed authentication");
+    }
+
+    check_v12::<Ascon128>(16, ASCON128_EMPTY_TAG, "Ascon-128 tag mismatch")?;
+    check_v12::<Ascon128a>(16, ASCON128A_EMPTY_TAG, "Ascon-128a tag mismatch")?;
+    check_v12::<Ascon80pq>(20, ASCON80PQ_EMPTY_TAG, "Ascon-80pq tag mismatch")?;
+
+    if ascon_hash::hash256(b"").to_vec() != unhex(HASH256_EMPTY) {
+        return Err("Ascon-Hash256 digest mismatch");
+    }
+    if ascon_hash::xof128(b"", 32) != unhex(XOF128_EMPTY) {
+        return Err("Ascon-XOF128 output mismatch");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_selftest_passes() {
+        assert_eq!(super::run(), Ok(()));
+    }
+}
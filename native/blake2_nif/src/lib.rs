@@ -0,0 +1,110 @@
+//! BLAKE2b NIF for GitFoil
+//!
+//! Provides BLAKE2b via Rustler NIF for interop with tools that
+//! fingerprint keys and files with BLAKE2 (libsodium's generichash,
+//! b2sum, age/WireGuard-style key ids).
+//!
+//! **Algorithm:** BLAKE2b (RFC 7693)
+//! - Digest: 1..=64 bytes, chosen per call (b2sum uses 64)
+//! - Key: 0..=64 bytes; an empty key means plain, unkeyed BLAKE2b
+//!
+//! The digest length is a BLAKE2 parameter, not a truncation: a 32-byte
+//! digest is not the first half of the 64-byte digest of the same input.
+
+use rustler::{Binary, Env, Error, OwnedBinary};
+
+rustler::init!("Elixir.GitFoil.Native.Blake2Nif");
+
+/// Largest BLAKE2b key and digest (bytes)
+const MAX_KEY_BYTES: usize = 64;
+const MAX_DIGEST_BYTES: usize = 64;
+
+/// BLAKE2b with the given key and digest length; `None` for bad parameters
+fn blake2b(data: &[u8], key: &[u8], out_len: usize) -> Option<blake2b_simd::Hash> {
+    if key.len() > MAX_KEY_BYTES || out_len == 0 || out_len > MAX_DIGEST_BYTES {
+        return None;
+    }
+
+    Some(
+        blake2b_simd::Params::new()
+            .hash_length(out_len)
+            .key(key)
+            .hash(data),
+    )
+}
+
+/// BLAKE2b hash, optionally keyed
+///
+/// ## Parameters
+/// - data: Data to hash
+/// - key: MAC key (0..=64 bytes; empty for an unkeyed hash)
+/// - out_len: Digest length in bytes (1..=64)
+///
+/// ## Returns
+/// - Ok(digest) of `out_len` bytes
+/// - Err: Key too long or digest length out of range
+#[rustler::nif(schedule = "DirtyCpu")]
+fn hash<'a>(env: Env<'a>, data: Binary, key: Binary, out_len: usize) -> Result<Binary<'a>, Error> {
+    let digest = blake2b(data.as_slice(), key.as_slice(), out_len).ok_or(Error::BadArg)?;
+
+    let mut digest_binary = OwnedBinary::new(out_len).unwrap();
+    digest_binary
+        .as_mut_slice()
+        .copy_from_slice(digest.as_bytes());
+    Ok(digest_binary.release(env))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(data: &[u8], key: &[u8], out_len: usize) -> String {
+        blake2b(data, key, out_len)
+            .unwrap()
+            .as_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// RFC 7693 appendix A
+    #[test]
+    fn test_rfc7693_abc() {
+        assert_eq!(
+            hex(b"abc", b"", 64),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+    }
+
+    #[test]
+    fn test_short_digest_is_not_truncation() {
+        assert_eq!(
+            hex(b"abc", b"", 32),
+            "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319"
+        );
+    }
+
+    /// Keyed vectors from the BLAKE2 reference blake2b-kat.txt
+    #[test]
+    fn test_keyed_kat() {
+        let key: Vec<u8> = (0..64).collect();
+        assert_eq!(
+            hex(b"", &key, 64),
+            "10ebb67700b1868efb4417987acf4690ae9d972fb7a590c2f02871799aaa4786\
+             b5e996e8f0f4eb981fc214b005f42d2ff4233499391653df7aefcbc13fc51568"
+        );
+        assert_eq!(
+            hex(&[0, 1, 2], &key, 64),
+            "33d0825dddf7ada99b0e7e307104ad07ca9cfd9692214f1561356315e784f3e5\
+             a17e364ae9dbb14cb2036df932b77f4b292761365fb328de7afdc6d8998f5fc1"
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_parameters() {
+        assert!(blake2b(b"", b"", 0).is_none());
+        assert!(blake2b(b"", b"", 65).is_none());
+        assert!(blake2b(b"", &[0u8; 65], 32).is_none());
+    }
+}
@@ -0,0 +1,93 @@
+//! BLAKE3 NIF for GitFoil
+//!
+//! Provides BLAKE3 in its three modes via Rustler NIF: plain hashing for
+//! fingerprints, keyed hashing as a fast MAC/PRF, and key derivation bound
+//! to a context string.
+//!
+//! **Algorithm:** BLAKE3
+//! - Digest: 256 bits (32 bytes)
+//! - Key (keyed_hash): 256 bits (32 bytes)
+//! - Context (derive_key): UTF-8 string, hardcoded and globally unique per
+//!   use, e.g. "GitFoil 2025-01-01 per-file key"
+//!
+//! **Scheduling:** `hash/1` and `keyed_hash/2` run on dirty CPU schedulers
+//! and hash inputs of 128 KiB and up across the rayon thread pool, so
+//! manifests over large binary assets use every core without blocking
+//! normal schedulers.
+
+use rustler::{Binary, Env, Error, OwnedBinary};
+
+rustler::init!("Elixir.GitFoil.Native.Blake3Nif");
+
+/// Inputs at least this large are hashed with rayon (bytes)
+///
+/// Below this the thread-pool handoff costs more than it saves.
+const PARALLEL_THRESHOLD: usize = 128 * 1024;
+
+/// Hash `data` with `hasher`, in parallel when it is large enough to pay off
+fn hash_with(mut hasher: blake3::Hasher, data: &[u8]) -> blake3::Hash {
+    if data.len() >= PARALLEL_THRESHOLD {
+        hasher.update_rayon(data);
+    } else {
+        hasher.update(data);
+    }
+    hasher.finalize()
+}
+
+/// Copy a digest into an Elixir binary
+fn digest_binary<'a>(env: Env<'a>, digest: &[u8]) -> Binary<'a> {
+    let mut digest_binary = OwnedBinary::new(digest.len()).unwrap();
+    digest_binary.as_mut_slice().copy_from_slice(digest);
+    digest_binary.release(env)
+}
+
+/// BLAKE3 hash
+///
+/// ## Parameters
+/// - data: Data to hash
+///
+/// ## Returns
+/// - 32-byte digest
+#[rustler::nif(schedule = "DirtyCpu")]
+fn hash<'a>(env: Env<'a>, data: Binary) -> Binary<'a> {
+    let digest = hash_with(blake3::Hasher::new(), data.as_slice());
+    digest_binary(env, digest.as_bytes())
+}
+
+/// BLAKE3 keyed hash
+///
+/// ## Parameters
+/// - key: 32 bytes
+/// - data: Data to hash
+///
+/// ## Returns
+/// - 32-byte digest
+/// - Err if the key is not 32 bytes
+#[rustler::nif(schedule = "DirtyCpu")]
+fn keyed_hash<'a>(env: Env<'a>, key: Binary, data: Binary) -> Result<Binary<'a>, Error> {
+    let key_array: &[u8; 32] = key.as_slice().try_into()
+        .map_err(|_| Error::BadArg)?;
+
+    let digest = hash_with(blake3::Hasher::new_keyed(key_array), data.as_slice());
+    Ok(digest_binary(env, digest.as_bytes()))
+}
+
+/// BLAKE3 key derivation
+///
+/// ## Parameters
+/// - context: UTF-8 context string identifying the purpose of the key
+/// - key_material: Input keying material
+///
+/// ## Returns
+/// - 32-byte derived key
+/// - Err if the context is not valid UTF-8
+#[rustler::nif]
+fn derive_key<'a>(env: Env<'a>, context: Binary, key_material: Binary) -> Result<Binary<'a>, Error> {
+    let context = std::str::from_utf8(context.as_slice())
+        .map_err(|_| Error::BadArg)?;
+
+    let mut derived = blake3::derive_key(context, key_material.as_slice());
+    let derived_binary = digest_binary(env, &derived);
+    derived.fill(0);
+    Ok(derived_binary)
+}
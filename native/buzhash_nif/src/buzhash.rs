@@ -0,0 +1,249 @@
+//! Buzhash rolling hash
+//!
+//! Cyclic-polynomial rolling hash (Uzgalis' Buzhash) over a fixed-size
+//! byte window. Rolling the window forward by one byte costs one table
+//! lookup per byte entering and leaving:
+//!
+//!   H' = rotl(H, 1) ^ rotl(T[out], window) ^ T[in]
+//!
+//! The substitution table is generated at compile time from a fixed seed,
+//! so hashes are stable across builds and platforms.
+
+/// Seed for the substitution table (first 64 bits of the fractional part of pi)
+const TABLE_SEED: u64 = 0x243F6A8885A308D3;
+
+/// Substitution table mapping each byte value to a pseudo-random 32-bit word
+const TABLE: [u32; 256] = build_table(TABLE_SEED);
+
+/// SplitMix64 step, used only to fill the substitution table
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    (state, z ^ (z >> 31))
+}
+
+const fn build_table(seed: u64) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut state = seed;
+    let mut i = 0;
+    while i < 256 {
+        let (next, out) = splitmix64(state);
+        state = next;
+        table[i] = (out >> 32) as u32;
+        i += 1;
+    }
+    table
+}
+
+/// Hash a complete window in one pass
+///
+/// Equal to the value a `Buzhash` with `window == data.len()` reports
+/// after rolling over `data`.
+pub fn hash(data: &[u8]) -> u32 {
+    data.iter()
+        .fold(0u32, |h, &b| h.rotate_left(1) ^ TABLE[b as usize])
+}
+
+/// Incremental rolling hash state
+pub struct Buzhash {
+    window: Vec<u8>,
+    pos: usize,
+    filled: usize,
+    hash: u32,
+}
+
+impl Buzhash {
+    /// Create a rolling hash over a window of `window_size` bytes
+    ///
+    /// Panics if `window_size` is zero.
+    pub fn new(window_size: usize) -> Self {
+        assert!(window_size > 0, "window size must be positive");
+        Buzhash {
+            window: vec![0u8; window_size],
+            pos: 0,
+            filled: 0,
+            hash: 0,
+        }
+    }
+
+    /// True once a full window of bytes has been rolled in
+    pub fn is_full(&self) -> bool {
+        self.filled == self.window.len()
+    }
+
+    /// Current hash of the bytes in the window
+    pub fn value(&self) -> u32 {
+        self.hash
+    }
+
+    /// Roll one byte into the window, dropping the oldest once full
+    #[inline]
+    pub fn roll(&mut self, byte: u8) -> u32 {
+        let size = self.window.len();
+        self.hash = self.hash.rotate_left(1) ^ TABLE[byte as usize];
+
+        if self.filled == size {
+            let out = self.window[self.pos];
+            self.hash ^= TABLE[out as usize].rotate_left((size % 32) as u32);
+        } else {
+            self.filled += 1;
+        }
+
+        self.window[self.pos] = byte;
+        self.pos = (self.pos + 1) % size;
+        self.hash
+    }
+
+    /// Roll every byte of `data` into the window
+    pub fn update(&mut self, data: &[u8]) -> u32 {
+        for &b in data {
+            self.roll(b);
+        }
+        self.hash
+    }
+
+    /// Roll `data` in and collect the offsets just past every byte where
+    /// the window is full and `hash & mask == mask`
+    ///
+    /// Offsets are relative to the start of `data`.
+    pub fn boundaries(&mut self, data: &[u8], mask: u32) -> Vec<usize> {
+        let mut offsets = Vec::new();
+        for (i, &b) in data.iter().enumerate() {
+            let h = self.roll(b);
+            if self.is_full() && h & mask == mask {
+                offsets.push(i + 1);
+            }
+        }
+        offsets
+    }
+
+    /// Forget all bytes rolled in so far
+    pub fn reset(&mut self) {
+        self.window.fill(0);
+        self.pos = 0;
+        self.filled = 0;
+        self.hash = 0;
+    }
+
+    /// Overwrite the window with zeros in a way the optimizer cannot elide
+    ///
+    /// The window holds the last `window_size` bytes of (usually plaintext)
+    /// input, so it is scrubbed before the memory is released.
+    pub fn wipe(&mut self) {
+        for b in self.window.iter_mut() {
+            // SAFETY: `b` is a valid, aligned reference into the window
+            unsafe { std::ptr::write_volatile(b, 0) };
+        }
+        std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+        self.reset();
+    }
+}
+
+impl Drop for Buzhash {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 131 + 7) as u8).collect()
+    }
+
+    #[test]
+    fn test_table_is_well_spread() {
+        // All 256 entries distinct and a balanced bit count overall
+        let mut sorted = TABLE.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 256);
+
+        let ones: u32 = TABLE.iter().map(|w| w.count_ones()).sum();
+        assert!((3800..=4400).contains(&ones), "unbalanced table: {} ones", ones);
+    }
+
+    #[test]
+    fn test_rolling_matches_direct_hash() {
+        let data = sample(1000);
+        for window in [1usize, 16, 32, 48, 64] {
+            let mut rolling = Buzhash::new(window);
+            for end in 1..=data.len() {
+                let h = rolling.roll(data[end - 1]);
+                let start = end.saturating_sub(window);
+                assert_eq!(h, hash(&data[start..end]), "window {} end {}", window, end);
+            }
+        }
+    }
+
+    #[test]
+    fn test_update_equals_byte_rolls() {
+        let data = sample(300);
+        let mut a = Buzhash::new(48);
+        let mut b = Buzhash::new(48);
+
+        a.update(&data[..100]);
+        a.update(&data[100..]);
+        for &byte in &data {
+            b.roll(byte);
+        }
+
+        assert_eq!(a.value(), b.value());
+    }
+
+    #[test]
+    fn test_boundaries_are_content_defined() {
+        // Shifting the input shifts the boundaries by the same amount
+        let data = sample(20000);
+        let mut shifted = vec![0xAAu8; 123];
+        shifted.extend_from_slice(&data);
+
+        let mask = (1 << 6) - 1;
+        let a = Buzhash::new(32).boundaries(&data, mask);
+        let b = Buzhash::new(32).boundaries(&shifted, mask);
+
+        assert!(!a.is_empty());
+        let b_tail: Vec<usize> = b.iter().filter(|&&o| o >= 123 + 32).map(|o| o - 123).collect();
+        let a_tail: Vec<usize> = a.iter().copied().filter(|&o| o >= 32).collect();
+        assert_eq!(a_tail, b_tail);
+    }
+
+    #[test]
+    fn test_boundaries_across_calls() {
+        let data = sample(5000);
+        let mask = (1 << 5) - 1;
+
+        let whole = Buzhash::new(32).boundaries(&data, mask);
+
+        let mut split = Buzhash::new(32);
+        let mut offsets = split.boundaries(&data[..1234], mask);
+        offsets.extend(split.boundaries(&data[1234..], mask).iter().map(|o| o + 1234));
+
+        assert_eq!(whole, offsets);
+    }
+
+    #[test]
+    fn test_wipe_clears_window() {
+        let mut rolling = Buzhash::new(16);
+        rolling.update(b"secret plaintext bytes");
+        rolling.wipe();
+
+        assert!(rolling.window.iter().all(|&b| b == 0));
+        assert_eq!(rolling.value(), 0);
+        assert!(!rolling.is_full());
+    }
+
+    #[test]
+    fn test_reset() {
+        let data = sample(100);
+        let mut rolling = Buzhash::new(16);
+        let first = rolling.update(&data);
+        rolling.reset();
+        assert!(!rolling.is_full());
+        assert_eq!(rolling.update(&data), first);
+    }
+}
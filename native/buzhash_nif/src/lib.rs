@@ -0,0 +1,147 @@
+//! Buzhash rolling-hash NIF for GitFoil
+//!
+//! Exposes an incremental rolling hash for experimenting with chunk-boundary
+//! strategies and rsync-style delta detection on top of the chunked format.
+//! This is a low-level building block; it makes no chunking decisions itself.
+//!
+//! **Algorithm:** Buzhash (cyclic polynomial)
+//! - Output: 32 bits
+//! - Window: 1..=65536 bytes, chosen per hasher
+//!
+//! **Security:**
+//! - NOT a cryptographic hash; trivially forgeable
+//! - Boundary positions leak information about plaintext; only use on data
+//!   that is encrypted afterwards or already public
+
+mod buzhash;
+
+use buzhash::Buzhash;
+use rustler::{Atom, Binary, Error, Resource, ResourceArc};
+use std::sync::Mutex;
+
+rustler::init!("Elixir.GitFoil.Native.BuzhashNif");
+
+mod atoms {
+    rustler::atoms! {
+        ok,
+    }
+}
+
+const MAX_WINDOW: usize = 65536;
+
+/// Rolling hash resource
+///
+/// The hasher is dropped (and its window scrubbed) by `wipe/1`, after
+/// which the resource can no longer be used.
+struct HasherResource {
+    hasher: Mutex<Option<Buzhash>>,
+}
+
+#[rustler::resource_impl]
+impl Resource for HasherResource {}
+
+impl HasherResource {
+    fn with<R>(&self, f: impl FnOnce(&mut Buzhash) -> R) -> Result<R, Error> {
+        let mut guard = self.hasher.lock().unwrap();
+        let hasher = guard
+            .as_mut()
+            .ok_or_else(|| Error::RaiseTerm(Box::new("hasher wiped")))?;
+        Ok(f(hasher))
+    }
+}
+
+/// Create a rolling hasher
+///
+/// ## Parameters
+/// - window_size: Window length in bytes (1..=65536)
+///
+/// ## Returns
+/// - Ok(hasher): Rolling hash resource
+/// - Err: Invalid window size
+#[rustler::nif]
+fn new(window_size: usize) -> Result<ResourceArc<HasherResource>, Error> {
+    if window_size == 0 || window_size > MAX_WINDOW {
+        return Err(Error::BadArg);
+    }
+
+    Ok(ResourceArc::new(HasherResource {
+        hasher: Mutex::new(Some(Buzhash::new(window_size))),
+    }))
+}
+
+/// Roll data into the window
+///
+/// ## Parameters
+/// - hasher: Resource from `new/1`
+/// - data: Bytes to roll in
+///
+/// ## Returns
+/// - Hash of the current window (the last `window_size` bytes seen)
+#[rustler::nif(schedule = "DirtyCpu")]
+fn update(hasher: ResourceArc<HasherResource>, data: Binary) -> Result<u32, Error> {
+    hasher.with(|h| h.update(data.as_slice()))
+}
+
+/// Roll data into the window and report candidate chunk boundaries
+///
+/// A boundary is reported after every byte where the window is full and
+/// `hash &&& mask == mask`. State carries over between calls, so a stream
+/// can be fed in pieces.
+///
+/// ## Parameters
+/// - hasher: Resource from `new/1`
+/// - data: Bytes to roll in
+/// - mask: Boundary mask (e.g. `2^13 - 1` for ~8 KiB average spacing)
+///
+/// ## Returns
+/// - List of offsets into `data` just past each boundary
+#[rustler::nif(schedule = "DirtyCpu")]
+fn boundaries(
+    hasher: ResourceArc<HasherResource>,
+    data: Binary,
+    mask: u32,
+) -> Result<Vec<usize>, Error> {
+    hasher.with(|h| h.boundaries(data.as_slice(), mask))
+}
+
+/// Current hash value without rolling anything in
+#[rustler::nif]
+fn value(hasher: ResourceArc<HasherResource>) -> Result<u32, Error> {
+    hasher.with(|h| h.value())
+}
+
+/// Clear the window so the hasher can be reused
+#[rustler::nif]
+fn reset(hasher: ResourceArc<HasherResource>) -> Result<ResourceArc<HasherResource>, Error> {
+    hasher.with(|h| h.reset())?;
+    Ok(hasher)
+}
+
+/// Scrub and invalidate a hasher immediately
+///
+/// Any later call on the resource raises.
+///
+/// ## Returns
+/// - :ok (also for an already wiped hasher)
+#[rustler::nif]
+fn wipe(hasher: ResourceArc<HasherResource>) -> Atom {
+    // Dropping the hasher scrubs its window
+    hasher.hasher.lock().unwrap().take();
+    atoms::ok()
+}
+
+/// Hash a whole block as one window
+///
+/// Equals the rolling value of a hasher whose window is exactly
+/// `byte_size(data)` after rolling over `data`, so block signatures can be
+/// matched against a rolling scan of another file.
+///
+/// ## Parameters
+/// - data: Block to hash
+///
+/// ## Returns
+/// - 32-bit hash
+#[rustler::nif(schedule = "DirtyCpu")]
+fn hash(data: Binary) -> u32 {
+    buzhash::hash(data.as_slice())
+}
@@ -0,0 +1,6 @@
+//! Seal throughput of ChaCha20-Poly1305 across message sizes
+//!
+//! Run with `cargo bench`; `benchmark/2` measures the same subjects from
+//! Elixir.
+
+nif_support::seal_bench!(chacha20poly1305_nif::bench::subjects);
@@ -0,0 +1,46 @@
+//! Benchmark subjects for `benchmark/2` and the criterion benches
+//!
+//! ChaCha20-Poly1305, the only AEAD the NIF exposes, is listed here under
+//! a fixed all-zero key and nonce; only the speed matters, not the
+//! ciphertext.
+//! The measuring itself is `nif_support::bench`.
+
+use chacha20poly1305::ChaCha20Poly1305;
+use nif_support::bench::{aead, Subject};
+
+/// Every AEAD in this library
+pub fn subjects() -> Vec<Subject> {
+    vec![aead::<ChaCha20Poly1305>("chacha20_poly1305", 256)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nif_support::bench::{fastest, throughput};
+    use std::time::Duration;
+
+    #[test]
+    fn test_every_subject_measures() {
+        for subject in subjects() {
+            let mbps = throughput(&subject, 1000, Duration::from_millis(1));
+            assert!(mbps > 0.0, "{} measured {}", subject.name, mbps);
+        }
+    }
+
+    #[test]
+    fn test_seal_encrypts_in_place() {
+        let subject = &subjects()[0];
+        let mut buffer = vec![0u8; 100];
+        subject.seal(&mut buffer);
+        assert!(buffer.iter().any(|&byte| byte != 0));
+    }
+
+    #[test]
+    fn test_fastest_filters_and_ranks() {
+        let ranked = fastest(subjects, 256);
+        assert!(!ranked.is_empty());
+        assert!(ranked.iter().all(|measurement| measurement.security >= 256));
+        assert!(ranked.windows(2).all(|pair| pair[0].mbps >= pair[1].mbps));
+        assert!(fastest(subjects, 512).is_empty());
+    }
+}
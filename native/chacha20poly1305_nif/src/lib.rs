@@ -1,144 +1,102 @@
-use rustler::{Env, Binary, Error, OwnedBinary};
+use chacha20poly1305::ChaCha20Poly1305;
+use nif_support::cipher_nifs::{cipher, BackendInfo};
+use rustler::{Binary, Env, Error, OwnedBinary, Term};
 
-rustler::init!("Elixir.GitFoil.Native.ChaCha20Poly1305Nif");
+pub mod bench;
+mod selftest;
 
-/// ChaCha20-Poly1305 Encryption (IETF variant)
+rustler::init!("Elixir.GitFoil.Native.ChaCha20Poly1305Nif", load = load);
+
+mod atoms {
+    rustler::atoms! {
+        sse2,
+        avx2,
+        soft,
+    }
+}
+
+/// Refuse to load if the cipher no longer matches RFC 8439
+///
+/// The self-test runs in debug builds and with the `verified` feature.
+/// The VM reports a failure as a non-zero `load` status; the `selftest`
+/// unit test names the vector that no longer matches.
+fn load(_env: Env, _info: Term) -> bool {
+    !cfg!(any(debug_assertions, feature = "verified")) || selftest::run().is_ok()
+}
+
+// encrypt/decrypt, the batch, blob, context and file NIFs, STREAM in all
+// its forms, benchmark/2 and fastest_algorithms/1
+nif_support::cipher_nifs! {
+    cipher: ChaCha20Poly1305,
+    name: "ChaCha20-Poly1305",
+    new: cipher::<ChaCha20Poly1305>,
+    sizes: { key: 32, nonce: 12, tag: 16, prefix: 7 },
+    subjects: bench::subjects,
+}
+
+/// HChaCha20 subkey derivation
+///
+/// Derives a subkey from a master key and a 16-byte input, as used for
+/// XChaCha20's nonce extension (draft-irtf-cfrg-xchacha section 2.2).
+/// Pair the subkey with the remaining nonce bytes in `encrypt/4` to
+/// build an extended-nonce scheme.
 ///
 /// Parameters:
 /// - key: 32 bytes (256 bits)
-/// - nonce: 12 bytes (96 bits) - IETF standard
-/// - plaintext: variable length
-/// - aad: variable length (additional authenticated data)
+/// - context: 16 bytes, e.g. the first part of a 24-byte nonce
 ///
 /// Returns:
-/// - Ok({ciphertext, tag}) where tag is 16 bytes (128 bits)
+/// - Ok(subkey) where subkey is 32 bytes
 /// - Err for invalid parameters
 #[rustler::nif]
-fn encrypt<'a>(
-    env: Env<'a>,
-    key: Binary,
-    nonce: Binary,
-    plaintext: Binary,
-    aad: Binary,
-) -> Result<(Binary<'a>, Binary<'a>), Error> {
-    use chacha20poly1305::{
-        aead::{Aead, KeyInit, Payload},
-        ChaCha20Poly1305,
-    };
-
-    // Validate key length (32 bytes = 256 bits)
-    if key.len() != 32 {
-        return Err(Error::BadArg);
-    }
-
-    // Validate nonce length (12 bytes = 96 bits for IETF variant)
-    if nonce.len() != 12 {
-        return Err(Error::BadArg);
-    }
-
-    // Convert to fixed-size arrays
-    let key_array: &[u8; 32] = key.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
-    let nonce_array: &[u8; 12] = nonce.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
+fn hchacha20<'a>(env: Env<'a>, key: Binary, context: Binary) -> Result<Binary<'a>, Error> {
+    use chacha20::{hchacha, R20};
 
-    // Create cipher instance
-    let cipher = ChaCha20Poly1305::new(key_array.into());
+    let key_array: &[u8; 32] = key.as_slice().try_into().map_err(|_| Error::BadArg)?;
+    let context_array: &[u8; 16] = context.as_slice().try_into().map_err(|_| Error::BadArg)?;
 
-    // Prepare payload with AAD
-    let payload = Payload {
-        msg: plaintext.as_slice(),
-        aad: aad.as_slice(),
-    };
+    let mut subkey = hchacha::<R20>(key_array.into(), context_array.into());
 
-    // Encrypt (returns ciphertext with tag appended)
-    let ciphertext_with_tag = cipher
-        .encrypt(nonce_array.into(), payload)
-        .map_err(|_| Error::RaiseTerm(Box::new("encryption failed")))?;
+    let mut subkey_binary = OwnedBinary::new(32).unwrap();
+    subkey_binary.as_mut_slice().copy_from_slice(&subkey);
+    subkey.fill(0);
 
-    // Split ciphertext and tag (tag is last 16 bytes)
-    let tag_start = ciphertext_with_tag.len().saturating_sub(16);
-    let ciphertext = &ciphertext_with_tag[..tag_start];
-    let tag = &ciphertext_with_tag[tag_start..];
-
-    // Copy to Elixir binaries
-    let mut ciphertext_binary = OwnedBinary::new(ciphertext.len()).unwrap();
-    ciphertext_binary.as_mut_slice().copy_from_slice(ciphertext);
-
-    let mut tag_binary = OwnedBinary::new(16).unwrap();
-    tag_binary.as_mut_slice().copy_from_slice(tag);
-
-    Ok((
-        ciphertext_binary.release(env),
-        tag_binary.release(env),
-    ))
+    Ok(subkey_binary.release(env))
 }
 
-/// ChaCha20-Poly1305 Decryption (IETF variant)
+/// Report which hardware path ChaCha20-Poly1305 takes on the running CPU
 ///
-/// Parameters:
-/// - key: 32 bytes (256 bits)
-/// - nonce: 12 bytes (96 bits) - IETF standard
-/// - ciphertext: variable length
-/// - tag: 16 bytes (128 bits) - authentication tag
-/// - aad: variable length (additional authenticated data)
+/// On x86-64 the `chacha20` and `poly1305` crates use AVX2 when the CPU
+/// has it and SSE2 otherwise (ChaCha20 only; Poly1305 falls back to
+/// portable code). Elsewhere both run in portable code: `chacha20` 0.9
+/// only uses NEON when built with `--cfg chacha20_force_neon`.
 ///
 /// Returns:
-/// - Ok(plaintext)
-/// - Err if authentication fails or parameters invalid
+/// - %{backend: atom, features: [atom]} where backend is :avx2, :sse2 or
+///   :soft, and features lists the CPU features it uses
 #[rustler::nif]
-fn decrypt<'a>(
-    env: Env<'a>,
-    key: Binary,
-    nonce: Binary,
-    ciphertext: Binary,
-    tag: Binary,
-    aad: Binary,
-) -> Result<Binary<'a>, Error> {
-    use chacha20poly1305::{
-        aead::{Aead, KeyInit, Payload},
-        ChaCha20Poly1305,
-    };
-
-    // Validate input sizes
-    if key.len() != 32 {
-        return Err(Error::BadArg);
-    }
-    if nonce.len() != 12 {
-        return Err(Error::BadArg);
-    }
-    if tag.len() != 16 {
-        return Err(Error::BadArg);
+fn backend_info() -> BackendInfo {
+    // SSE2 is part of the x86-64 baseline
+    #[cfg(target_arch = "x86_64")]
+    let (sse2, avx2) = (true, std::is_x86_feature_detected!("avx2"));
+
+    #[cfg(not(target_arch = "x86_64"))]
+    let (sse2, avx2) = (false, false);
+
+    if avx2 {
+        BackendInfo {
+            backend: atoms::avx2(),
+            features: vec![atoms::sse2(), atoms::avx2()],
+        }
+    } else if sse2 {
+        BackendInfo {
+            backend: atoms::sse2(),
+            features: vec![atoms::sse2()],
+        }
+    } else {
+        BackendInfo {
+            backend: atoms::soft(),
+            features: vec![],
+        }
     }
-
-    // Convert to fixed-size arrays
-    let key_array: &[u8; 32] = key.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
-    let nonce_array: &[u8; 12] = nonce.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
-
-    // Create cipher instance
-    let cipher = ChaCha20Poly1305::new(key_array.into());
-
-    // Combine ciphertext and tag (ChaCha20Poly1305 expects them together)
-    let mut ciphertext_with_tag = Vec::with_capacity(ciphertext.len() + 16);
-    ciphertext_with_tag.extend_from_slice(ciphertext.as_slice());
-    ciphertext_with_tag.extend_from_slice(tag.as_slice());
-
-    // Prepare payload with AAD
-    let payload = Payload {
-        msg: &ciphertext_with_tag,
-        aad: aad.as_slice(),
-    };
-
-    // Decrypt and verify
-    let plaintext = cipher
-        .decrypt(nonce_array.into(), payload)
-        .map_err(|_| Error::RaiseTerm(Box::new("authentication failed")))?;
-
-    // Copy to Elixir binary
-    let mut plaintext_binary = OwnedBinary::new(plaintext.len()).unwrap();
-    plaintext_binary.as_mut_slice().copy_from_slice(&plaintext);
-
-    Ok(plaintext_binary.release(env))
 }
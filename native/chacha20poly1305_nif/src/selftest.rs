@@ -0,0 +1,102 @@
+//! Load-time self-test against RFC 8439
+//!
+//! Checks the linked chacha20poly1305 crate against the AEAD test vector
+//! in RFC 8439 section 2.8.2, so a dependency update that changes its
+//! output is caught before anything is encrypted with it. HChaCha20 is
+//! checked against draft-irtf-cfrg-xchacha section 2.2.1.
+
+use chacha20::{hchacha, R20};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305,
+};
+
+const PLAINTEXT: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+
+const CIPHERTEXT_AND_TAG: &str = "\
+d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc\
+3ff4def08e4b7a9de576d26586cec64b6116\
+1ae10b594f09e26a7e902ecbd0600691";
+
+fn unhex(text: &str) -> Vec<u8> {
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Run the RFC 8439 vector through encrypt and decrypt, then HChaCha20
+pub fn run() -> Result<(), &'static str> {
+    aead()?;
+    hchacha20()
+}
+
+fn aead() -> Result<(), &'static str> {
+    let key: Vec<u8> = (0x80..=0x9f).collect();
+    let nonce = unhex("070000004041424344454647");
+    let aad = unhex("50515253c0c1c2c3c4c5c6c7");
+    let expected = unhex(CIPHERTEXT_AND_TAG);
+
+    let cipher = ChaCha20Poly1305::new_from_slice(&key).map_err(|_| "key rejected")?;
+    let sealed = cipher
+        .encrypt(
+            nonce.as_slice().into(),
+            Payload {
+                msg: PLAINTEXT,
+                aad: &aad,
+            },
+        )
+        .map_err(|_| "encryption failed")?;
+    if sealed != expected {
+        return Err("RFC 8439 ciphertext mismatch");
+    }
+
+    let opened = cipher
+        .decrypt(
+            nonce.as_slice().into(),
+            Payload {
+                msg: &expected,
+                aad: &aad,
+            },
+        )
+        .map_err(|_| "RFC 8439 vector failed authentication")?;
+    if opened != PLAINTEXT {
+        return Err("RFC 8439 plaintext mismatch");
+    }
+
+    let mut forged = expected;
+    let last = forged.len() - 1;
+    forged[last] ^= 1;
+    let payload = Payload {
+        msg: &forged,
+        aad: &aad,
+    };
+    if cipher.decrypt(nonce.as_slice().into(), payload).is_ok() {
+        return Err("forged tag accepted");
+    }
+
+    Ok(())
+}
+
+fn hchacha20() -> Result<(), &'static str> {
+    let key: Vec<u8> = (0x00..=0x1f).collect();
+    let input = unhex("000000090000004a0000000031415927");
+    let expected = unhex("82413b4227b27bfed30e42508a877d73a0f9e4d58a74a853c12ec41326d3ecdc");
+
+    let subkey = hchacha::<R20>(key.as_slice().into(), input.as_slice().into());
+    if subkey.as_slice() != expected.as_slice() {
+        return Err("HChaCha20 subkey mismatch");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_selftest_passes() {
+        assert_eq!(super::run(), Ok(()));
+    }
+}
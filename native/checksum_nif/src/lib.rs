@@ -0,0 +1,71 @@
+//! Non-cryptographic checksum NIF for GitFoil
+//!
+//! Provides fast checksums for change detection and cache keys in the
+//! repo-scanning and chunking code, where a cryptographic hash per file
+//! would dominate runtime.
+//!
+//! **Algorithms:**
+//! - xxh3 (XXH3-64): 64-bit output, seed 0
+//! - XXH3-128: 128-bit output, seed 0; use it where a 64-bit value could
+//!   collide by chance (e.g. change detection over millions of files)
+//! - CRC32C (Castagnoli): 32-bit output, SSE4.2/ARMv8 CRC instructions when available
+//!
+//! **Security:**
+//! - NOT collision resistant against an adversary
+//! - Never use these for integrity of untrusted data; use the AEAD tags or SHA-2
+
+use rustler::Binary;
+
+rustler::init!("Elixir.GitFoil.Native.ChecksumNif");
+
+/// XXH3-64 checksum
+///
+/// ## Parameters
+/// - data: Data to checksum
+///
+/// ## Returns
+/// - 64-bit unsigned integer
+#[rustler::nif(schedule = "DirtyCpu")]
+fn xxh3_64(data: Binary) -> u64 {
+    xxhash_rust::xxh3::xxh3_64(data.as_slice())
+}
+
+/// XXH3-128 checksum
+///
+/// ## Parameters
+/// - data: Data to checksum
+///
+/// ## Returns
+/// - 128-bit unsigned integer
+#[rustler::nif(schedule = "DirtyCpu")]
+fn xxh3_128(data: Binary) -> u128 {
+    xxhash_rust::xxh3::xxh3_128(data.as_slice())
+}
+
+/// CRC32C checksum
+///
+/// ## Parameters
+/// - data: Data to checksum
+///
+/// ## Returns
+/// - 32-bit unsigned integer
+#[rustler::nif(schedule = "DirtyCpu")]
+fn crc32c(data: Binary) -> u32 {
+    crc32c::crc32c(data.as_slice())
+}
+
+/// Continue a CRC32C checksum over the next piece of data
+///
+/// `crc32c_append(crc32c(a), b)` equals `crc32c(a <> b)`, so large
+/// inputs can be checksummed chunk by chunk.
+///
+/// ## Parameters
+/// - crc: Checksum of the data so far (0 to start)
+/// - data: Next piece of data
+///
+/// ## Returns
+/// - 32-bit unsigned integer
+#[rustler::nif(schedule = "DirtyCpu")]
+fn crc32c_append(crc: u32, data: Binary) -> u32 {
+    crc32c::crc32c_append(crc, data.as_slice())
+}
@@ -0,0 +1,572 @@
+//! Deoxys-II-256 on the CPU's AES instructions
+//!
+//! Deoxys-BC-384 is sixteen AES rounds, each keyed by its own subtweakey,
+//! so with AES-NI or the ARMv8 Crypto Extensions a round is a single
+//! instruction. The `deoxys` crate goes through `aes::hazmat` one round
+//! at a time and rebuilds the tweak schedule byte by byte for every block.
+//! Here the key half of the schedule is computed once per key, the tweak
+//! half costs one byte shuffle per round, and `LANES` blocks are in flight
+//! at once so their rounds overlap.
+//!
+//! `DeoxysII256` is a drop-in for `deoxys::DeoxysII256`: it uses this
+//! backend when `hardware()` allows and wraps the `deoxys` crate
+//! otherwise. The backend is only compiled with the `accel` feature, and
+//! is only used after it has produced the same output as the crate on the
+//! running machine.
+
+use deoxys::aead::{
+    consts::{U0, U15, U16, U32},
+    AeadCore, AeadInPlace, Error, Key, KeyInit, KeySizeUser, Nonce, Tag,
+};
+use std::sync::OnceLock;
+
+type Block = [u8; 16];
+
+/// Deoxys-BC-384 rounds
+const ROUNDS: usize = 16;
+
+/// Blocks encrypted side by side
+const LANES: usize = 4;
+
+// Tweak prefixes (top nibble of the first byte), Deoxys-II section 2.4
+const TWEAK_AD: u8 = 0x20;
+const TWEAK_AD_LAST: u8 = 0x60;
+const TWEAK_M: u8 = 0x00;
+const TWEAK_M_LAST: u8 = 0x40;
+const TWEAK_TAG: u8 = 0x10;
+const TWEAK_ENC: u8 = 0x80;
+
+/// Round constants, 0x2f times x^i in GF(2^8)
+const RCON: [u8; ROUNDS + 1] = [
+    0x2f, 0x5e, 0xbc, 0x63, 0xc6, 0x97, 0x35, 0x6a, 0xd4, 0xb3, 0x7d, 0xfa, 0xef, 0xc5, 0x91, 0x39,
+    0x72,
+];
+
+/// The tweakey byte permutation h
+const H: [u8; 16] = [1, 6, 11, 12, 5, 10, 15, 0, 9, 14, 3, 4, 13, 2, 7, 8];
+
+/// h applied 0 to `ROUNDS` times, as byte shuffle masks
+#[cfg(feature = "accel")]
+const H_POWERS: [Block; ROUNDS + 1] = h_powers();
+
+#[cfg(feature = "accel")]
+const fn h_powers() -> [Block; ROUNDS + 1] {
+    let mut powers = [[0u8; 16]; ROUNDS + 1];
+    let mut j = 0;
+    while j < 16 {
+        powers[0][j] = j as u8;
+        j += 1;
+    }
+    let mut i = 1;
+    while i <= ROUNDS {
+        let mut j = 0;
+        while j < 16 {
+            powers[i][j] = powers[i - 1][H[j] as usize];
+            j += 1;
+        }
+        i += 1;
+    }
+    powers
+}
+
+fn lfsr2(x: u8) -> u8 {
+    (x << 1) | (((x >> 7) ^ (x >> 5)) & 1)
+}
+
+fn lfsr3(x: u8) -> u8 {
+    (x >> 1) | (((x << 7) ^ (x << 1)) & 0x80)
+}
+
+/// Whether the CPU has the instructions the backend needs
+fn cpu_supported() -> bool {
+    #[cfg(all(feature = "accel", target_arch = "x86_64"))]
+    let supported = std::is_x86_feature_detected!("aes") && std::is_x86_feature_detected!("ssse3");
+
+    #[cfg(all(feature = "accel", target_arch = "aarch64"))]
+    let supported = std::arch::is_aarch64_feature_detected!("aes");
+
+    #[cfg(not(all(
+        feature = "accel",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )))]
+    let supported = false;
+
+    supported
+}
+
+/// Why `DeoxysII256` is not on the hardware backend
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fallback {
+    /// Not compiled in, or the CPU lacks the instructions
+    Unsupported,
+    /// Compiled in and supported, but disagreed with the `deoxys` crate
+    Disagreed,
+}
+
+/// Whether `DeoxysII256` runs on the hardware backend
+///
+/// Decided once per process: the backend must be compiled in, supported
+/// by the CPU, and agree with the `deoxys` crate on a few messages.
+pub fn hardware() -> bool {
+    fallback().is_none()
+}
+
+/// Why the hardware backend was passed over, if it was
+pub fn fallback() -> Option<Fallback> {
+    static FALLBACK: OnceLock<Option<Fallback>> = OnceLock::new();
+    *FALLBACK.get_or_init(|| {
+        if !cpu_supported() {
+            Some(Fallback::Unsupported)
+        } else if !agrees_with_crate() {
+            Some(Fallback::Disagreed)
+        } else {
+            None
+        }
+    })
+}
+
+/// Seal messages of awkward lengths with both backends and compare
+fn agrees_with_crate() -> bool {
+    let key: Vec<u8> = (0..32).collect();
+    let nonce: Vec<u8> = (0x40..0x4f).collect();
+    let schedule = Schedule::new(&key);
+    let reference = deoxys::DeoxysII256::new(Key::<DeoxysII256>::from_slice(&key));
+
+    [0, 1, 15, 16, 17, 64, 100].iter().all(|&len| {
+        let message: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let aad = &message[..len / 2];
+
+        let mut ours = message.clone();
+        let tag = schedule.seal(&nonce, aad, &mut ours);
+        let mut theirs = message.clone();
+        let expected = reference.encrypt_in_place_detached(
+            Nonce::<DeoxysII256>::from_slice(&nonce),
+            aad,
+            &mut theirs,
+        );
+        expected.is_ok_and(|expected| ours == theirs && tag[..] == expected[..])
+    })
+}
+
+/// Deoxys-BC-384 with the key half of the tweakey schedule precomputed
+struct Schedule {
+    /// TK2 ^ TK3 ^ RC for every round
+    subkeys: [Block; ROUNDS + 1],
+}
+
+impl Schedule {
+    fn new(key: &[u8]) -> Self {
+        let mut tk3: Block = key[..16].try_into().unwrap();
+        let mut tk2: Block = key[16..32].try_into().unwrap();
+        let mut subkeys = [[0u8; 16]; ROUNDS + 1];
+
+        for (subkey, rcon) in subkeys.iter_mut().zip(RCON) {
+            for ((byte, a), b) in subkey.iter_mut().zip(tk2).zip(tk3) {
+                *byte = a ^ b;
+            }
+            for (byte, rc) in subkey.iter_mut().zip([1, 2, 4, 8, rcon, rcon, rcon, rcon]) {
+                *byte ^= rc;
+            }
+            tk2 = H.map(|i| lfsr2(tk2[i as usize]));
+            tk3 = H.map(|i| lfsr3(tk3[i as usize]));
+        }
+        Schedule { subkeys }
+    }
+
+    /// Encrypt each block in place under the matching tweak
+    fn encrypt(&self, tweaks: &[Block], blocks: &mut [Block]) {
+        debug_assert_eq!(tweaks.len(), blocks.len());
+
+        // Only constructed once `cpu_supported()` has said yes
+        #[cfg(all(feature = "accel", target_arch = "x86_64"))]
+        unsafe {
+            x86::encrypt(&self.subkeys, tweaks, blocks);
+        }
+
+        #[cfg(all(feature = "accel", target_arch = "aarch64"))]
+        unsafe {
+            arm::encrypt(&self.subkeys, tweaks, blocks);
+        }
+
+        #[cfg(not(all(
+            feature = "accel",
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        )))]
+        unreachable!("no accelerated Deoxys-BC on this target");
+    }
+
+    /// XOR the encryption of every block of `data` into `auth`, with the
+    /// last partial block padded with 10*
+    fn absorb(&self, prefix: u8, last_prefix: u8, data: &[u8], auth: &mut Block) {
+        let mut tweaks = [[0u8; 16]; LANES];
+        let mut blocks = [[0u8; 16]; LANES];
+        let mut chunks = data.chunks_exact(16 * LANES);
+        let mut index = 0;
+
+        for chunk in &mut chunks {
+            for ((tweak, block), input) in tweaks
+                .iter_mut()
+                .zip(&mut blocks)
+                .zip(chunk.chunks_exact(16))
+            {
+                *tweak = counter_tweak(prefix, index);
+                block.copy_from_slice(input);
+                index += 1;
+            }
+            self.encrypt(&tweaks, &mut blocks);
+            blocks.iter().for_each(|block| xor_into(auth, block));
+        }
+
+        let rest = chunks.remainder();
+        for ((tweak, block), input) in tweaks.iter_mut().zip(&mut blocks).zip(rest.chunks(16)) {
+            if input.len() == 16 {
+                *tweak = counter_tweak(prefix, index);
+                block.copy_from_slice(input);
+            } else {
+                *tweak = counter_tweak(last_prefix, index);
+                *block = [0u8; 16];
+                block[..input.len()].copy_from_slice(input);
+                block[input.len()] = 0x80;
+            }
+            index += 1;
+        }
+        let count = rest.len().div_ceil(16);
+        self.encrypt(&tweaks[..count], &mut blocks[..count]);
+        blocks[..count]
+            .iter()
+            .for_each(|block| xor_into(auth, block));
+    }
+
+    /// Tag over the absorbed AD and message
+    fn tag(&self, nonce: &[u8], auth: Block) -> Block {
+        let mut tweak = [0u8; 16];
+        tweak[0] = TWEAK_TAG;
+        tweak[1..].copy_from_slice(nonce);
+        let mut tag = [auth];
+        self.encrypt(&[tweak], &mut tag);
+        tag[0]
+    }
+
+    /// XOR the keystream for `tag` into `buffer`
+    fn keystream(&self, nonce: &[u8], tag: &Block, buffer: &mut [u8]) {
+        let mut base = *tag;
+        base[0] |= TWEAK_ENC;
+        let mut input = [0u8; 16];
+        input[1..].copy_from_slice(nonce);
+
+        let mut tweaks = [[0u8; 16]; LANES];
+        let mut blocks = [[0u8; 16]; LANES];
+        let mut index = 0u64;
+        for chunk in buffer.chunks_mut(16 * LANES) {
+            let count = chunk.len().div_ceil(16);
+            for (tweak, block) in tweaks.iter_mut().zip(&mut blocks).take(count) {
+                *tweak = base;
+                for (byte, counter) in tweak[8..].iter_mut().zip(index.to_be_bytes()) {
+                    *byte ^= counter;
+                }
+                *block = input;
+                index += 1;
+            }
+            self.encrypt(&tweaks[..count], &mut blocks[..count]);
+            for (part, block) in chunk.chunks_mut(16).zip(&blocks) {
+                part.iter_mut()
+                    .zip(block)
+                    .for_each(|(byte, key)| *byte ^= key);
+            }
+        }
+    }
+
+    /// Deoxys-II encryption in place, returning the tag
+    fn seal(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8]) -> Block {
+        let mut auth = [0u8; 16];
+        self.absorb(TWEAK_AD, TWEAK_AD_LAST, aad, &mut auth);
+        self.absorb(TWEAK_M, TWEAK_M_LAST, buffer, &mut auth);
+        let tag = self.tag(nonce, auth);
+        self.keystream(nonce, &tag, buffer);
+        tag
+    }
+
+    /// Deoxys-II decryption in place; `false`, with `buffer` zeroed, if
+    /// the tag doesn't verify
+    fn open(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8], tag: &[u8]) -> bool {
+        let tag: &Block = tag.try_into().unwrap();
+        self.keystream(nonce, tag, buffer);
+
+        let mut auth = [0u8; 16];
+        self.absorb(TWEAK_AD, TWEAK_AD_LAST, aad, &mut auth);
+        self.absorb(TWEAK_M, TWEAK_M_LAST, buffer, &mut auth);
+        let expected = self.tag(nonce, auth);
+
+        // Constant-time comparison
+        let difference = expected
+            .iter()
+            .zip(tag)
+            .fold(0, |acc, (a, b)| acc | (a ^ b));
+        if difference != 0 {
+            buffer.fill(0);
+        }
+        difference == 0
+    }
+}
+
+impl Drop for Schedule {
+    fn drop(&mut self) {
+        for subkey in self.subkeys.iter_mut() {
+            for byte in subkey.iter_mut() {
+                // SAFETY: byte is a valid, aligned &mut u8
+                unsafe { std::ptr::write_volatile(byte, 0) };
+            }
+        }
+    }
+}
+
+/// Tweak with a 4-bit prefix and a big-endian block index
+fn counter_tweak(prefix: u8, index: u64) -> Block {
+    let mut tweak = [0u8; 16];
+    tweak[0] = prefix;
+    tweak[8..].copy_from_slice(&index.to_be_bytes());
+    tweak
+}
+
+fn xor_into(acc: &mut Block, block: &Block) {
+    acc.iter_mut().zip(block).for_each(|(a, b)| *a ^= b);
+}
+
+#[cfg(all(feature = "accel", target_arch = "x86_64"))]
+mod x86 {
+    use super::{Block, H_POWERS, LANES, ROUNDS};
+    use std::arch::x86_64::*;
+
+    #[inline(always)]
+    unsafe fn load(block: &Block) -> __m128i {
+        _mm_loadu_si128(block.as_ptr().cast())
+    }
+
+    /// Deoxys-BC-384 on AES-NI, the tweak schedule one `pshufb` a round
+    ///
+    /// # Safety
+    /// The CPU must support AES-NI and SSSE3.
+    #[target_feature(enable = "aes,ssse3")]
+    pub unsafe fn encrypt(subkeys: &[Block; ROUNDS + 1], tweaks: &[Block], blocks: &mut [Block]) {
+        let mut keys = [_mm_setzero_si128(); ROUNDS + 1];
+        let mut perms = [_mm_setzero_si128(); ROUNDS + 1];
+        for ((key, perm), (subkey, power)) in keys
+            .iter_mut()
+            .zip(&mut perms)
+            .zip(subkeys.iter().zip(&H_POWERS))
+        {
+            *key = load(subkey);
+            *perm = load(power);
+        }
+
+        for (tweaks, blocks) in tweaks.chunks(LANES).zip(blocks.chunks_mut(LANES)) {
+            let lanes = blocks.len();
+            let mut tweak = [_mm_setzero_si128(); LANES];
+            let mut state = [_mm_setzero_si128(); LANES];
+            for ((t, s), (input_tweak, input)) in tweak
+                .iter_mut()
+                .zip(&mut state)
+                .zip(tweaks.iter().zip(blocks.iter()))
+            {
+                *t = load(input_tweak);
+                *s = _mm_xor_si128(load(input), _mm_xor_si128(keys[0], *t));
+            }
+
+            for (key, perm) in keys[1..].iter().zip(&perms[1..]) {
+                for (s, t) in state.iter_mut().zip(&tweak).take(lanes) {
+                    *s = _mm_aesenc_si128(*s, _mm_xor_si128(*key, _mm_shuffle_epi8(*t, *perm)));
+                }
+            }
+
+            for (output, s) in blocks.iter_mut().zip(&state) {
+                _mm_storeu_si128(output.as_mut_ptr().cast(), *s);
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "accel", target_arch = "aarch64"))]
+mod arm {
+    use super::{Block, H_POWERS, LANES, ROUNDS};
+    use std::arch::aarch64::*;
+
+    /// Deoxys-BC-384 on the ARMv8 AES instructions, the tweak schedule one
+    /// `tbl` a round
+    ///
+    /// `aese` XORs its key in before SubBytes and ShiftRows, so it is
+    /// given zero and the subtweakey is XORed in after `aesmc`, which
+    /// matches the AES round Deoxys-BC uses.
+    ///
+    /// # Safety
+    /// The CPU must support the AES instructions.
+    #[target_feature(enable = "neon,aes")]
+    pub unsafe fn encrypt(subkeys: &[Block; ROUNDS + 1], tweaks: &[Block], blocks: &mut [Block]) {
+        let zero = vdupq_n_u8(0);
+        let mut keys = [zero; ROUNDS + 1];
+        let mut perms = [zero; ROUNDS + 1];
+        for ((key, perm), (subkey, power)) in keys
+            .iter_mut()
+            .zip(&mut perms)
+            .zip(subkeys.iter().zip(&H_POWERS))
+        {
+            *key = vld1q_u8(subkey.as_ptr());
+            *perm = vld1q_u8(power.as_ptr());
+        }
+
+        for (tweaks, blocks) in tweaks.chunks(LANES).zip(blocks.chunks_mut(LANES)) {
+            let lanes = blocks.len();
+            let mut tweak = [zero; LANES];
+            let mut state = [zero; LANES];
+            for ((t, s), (input_tweak, input)) in tweak
+                .iter_mut()
+                .zip(&mut state)
+                .zip(tweaks.iter().zip(blocks.iter()))
+            {
+                *t = vld1q_u8(input_tweak.as_ptr());
+                *s = veorq_u8(vld1q_u8(input.as_ptr()), veorq_u8(keys[0], *t));
+            }
+
+            for (key, perm) in keys[1..].iter().zip(&perms[1..]) {
+                for (s, t) in state.iter_mut().zip(&tweak).take(lanes) {
+                    let subtweakey = veorq_u8(*key, vqtbl1q_u8(*t, *perm));
+                    *s = veorq_u8(vaesmcq_u8(vaeseq_u8(*s, zero)), subtweakey);
+                }
+            }
+
+            for (output, s) in blocks.iter_mut().zip(&state) {
+                vst1q_u8(output.as_mut_ptr(), *s);
+            }
+        }
+    }
+}
+
+/// Deoxys-II-256-128, on the hardware backend where `hardware()` allows
+pub struct DeoxysII256(Backend);
+
+enum Backend {
+    Hardware(Box<Schedule>),
+    Soft(Box<deoxys::DeoxysII256>),
+}
+
+impl KeySizeUser for DeoxysII256 {
+    type KeySize = U32;
+}
+
+impl KeyInit for DeoxysII256 {
+    fn new(key: &Key<Self>) -> Self {
+        if hardware() {
+            DeoxysII256(Backend::Hardware(Box::new(Schedule::new(key))))
+        } else {
+            DeoxysII256(Backend::Soft(Box::new(deoxys::DeoxysII256::new(key))))
+        }
+    }
+}
+
+impl AeadCore for DeoxysII256 {
+    type NonceSize = U15;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+impl AeadInPlace for DeoxysII256 {
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<Tag<Self>, Error> {
+        match &self.0 {
+            Backend::Hardware(schedule) => Ok(schedule.seal(nonce, associated_data, buffer).into()),
+            Backend::Soft(cipher) => {
+                cipher.encrypt_in_place_detached(nonce, associated_data, buffer)
+            }
+        }
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> Result<(), Error> {
+        match &self.0 {
+            Backend::Hardware(schedule) => {
+                if schedule.open(nonce, associated_data, buffer, tag) {
+                    Ok(())
+                } else {
+                    Err(Error)
+                }
+            }
+            Backend::Soft(cipher) => {
+                cipher.decrypt_in_place_detached(nonce, associated_data, buffer, tag)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair() -> (Schedule, deoxys::DeoxysII256) {
+        let key: Vec<u8> = (100..132).collect();
+        (
+            Schedule::new(&key),
+            deoxys::DeoxysII256::new(Key::<DeoxysII256>::from_slice(&key)),
+        )
+    }
+
+    #[test]
+    fn test_matches_deoxys_crate() {
+        if !cpu_supported() {
+            return;
+        }
+        let (schedule, reference) = pair();
+        let nonce = [7u8; 15];
+
+        for len in (0..=130).chain([1000, 4096 + 3]) {
+            let message: Vec<u8> = (0..len).map(|i| (i * 31) as u8).collect();
+            let aad = &message[..len % 70];
+
+            let mut ours = message.clone();
+            let tag = schedule.seal(&nonce, aad, &mut ours);
+            let mut theirs = message.clone();
+            let expected = reference
+                .encrypt_in_place_detached(
+                    Nonce::<DeoxysII256>::from_slice(&nonce),
+                    aad,
+                    &mut theirs,
+                )
+                .unwrap();
+            assert_eq!(ours, theirs, "ciphertext, length {}", len);
+            assert_eq!(tag[..], expected[..], "tag, length {}", len);
+
+            assert!(schedule.open(&nonce, aad, &mut ours, &tag));
+            assert_eq!(ours, message);
+        }
+    }
+
+    #[test]
+    fn test_open_rejects_tampering() {
+        if !cpu_supported() {
+            return;
+        }
+        let (schedule, _) = pair();
+        let nonce = [7u8; 15];
+        let mut buffer = b"tampered with in transit".to_vec();
+        let tag = schedule.seal(&nonce, b"aad", &mut buffer);
+
+        buffer[3] ^= 1;
+        assert!(!schedule.open(&nonce, b"aad", &mut buffer, &tag));
+        assert!(buffer.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    #[cfg(feature = "accel")]
+    fn test_h_powers() {
+        // h has order 8 on the tweak: TK1 repeats every eight rounds
+        assert_eq!(H_POWERS[8], H_POWERS[0]);
+        assert_eq!(H_POWERS[1], H);
+    }
+}
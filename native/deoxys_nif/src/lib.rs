@@ -1,11 +1,48 @@
-use rustler::{Env, Binary, Error, OwnedBinary};
+mod accel;
+pub mod bench;
+
+use deoxys::DeoxysII128;
+use nif_support::cipher_nifs::{cipher, open, seal};
+use nif_support::iodata::IoData;
+use rustler::{Binary, Env, Error};
 
 rustler::init!("Elixir.GitFoil.Native.DeoxysNif");
 
-/// Deoxys-II-256 Encryption
+mod atoms {
+    rustler::atoms! {
+        #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+        aes,
+        #[cfg(target_arch = "x86_64")]
+        aes_ni,
+        #[cfg(target_arch = "x86_64")]
+        ssse3,
+        #[cfg(target_arch = "aarch64")]
+        armv8_crypto,
+        soft,
+        unsupported,
+        self_check_failed,
+    }
+}
+
+// encrypt/decrypt, the batch, blob, context and file NIFs, STREAM in all
+// its forms, benchmark/2 and fastest_algorithms/1, on AES instructions
+// when the CPU has them (see `accel`)
+nif_support::cipher_nifs! {
+    cipher: accel::DeoxysII256,
+    name: "Deoxys-II-256",
+    new: cipher::<accel::DeoxysII256>,
+    sizes: { key: 32, nonce: 15, tag: 16, prefix: 10 },
+    subjects: bench::subjects,
+}
+
+/// Deoxys-II-128 Encryption
+///
+/// Deoxys-II-128-128: 256-bit tweakey (128-bit key + 128-bit tweak) instead
+/// of the 384-bit tweakey of Deoxys-II-256. Ciphertexts are not
+/// interchangeable with `encrypt/4`.
 ///
 /// Parameters:
-/// - key: 32 bytes
+/// - key: 16 bytes
 /// - nonce: 15 bytes (120 bits - Deoxys-II specification)
 /// - plaintext: variable length
 /// - aad: variable length (additional authenticated data)
@@ -13,68 +50,22 @@ rustler::init!("Elixir.GitFoil.Native.DeoxysNif");
 /// Returns:
 /// - Ok({ciphertext, tag}) where tag is 16 bytes
 /// - Err for errors
-#[rustler::nif]
-fn encrypt<'a>(
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_128<'a>(
     env: Env<'a>,
     key: Binary,
     nonce: Binary,
-    plaintext: Binary,
-    aad: Binary,
+    plaintext: IoData,
+    aad: IoData,
 ) -> Result<(Binary<'a>, Binary<'a>), Error> {
-    // Validate key length (32 bytes = 256 bits)
-    if key.len() != 32 {
-        return Err(Error::BadArg);
-    }
-
-    // Validate nonce length (15 bytes = 120 bits, Deoxys-II spec)
-    if nonce.len() != 15 {
-        return Err(Error::BadArg);
-    }
-
-    // Use the deoxys crate's AEAD trait implementation
-    use deoxys::DeoxysII256;
-    use deoxys::aead::{Aead, KeyInit, Payload};
-
-    // Convert to GenericArray types
-    let key_array = deoxys::aead::generic_array::GenericArray::from_slice(key.as_slice());
-    let nonce_array = deoxys::aead::generic_array::GenericArray::from_slice(nonce.as_slice());
-
-    // Create cipher
-    let cipher = DeoxysII256::new(key_array);
-
-    // Create payload with AAD
-    let payload = Payload {
-        msg: plaintext.as_slice(),
-        aad: aad.as_slice(),
-    };
-
-    // Encrypt
-    let ciphertext_with_tag = cipher
-        .encrypt(nonce_array, payload)
-        .map_err(|_| Error::RaiseTerm(Box::new("encryption failed")))?;
-
-    // Split ciphertext and tag (last 16 bytes)
-    let tag_start = ciphertext_with_tag.len() - 16;
-    let ciphertext = &ciphertext_with_tag[..tag_start];
-    let tag = &ciphertext_with_tag[tag_start..];
-
-    // Copy to Elixir binaries
-    let mut ciphertext_binary = OwnedBinary::new(ciphertext.len()).unwrap();
-    ciphertext_binary.as_mut_slice().copy_from_slice(ciphertext);
-
-    let mut tag_binary = OwnedBinary::new(16).unwrap();
-    tag_binary.as_mut_slice().copy_from_slice(tag);
-
-    Ok((
-        ciphertext_binary.release(env),
-        tag_binary.release(env),
-    ))
+    let cipher: DeoxysII128 = cipher(key.as_slice())?;
+    seal(env, &cipher, nonce.as_slice(), &plaintext, &aad.to_cow())
 }
 
-/// Deoxys-II-256 Decryption
+/// Deoxys-II-128 Decryption
 ///
 /// Parameters:
-/// - key: 32 bytes
+/// - key: 16 bytes
 /// - nonce: 15 bytes (120 bits - Deoxys-II specification)
 /// - ciphertext: variable length
 /// - tag: 16 bytes (authentication tag)
@@ -83,56 +74,74 @@ fn encrypt<'a>(
 /// Returns:
 /// - Ok(plaintext)
 /// - Err if authentication fails
-#[rustler::nif]
-fn decrypt<'a>(
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_128<'a>(
     env: Env<'a>,
     key: Binary,
     nonce: Binary,
-    ciphertext: Binary,
+    ciphertext: IoData,
     tag: Binary,
-    aad: Binary,
+    aad: IoData,
 ) -> Result<Binary<'a>, Error> {
-    // Validate input sizes
-    if key.len() != 32 {
-        return Err(Error::BadArg);
-    }
-    if nonce.len() != 15 {
-        return Err(Error::BadArg);
-    }
-    if tag.len() != 16 {
-        return Err(Error::BadArg);
-    }
-
-    // Use the deoxys crate's AEAD trait implementation
-    use deoxys::DeoxysII256;
-    use deoxys::aead::{Aead, KeyInit, Payload};
-
-    // Convert to GenericArray types
-    let key_array = deoxys::aead::generic_array::GenericArray::from_slice(key.as_slice());
-    let nonce_array = deoxys::aead::generic_array::GenericArray::from_slice(nonce.as_slice());
-
-    // Reconstruct ciphertext with tag
-    let mut ciphertext_with_tag = Vec::with_capacity(ciphertext.len() + 16);
-    ciphertext_with_tag.extend_from_slice(ciphertext.as_slice());
-    ciphertext_with_tag.extend_from_slice(tag.as_slice());
-
-    // Create cipher
-    let cipher = DeoxysII256::new(key_array);
-
-    // Create payload with AAD
-    let payload = Payload {
-        msg: &ciphertext_with_tag,
-        aad: aad.as_slice(),
-    };
-
-    // Decrypt and verify
-    let plaintext = cipher
-        .decrypt(nonce_array, payload)
-        .map_err(|_| Error::RaiseTerm(Box::new("authentication failed")))?;
+    let cipher: DeoxysII128 = cipher(key.as_slice())?;
+    open(
+        env,
+        &cipher,
+        nonce.as_slice(),
+        &ciphertext,
+        tag.as_slice(),
+        &aad.to_cow(),
+    )
+}
 
-    // Copy to Elixir binary
-    let mut plaintext_binary = OwnedBinary::new(plaintext.len()).unwrap();
-    plaintext_binary.as_mut_slice().copy_from_slice(&plaintext);
+/// Hardware path a cipher takes on the running CPU
+#[derive(rustler::NifMap)]
+struct BackendInfo {
+    backend: rustler::Atom,
+    features: Vec<rustler::Atom>,
+    fallback: Option<rustler::Atom>,
+}
 
-    Ok(plaintext_binary.release(env))
+/// Report which hardware path Deoxys-II-256 takes on the running CPU
+///
+/// With the `accel` feature, Deoxys-BC runs on AES-NI (x86_64) or the
+/// ARMv8 Crypto Extensions (aarch64), tweakey schedule included, once a
+/// startup self-check against the `deoxys` crate passes. Otherwise the
+/// crate is used and reported as :soft. Deoxys-II-128 always uses the crate.
+///
+/// Returns:
+/// - %{backend: atom, features: [atom], fallback: atom | nil} where
+///   backend is :aes_ni, :armv8_crypto or :soft, features lists the CPU
+///   features it uses, and fallback says why :soft was chosen:
+///   :unsupported (not compiled in or no CPU support) or
+///   :self_check_failed (the hardware path disagreed with the crate)
+#[rustler::nif]
+fn backend_info() -> BackendInfo {
+    #[cfg(target_arch = "x86_64")]
+    let (accelerated, features) = (atoms::aes_ni(), vec![atoms::aes(), atoms::ssse3()]);
+
+    #[cfg(target_arch = "aarch64")]
+    let (accelerated, features) = (atoms::armv8_crypto(), vec![atoms::aes()]);
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    let (accelerated, features) = (atoms::soft(), vec![]);
+
+    match accel::fallback() {
+        None => BackendInfo {
+            backend: accelerated,
+            features,
+            fallback: None,
+        },
+        Some(reason) => {
+            let reason = match reason {
+                accel::Fallback::Unsupported => atoms::unsupported(),
+                accel::Fallback::Disagreed => atoms::self_check_failed(),
+            };
+            BackendInfo {
+                backend: atoms::soft(),
+                features: vec![],
+                fallback: Some(reason),
+            }
+        }
+    }
 }
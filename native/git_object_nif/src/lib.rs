@@ -0,0 +1,62 @@
+//! Git object hashing NIF for GitFoil
+//!
+//! Computes git blob object ids natively so the OID can be bound into the
+//! AEAD associated data without hashing large files a second time on the
+//! Elixir side.
+//!
+//! **Algorithms:**
+//! - :sha1: collision-detecting SHA-1 (sha1dc), 20-byte id
+//! - :sha256: SHA-256, 32-byte id, for `--object-format=sha256` repos
+//!
+//! Ids are returned as raw bytes; hex-encode them for display.
+
+mod oid;
+
+use oid::Algorithm;
+use rustler::{Atom, Binary, Env, Error, NifResult, OwnedBinary};
+
+rustler::init!("Elixir.GitFoil.Native.GitObjectNif");
+
+mod atoms {
+    rustler::atoms! {
+        sha1,
+        sha256,
+        sha1_collision,
+    }
+}
+
+/// Git blob object id
+///
+/// Runs on a dirty CPU scheduler, since blobs can be arbitrarily large.
+///
+/// ## Parameters
+/// - data: Blob content (the file as git stores it, after clean filters)
+/// - algorithm: :sha1 or :sha256
+///
+/// ## Returns
+/// - {:ok, oid}: 20-byte (SHA-1) or 32-byte (SHA-256) id
+/// - {:error, :sha1_collision}: The content matches a SHA-1 collision
+///   attack; git would refuse it too
+/// - Err: Unknown algorithm
+#[rustler::nif(schedule = "DirtyCpu")]
+fn hash_object<'a>(
+    env: Env<'a>,
+    data: Binary,
+    algorithm: Atom,
+) -> NifResult<Result<Binary<'a>, Atom>> {
+    let algorithm = if algorithm == atoms::sha1() {
+        Algorithm::Sha1
+    } else if algorithm == atoms::sha256() {
+        Algorithm::Sha256
+    } else {
+        return Err(Error::BadArg);
+    };
+
+    Ok(oid::blob_oid(algorithm, data.as_slice())
+        .map(|oid| {
+            let mut binary = OwnedBinary::new(oid.len()).unwrap();
+            binary.as_mut_slice().copy_from_slice(&oid);
+            binary.release(env)
+        })
+        .map_err(|_| atoms::sha1_collision()))
+}
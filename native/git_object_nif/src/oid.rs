@@ -0,0 +1,81 @@
+//! Git blob object ids
+//!
+//! A blob's id is the hash of `"blob <size>\0"` followed by its content.
+//! SHA-1 repositories use the collision-detecting SHA-1 from sha1dc, the
+//! same one git itself builds with: input that carries a known
+//! collision-attack pattern is rejected rather than given an id.
+
+use sha1_checked::Sha1;
+use sha2::{Digest, Sha256};
+
+/// Object format of the repository
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+}
+
+/// The content matches a SHA-1 collision attack
+#[derive(Debug, PartialEq, Eq)]
+pub struct Collision;
+
+fn blob_header(len: usize) -> String {
+    format!("blob {}\0", len)
+}
+
+/// Object id of a blob with content `data`
+pub fn blob_oid(algorithm: Algorithm, data: &[u8]) -> Result<Vec<u8>, Collision> {
+    let header = blob_header(data.len());
+    match algorithm {
+        Algorithm::Sha1 => {
+            let mut hasher = Sha1::builder().safe_hash(false).build();
+            hasher.update(header.as_bytes());
+            hasher.update(data);
+            let result = hasher.try_finalize();
+            if result.has_collision() {
+                return Err(Collision);
+            }
+            Ok(result.hash().to_vec())
+        }
+        Algorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            hasher.update(header.as_bytes());
+            hasher.update(data);
+            Ok(hasher.finalize().to_vec())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(algorithm: Algorithm, data: &[u8]) -> String {
+        blob_oid(algorithm, data)
+            .unwrap()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Ids as printed by `git hash-object` (and `--object-format=sha256`)
+    #[test]
+    fn test_known_blob_ids() {
+        assert_eq!(
+            hex(Algorithm::Sha1, b""),
+            "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"
+        );
+        assert_eq!(
+            hex(Algorithm::Sha1, b"hello world\n"),
+            "3b18e512dba79e4c8300dd08aeb37f8e728b8dad"
+        );
+        assert_eq!(
+            hex(Algorithm::Sha256, b""),
+            "473a0f4c3be8a93681a267e3b1e9a7dcda1185436fe141f7749120a303721813"
+        );
+        assert_eq!(
+            hex(Algorithm::Sha256, b"hello world\n"),
+            "0bd69098bd9b9cc5934a610ab65da429b525361147faa7b5b922919e9a23143d"
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Offline brute-force cost model for passphrase-derived keys
+//!
+//! Estimates how long, and how much, an attacker needs to search a
+//! passphrase space given the KDF parameters and a hardware model.
+//! Figures are order-of-magnitude: the model counts the dominant work per
+//! guess (hash compressions for PBKDF2, memory traffic for Argon2) and
+//! ignores everything else, which favours the attacker slightly.
+//!
+//! - PBKDF2-HMAC-SHA-x: 2 compressions per iteration (the HMAC pads are
+//!   precomputed once per guess), one output block
+//! - Argon2id: each pass reads two 1 KiB blocks and writes one for every
+//!   block of memory, so a guess moves `3 KiB * memory_kib * iterations`
+//!   bytes. Lanes (parallelism) split the same work and don't change the
+//!   total. A device that can't hold one instance can't attack at all.
+
+/// Key derivation function and its cost parameters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kdf {
+    Pbkdf2Sha256 { iterations: u32 },
+    Pbkdf2Sha512 { iterations: u32 },
+    Argon2id { memory_kib: u32, iterations: u32 },
+}
+
+/// Attacker hardware model (one device, or one rented instance)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hardware {
+    pub name: String,
+    /// SHA-256 compression function calls per second
+    pub sha256_per_second: f64,
+    /// SHA-512 compression function calls per second
+    pub sha512_per_second: f64,
+    /// Memory available for attack instances (bytes)
+    pub memory_bytes: f64,
+    /// Sustained memory bandwidth (bytes per second)
+    pub memory_bandwidth: f64,
+    /// Cost of running the device (USD per hour)
+    pub cost_per_hour: f64,
+}
+
+/// Attack estimate for one hardware model
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    pub guesses_per_second: f64,
+    /// Expected time to find the passphrase (half the space); `None` if the
+    /// device can't run the KDF at all
+    pub expected_seconds: Option<f64>,
+    /// Expected cost in USD; `None` when `expected_seconds` is
+    pub expected_cost: Option<f64>,
+}
+
+/// Largest passphrase entropy accepted, so `2^bits` stays a finite f64
+pub const MAX_ENTROPY_BITS: f64 = 512.0;
+
+/// Argon2 block size (bytes)
+const ARGON2_BLOCK: f64 = 1024.0;
+
+/// Reference hardware used when the caller doesn't supply any
+///
+/// Rough public figures (hashcat benchmarks, vendor memory specs, typical
+/// on-demand cloud pricing) as of 2024; refresh as hardware moves on.
+pub fn default_hardware() -> Vec<Hardware> {
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    vec![
+        Hardware {
+            name: "consumer_gpu".to_string(),
+            sha256_per_second: 2.2e10,
+            sha512_per_second: 7.0e9,
+            memory_bytes: 24.0 * GIB,
+            memory_bandwidth: 1.0e12,
+            cost_per_hour: 0.7,
+        },
+        Hardware {
+            name: "datacenter_gpu".to_string(),
+            sha256_per_second: 2.5e10,
+            sha512_per_second: 8.0e9,
+            memory_bytes: 80.0 * GIB,
+            memory_bandwidth: 3.3e12,
+            cost_per_hour: 2.5,
+        },
+        Hardware {
+            name: "server_cpu".to_string(),
+            sha256_per_second: 2.0e9,
+            sha512_per_second: 5.0e8,
+            memory_bytes: 256.0 * GIB,
+            memory_bandwidth: 2.0e11,
+            cost_per_hour: 3.0,
+        },
+    ]
+}
+
+/// Guesses per second one device sustains against `kdf`
+pub fn guesses_per_second(kdf: Kdf, hw: &Hardware) -> f64 {
+    match kdf {
+        Kdf::Pbkdf2Sha256 { iterations } => hw.sha256_per_second / (2.0 * iterations.max(1) as f64),
+        Kdf::Pbkdf2Sha512 { iterations } => hw.sha512_per_second / (2.0 * iterations.max(1) as f64),
+        Kdf::Argon2id {
+            memory_kib,
+            iterations,
+        } => {
+            let instance_bytes = memory_kib as f64 * ARGON2_BLOCK;
+            if instance_bytes > hw.memory_bytes {
+                return 0.0;
+            }
+            let traffic = 3.0 * instance_bytes * iterations.max(1) as f64;
+            hw.memory_bandwidth / traffic
+        }
+    }
+}
+
+/// Estimate the expected cost of brute-forcing a passphrase
+///
+/// `entropy_bits` must be in `0.0..=MAX_ENTROPY_BITS`.
+pub fn estimate(kdf: Kdf, entropy_bits: f64, hw: &Hardware) -> Estimate {
+    assert!(
+        (0.0..=MAX_ENTROPY_BITS).contains(&entropy_bits),
+        "entropy out of range"
+    );
+
+    let rate = guesses_per_second(kdf, hw);
+    if rate <= 0.0 || !rate.is_finite() {
+        return Estimate {
+            guesses_per_second: 0.0,
+            expected_seconds: None,
+            expected_cost: None,
+        };
+    }
+
+    // On average the passphrase turns up halfway through the space
+    let guesses = (entropy_bits - 1.0).exp2();
+    let seconds = guesses / rate;
+    Estimate {
+        guesses_per_second: rate,
+        expected_seconds: Some(seconds),
+        expected_cost: Some(seconds / 3600.0 * hw.cost_per_hour),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hw() -> Hardware {
+        Hardware {
+            name: "test".to_string(),
+            sha256_per_second: 1.0e9,
+            sha512_per_second: 5.0e8,
+            memory_bytes: 1024.0 * 1024.0 * 1024.0,
+            memory_bandwidth: 3.0 * 1024.0 * 1024.0 * 1024.0,
+            cost_per_hour: 3600.0,
+        }
+    }
+
+    #[test]
+    fn test_pbkdf2_rate() {
+        let kdf = Kdf::Pbkdf2Sha256 {
+            iterations: 500_000,
+        };
+        assert_eq!(guesses_per_second(kdf, &hw()), 1000.0);
+
+        let kdf = Kdf::Pbkdf2Sha512 {
+            iterations: 250_000,
+        };
+        assert_eq!(guesses_per_second(kdf, &hw()), 1000.0);
+    }
+
+    #[test]
+    fn test_argon2_rate_is_bandwidth_bound() {
+        // 3 GiB/s over 3 MiB of traffic per pass = 1024 passes/s
+        let kdf = Kdf::Argon2id {
+            memory_kib: 1024,
+            iterations: 2,
+        };
+        assert_eq!(guesses_per_second(kdf, &hw()), 512.0);
+    }
+
+    #[test]
+    fn test_argon2_too_big_for_device() {
+        let kdf = Kdf::Argon2id {
+            memory_kib: 2 * 1024 * 1024,
+            iterations: 1,
+        };
+        let e = estimate(kdf, 40.0, &hw());
+        assert_eq!(e.guesses_per_second, 0.0);
+        assert_eq!(e.expected_seconds, None);
+        assert_eq!(e.expected_cost, None);
+    }
+
+    #[test]
+    fn test_estimate_time_and_cost() {
+        // 1000 guesses/s, 2^20 bits -> 2^19 expected guesses
+        let kdf = Kdf::Pbkdf2Sha256 {
+            iterations: 500_000,
+        };
+        let e = estimate(kdf, 20.0, &hw());
+        assert_eq!(e.expected_seconds, Some(524.288));
+        // cost_per_hour of 3600 is one dollar per second
+        assert!((e.expected_cost.unwrap() - 524.288).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_more_entropy_costs_more() {
+        let kdf = Kdf::Argon2id {
+            memory_kib: 65536,
+            iterations: 3,
+        };
+        for hw in default_hardware() {
+            let weak = estimate(kdf, 40.0, &hw).expected_seconds.unwrap();
+            let strong = estimate(kdf, 41.0, &hw).expected_seconds.unwrap();
+            assert!((strong / weak - 2.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_max_entropy_stays_finite() {
+        let kdf = Kdf::Pbkdf2Sha256 { iterations: 1 };
+        let e = estimate(kdf, MAX_ENTROPY_BITS, &hw());
+        assert!(e.expected_seconds.unwrap().is_finite());
+        assert!(e.expected_cost.unwrap().is_finite());
+    }
+}
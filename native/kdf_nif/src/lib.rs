@@ -0,0 +1,316 @@
+//! Key derivation NIF for GitFoil
+//!
+//! **Functions:**
+//! - `estimate_crack_cost/2,3`: projected attacker time and cost for a
+//!   passphrase-derived key, so the init wizard can justify KDF settings
+//!   with concrete numbers
+//! - `pbkdf2_sha512/4`: PBKDF2-HMAC-SHA512 (RFC 8018), for keyfile formats
+//!   that mandate it
+//! - `balloon_sha256/4`: Balloon hashing (Boneh, Corrigan-Gibbs, Schechter
+//!   2016) over SHA-256, a memory-hard alternative for deployments whose
+//!   policy rules out Argon2
+
+mod estimate;
+
+use estimate::{Hardware, Kdf};
+use rustler::{Atom, Binary, Env, Error, NifMap, NifResult, OwnedBinary, Term};
+use sha2::{Sha256, Sha512};
+
+rustler::init!("Elixir.GitFoil.Native.KdfNif");
+
+mod atoms {
+    rustler::atoms! {
+        algorithm,
+        iterations,
+        memory_kib,
+        pbkdf2_sha256,
+        pbkdf2_sha512,
+        argon2id,
+        name,
+        sha256_per_second,
+        sha512_per_second,
+        memory_bytes,
+        memory_bandwidth,
+        cost_per_hour,
+    }
+}
+
+/// Largest PBKDF2 output accepted (bytes)
+const MAX_PBKDF2_OUTPUT: usize = 1 << 16;
+
+/// Largest Balloon space cost accepted (32-byte blocks, 1 GiB)
+const MAX_BALLOON_SPACE: u32 = 1 << 25;
+
+#[derive(NifMap)]
+struct CrackEstimate {
+    hardware: String,
+    guesses_per_second: f64,
+    expected_seconds: Option<f64>,
+    expected_cost: Option<f64>,
+}
+
+/// Decode an Elixir integer or float
+fn number(term: Term) -> NifResult<f64> {
+    term.decode::<f64>()
+        .or_else(|_| term.decode::<i64>().map(|n| n as f64))
+}
+
+/// Decode a map value that must be a finite, non-negative number
+fn field(map: Term, key: Atom) -> NifResult<f64> {
+    let value = number(map.map_get(key)?)?;
+    if !value.is_finite() || value < 0.0 {
+        return Err(Error::BadArg);
+    }
+    Ok(value)
+}
+
+fn decode_kdf(params: Term) -> NifResult<Kdf> {
+    let algorithm: Atom = params.map_get(atoms::algorithm())?.decode()?;
+    let iterations: u32 = params.map_get(atoms::iterations())?.decode()?;
+
+    if algorithm == atoms::pbkdf2_sha256() {
+        Ok(Kdf::Pbkdf2Sha256 { iterations })
+    } else if algorithm == atoms::pbkdf2_sha512() {
+        Ok(Kdf::Pbkdf2Sha512 { iterations })
+    } else if algorithm == atoms::argon2id() {
+        let memory_kib: u32 = params.map_get(atoms::memory_kib())?.decode()?;
+        Ok(Kdf::Argon2id {
+            memory_kib,
+            iterations,
+        })
+    } else {
+        Err(Error::BadArg)
+    }
+}
+
+fn decode_hardware(model: Term) -> NifResult<Hardware> {
+    Ok(Hardware {
+        name: model.map_get(atoms::name())?.decode()?,
+        sha256_per_second: field(model, atoms::sha256_per_second())?,
+        sha512_per_second: field(model, atoms::sha512_per_second())?,
+        memory_bytes: field(model, atoms::memory_bytes())?,
+        memory_bandwidth: field(model, atoms::memory_bandwidth())?,
+        cost_per_hour: field(model, atoms::cost_per_hour())?,
+    })
+}
+
+fn crack_cost(
+    kdf_params: Term,
+    entropy_bits: Term,
+    hardware: &[Hardware],
+) -> NifResult<Vec<CrackEstimate>> {
+    let kdf = decode_kdf(kdf_params)?;
+    let entropy_bits = number(entropy_bits)?;
+    if !(0.0..=estimate::MAX_ENTROPY_BITS).contains(&entropy_bits) {
+        return Err(Error::BadArg);
+    }
+
+    Ok(hardware
+        .iter()
+        .map(|hw| {
+            let e = estimate::estimate(kdf, entropy_bits, hw);
+            CrackEstimate {
+                hardware: hw.name.clone(),
+                guesses_per_second: e.guesses_per_second,
+                expected_seconds: e.expected_seconds,
+                expected_cost: e.expected_cost,
+            }
+        })
+        .collect())
+}
+
+/// Estimate offline brute-force cost against the built-in hardware models
+///
+/// ## Parameters
+/// - kdf_params: `%{algorithm: :argon2id, memory_kib: m, iterations: t}` or
+///   `%{algorithm: :pbkdf2_sha256 | :pbkdf2_sha512, iterations: n}`
+///   (extra keys such as `:parallelism` are ignored; lanes don't change
+///   the attacker's total work)
+/// - passphrase_entropy_bits: 0..512, integer or float
+///
+/// ## Returns
+/// - List of `%{hardware: name, guesses_per_second: rate,
+///   expected_seconds: s | nil, expected_cost: usd | nil}`, one per model.
+///   `nil` means the device can't run the KDF at all (not enough memory).
+#[rustler::nif]
+fn estimate_crack_cost(
+    kdf_params: Term,
+    passphrase_entropy_bits: Term,
+) -> NifResult<Vec<CrackEstimate>> {
+    crack_cost(
+        kdf_params,
+        passphrase_entropy_bits,
+        &estimate::default_hardware(),
+    )
+}
+
+/// Estimate offline brute-force cost against caller-supplied hardware models
+///
+/// ## Parameters
+/// - kdf_params, passphrase_entropy_bits: As for `estimate_crack_cost/2`
+/// - hardware: List of `%{name: string, sha256_per_second: n,
+///   sha512_per_second: n, memory_bytes: n, memory_bandwidth: bytes_per_s,
+///   cost_per_hour: usd}`
+///
+/// ## Returns
+/// - As for `estimate_crack_cost/2`
+#[rustler::nif(name = "estimate_crack_cost")]
+fn estimate_crack_cost_with(
+    kdf_params: Term,
+    passphrase_entropy_bits: Term,
+    hardware: Vec<Term>,
+) -> NifResult<Vec<CrackEstimate>> {
+    let hardware = hardware
+        .into_iter()
+        .map(decode_hardware)
+        .collect::<NifResult<Vec<_>>>()?;
+    crack_cost(kdf_params, passphrase_entropy_bits, &hardware)
+}
+
+/// PBKDF2-HMAC-SHA512
+///
+/// Runs on a dirty CPU scheduler, so a high `iterations` does not stall
+/// the normal schedulers.
+///
+/// ## Parameters
+/// - password: Passphrase bytes
+/// - salt: Salt (any length)
+/// - iterations: Iteration count (>= 1)
+/// - length: Output length (1..=65536 bytes)
+///
+/// ## Returns
+/// - Ok(key): Derived key of `length` bytes
+/// - Err: Zero iterations or length out of range
+#[rustler::nif(schedule = "DirtyCpu")]
+fn pbkdf2_sha512<'a>(
+    env: Env<'a>,
+    password: Binary,
+    salt: Binary,
+    iterations: u32,
+    length: usize,
+) -> NifResult<Binary<'a>> {
+    if iterations == 0 || length == 0 || length > MAX_PBKDF2_OUTPUT {
+        return Err(Error::BadArg);
+    }
+
+    let mut key = OwnedBinary::new(length).ok_or(Error::BadArg)?;
+    pbkdf2::pbkdf2_hmac::<Sha512>(
+        password.as_slice(),
+        salt.as_slice(),
+        iterations,
+        key.as_mut_slice(),
+    );
+    Ok(key.release(env))
+}
+
+/// Balloon-SHA256
+///
+/// Runs on a dirty CPU scheduler: a derivation is meant to take a while.
+///
+/// ## Parameters
+/// - password: Passphrase bytes
+/// - salt: Salt (at least 16 random bytes recommended)
+/// - space_cost: Buffer size in 32-byte blocks (1..=2^25, i.e. up to 1 GiB)
+/// - time_cost: Mixing rounds over the buffer (>= 1)
+///
+/// ## Returns
+/// - Ok(key): 32-byte derived key
+/// - Err: A cost is zero or the space cost is too large
+#[rustler::nif(schedule = "DirtyCpu")]
+fn balloon_sha256<'a>(
+    env: Env<'a>,
+    password: Binary,
+    salt: Binary,
+    space_cost: u32,
+    time_cost: u32,
+) -> NifResult<Binary<'a>> {
+    use balloon_hash::{Algorithm, Balloon, Params};
+    use zeroize::Zeroize;
+
+    if space_cost == 0 || space_cost > MAX_BALLOON_SPACE || time_cost == 0 {
+        return Err(Error::BadArg);
+    }
+
+    let params = Params::new(space_cost, time_cost, 1).map_err(|_| Error::BadArg)?;
+    let mut derived = Balloon::<Sha256>::new(Algorithm::Balloon, params, None)
+        .hash(password.as_slice(), salt.as_slice())
+        .map_err(|_| Error::BadArg)?;
+
+    let mut key = OwnedBinary::new(derived.len()).unwrap();
+    key.as_mut_slice().copy_from_slice(&derived);
+    derived.as_mut_slice().zeroize();
+    Ok(key.release(env))
+}
+
+#[cfg(test)]
+mod tests {
+    use balloon_hash::{Algorithm, Balloon, Params};
+    use sha2::{Sha256, Sha512};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn balloon_hex(password: &[u8], salt: &[u8], space_cost: u32, time_cost: u32) -> String {
+        // Same parameters as `balloon_sha256`
+        let params = Params::new(space_cost, time_cost, 1).unwrap();
+        let derived = Balloon::<Sha256>::new(Algorithm::Balloon, params, None)
+            .hash(password, salt)
+            .unwrap();
+        hex(&derived)
+    }
+
+    fn pbkdf2_hex(password: &[u8], salt: &[u8], iterations: u32, length: usize) -> String {
+        let mut key = vec![0u8; length];
+        pbkdf2::pbkdf2_hmac::<Sha512>(password, salt, iterations, &mut key);
+        hex(&key)
+    }
+
+    /// RFC 6070-style vectors for PBKDF2-HMAC-SHA512
+    #[test]
+    fn test_pbkdf2_sha512_vectors() {
+        assert_eq!(
+            pbkdf2_hex(b"password", b"salt", 1, 64),
+            "867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf252\
+             c02d470a285a0501bad999bfe943c08f050235d7d68b1da55e63f73b60a57fce"
+        );
+        assert_eq!(
+            pbkdf2_hex(b"password", b"salt", 2, 64),
+            "e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53c\
+             f76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e"
+        );
+        assert_eq!(
+            pbkdf2_hex(
+                b"passwordPASSWORDpassword",
+                b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+                4096,
+                64
+            ),
+            "8c0511f4c6e597c6ac6315d8f0362e225f3c501495ba23b868c005174dc4ee71\
+             115b59f9e60cd9532fa33e0f75aefe30225c583a186cd82bd4daea9724a3d3b8"
+        );
+    }
+
+    #[test]
+    fn test_pbkdf2_sha512_output_is_prefix_consistent() {
+        let long = pbkdf2_hex(b"password", b"salt", 2, 100);
+        assert_eq!(pbkdf2_hex(b"password", b"salt", 2, 13), long[..26]);
+    }
+
+    /// Balloon reference implementation vectors (SHA-256, delta = 3)
+    #[test]
+    fn test_balloon_sha256_vectors() {
+        assert_eq!(
+            balloon_hex(b"hunter42", b"examplesalt", 1024, 3),
+            "716043dff777b44aa7b88dcbab12c078abecfac9d289c5b5195967aa63440dfb"
+        );
+        assert_eq!(
+            balloon_hex(b"", b"salt", 3, 3),
+            "5f02f8206f9cd212485c6bdf85527b698956701ad0852106f94b94ee94577378"
+        );
+        assert_eq!(
+            balloon_hex(b"password", b"", 3, 3),
+            "20aa99d7fe3f4df4bd98c655c5480ec98b143107a331fd491deda885c4d6a6cc"
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! Key cache with time-to-live eviction
+//!
+//! Entries expire a fixed TTL after they were inserted. A reaper thread
+//! sleeps until the next expiry and drops expired entries, which zeroizes
+//! them, so keys are wiped on time even if nothing touches the cache.
+//! Lookups also check expiry themselves, so a late reaper can never hand
+//! out an expired key.
+
+use crate::secret::LockedKey;
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct Entry {
+    key: LockedKey,
+    expires: Instant,
+}
+
+struct State {
+    ttl: Duration,
+    entries: HashMap<Vec<u8>, Entry>,
+    closed: bool,
+}
+
+struct Inner {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+/// Snapshot of one cached key, without the key bytes
+pub struct EntryStatus {
+    pub id: Vec<u8>,
+    pub expires_in: Duration,
+    pub locked: bool,
+}
+
+/// TTL key cache
+pub struct KeyCache {
+    inner: Arc<Inner>,
+}
+
+impl KeyCache {
+    /// Create an empty cache whose entries live for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        let inner = Arc::new(Inner {
+            state: Mutex::new(State {
+                ttl,
+                entries: HashMap::new(),
+                closed: false,
+            }),
+            wake: Condvar::new(),
+        });
+
+        let reaper = Arc::clone(&inner);
+        thread::Builder::new()
+            .name("gitfoil-keycache".into())
+            .spawn(move || reap(&reaper))
+            .expect("failed to spawn key cache reaper");
+
+        KeyCache { inner }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.inner.state.lock().unwrap()
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.state().ttl
+    }
+
+    /// Store `key` under `id`, replacing (and wiping) any previous entry
+    ///
+    /// Returns false, storing nothing, once the cache has been closed.
+    pub fn put(&self, id: &[u8], key: &[u8]) -> bool {
+        let mut state = self.state();
+        if state.closed {
+            return false;
+        }
+
+        let expires = Instant::now() + state.ttl;
+        state.entries.insert(
+            id.to_vec(),
+            Entry {
+                key: LockedKey::new(key),
+                expires,
+            },
+        );
+        drop(state);
+        self.inner.wake.notify_all();
+        true
+    }
+
+    /// Run `f` on the key stored under `id`, if present and not expired
+    pub fn with_key<R>(&self, id: &[u8], f: impl FnOnce(&[u8]) -> R) -> Option<R> {
+        let mut state = self.state();
+        let now = Instant::now();
+
+        match state.entries.get(id) {
+            Some(entry) if entry.expires > now => Some(f(entry.key.as_slice())),
+            Some(_) => {
+                state.entries.remove(id);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Wipe the entry stored under `id`; true if there was one
+    pub fn evict(&self, id: &[u8]) -> bool {
+        self.state().entries.remove(id).is_some()
+    }
+
+    /// Wipe every entry
+    pub fn lock(&self) {
+        self.state().entries.clear();
+    }
+
+    /// Wipe every entry and refuse new ones; stops the reaper thread
+    pub fn close(&self) {
+        let mut state = self.state();
+        state.closed = true;
+        state.entries.clear();
+        drop(state);
+        self.inner.wake.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state().closed
+    }
+
+    /// Describe the live entries
+    pub fn status(&self) -> Vec<EntryStatus> {
+        let state = self.state();
+        let now = Instant::now();
+
+        let mut entries: Vec<EntryStatus> = state
+            .entries
+            .iter()
+            .filter(|(_, e)| e.expires > now)
+            .map(|(id, e)| EntryStatus {
+                id: id.clone(),
+                expires_in: e.expires - now,
+                locked: e.key.is_locked(),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        entries
+    }
+}
+
+impl Drop for KeyCache {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Reaper loop: drop expired entries, then sleep until the next expiry
+fn reap(inner: &Inner) {
+    let mut state = inner.state.lock().unwrap();
+    loop {
+        if state.closed {
+            return;
+        }
+
+        let now = Instant::now();
+        state.entries.retain(|_, e| e.expires > now);
+
+        let next = state.entries.values().map(|e| e.expires).min();
+        state = match next {
+            Some(expires) => inner.wake.wait_timeout(state, expires - now).unwrap().0,
+            None => inner.wake.wait(state).unwrap(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_and_get() {
+        let cache = KeyCache::new(Duration::from_secs(60));
+        cache.put(b"repo", &[7u8; 32]);
+
+        assert_eq!(cache.with_key(b"repo", |k| k.to_vec()), Some(vec![7u8; 32]));
+        assert_eq!(cache.with_key(b"other", |k| k.to_vec()), None);
+    }
+
+    #[test]
+    fn test_put_replaces() {
+        let cache = KeyCache::new(Duration::from_secs(60));
+        cache.put(b"repo", &[1u8; 16]);
+        cache.put(b"repo", &[2u8; 16]);
+
+        assert_eq!(cache.with_key(b"repo", |k| k[0]), Some(2));
+        assert_eq!(cache.status().len(), 1);
+    }
+
+    #[test]
+    fn test_lock_and_evict() {
+        let cache = KeyCache::new(Duration::from_secs(60));
+        cache.put(b"a", &[1u8; 16]);
+        cache.put(b"b", &[2u8; 16]);
+
+        assert!(cache.evict(b"a"));
+        assert!(!cache.evict(b"a"));
+        assert_eq!(cache.status().len(), 1);
+
+        cache.lock();
+        assert!(cache.status().is_empty());
+        assert_eq!(cache.with_key(b"b", |k| k.len()), None);
+    }
+
+    #[test]
+    fn test_close_refuses_new_keys() {
+        let cache = KeyCache::new(Duration::from_secs(60));
+        assert!(cache.put(b"a", &[1u8; 16]));
+
+        cache.close();
+        assert!(cache.is_closed());
+        assert!(cache.status().is_empty());
+        assert!(!cache.put(b"b", &[2u8; 16]));
+        assert!(cache.with_key(b"b", |_| ()).is_none());
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let cache = KeyCache::new(Duration::from_millis(50));
+        cache.put(b"repo", &[9u8; 32]);
+        assert!(cache.with_key(b"repo", |_| ()).is_some());
+
+        thread::sleep(Duration::from_millis(120));
+
+        // Reaper should have removed it without any lookup
+        assert!(cache.inner.state.lock().unwrap().entries.is_empty());
+        assert!(cache.with_key(b"repo", |_| ()).is_none());
+    }
+
+    #[test]
+    fn test_status_is_sorted_and_hides_keys() {
+        let cache = KeyCache::new(Duration::from_secs(60));
+        cache.put(b"b", &[2u8; 16]);
+        cache.put(b"a", &[1u8; 16]);
+
+        let status = cache.status();
+        let ids: Vec<&[u8]> = status.iter().map(|e| e.id.as_slice()).collect();
+        assert_eq!(ids, vec![b"a".as_slice(), b"b".as_slice()]);
+        assert!(status.iter().all(|e| e.expires_in <= Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,311 @@
+//! Key import from environment variables
+//!
+//! CI pipelines hand keys over as environment variables. Decoding and
+//! checking them here means the key bytes go straight from the process
+//! environment into a `LockedKey`, never through an Elixir string.
+//!
+//! **Encodings:** hex, base64 (standard or URL-safe, padding optional) and
+//! bech32/bech32m (any human-readable prefix). Some strings are valid in
+//! more than one encoding (all-hex text is also base64), so every
+//! decoding that succeeds is tried against the expected fingerprint.
+//!
+//! **Fingerprint:** SHA-256 of the raw key bytes, given as 64 hex digits or
+//! as the 32 raw bytes.
+
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+/// Why an import failed
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportError {
+    /// The variable is unset or not valid UTF-8
+    NotSet,
+    /// The value is not hex, base64 or bech32
+    InvalidEncoding,
+    /// The decoded key is empty or longer than allowed
+    InvalidLength,
+    /// No decoding matches the expected fingerprint
+    FingerprintMismatch,
+}
+
+/// SHA-256 fingerprint of a key
+pub fn fingerprint(key: &[u8]) -> [u8; 32] {
+    let digest = Sha256::digest(key);
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&digest[..32]);
+    out
+}
+
+/// Parse an expected fingerprint: 64 hex digits or 32 raw bytes
+pub fn parse_fingerprint(text: &[u8]) -> Option<[u8; 32]> {
+    if let Ok(raw) = <[u8; 32]>::try_from(text) {
+        return Some(raw);
+    }
+    let decoded = decode_hex(std::str::from_utf8(text).ok()?)?;
+    <[u8; 32]>::try_from(decoded.as_slice()).ok()
+}
+
+/// Decode `value` and return the key whose fingerprint is `expected`
+pub fn decode_key(
+    value: &str,
+    expected: &[u8; 32],
+    max_len: usize,
+) -> Result<Zeroizing<Vec<u8>>, ImportError> {
+    let value = value.trim();
+    let candidates: Vec<Zeroizing<Vec<u8>>> = [
+        decode_hex(value),
+        decode_bech32(value),
+        decode_base64(value),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    if candidates.is_empty() {
+        return Err(ImportError::InvalidEncoding);
+    }
+    if !candidates
+        .iter()
+        .any(|key| !key.is_empty() && key.len() <= max_len)
+    {
+        return Err(ImportError::InvalidLength);
+    }
+
+    candidates
+        .into_iter()
+        .filter(|key| !key.is_empty() && key.len() <= max_len)
+        .find(|key| ct_eq(&fingerprint(key), expected))
+        .ok_or(ImportError::FingerprintMismatch)
+}
+
+/// Read and decode the key in environment variable `var`
+pub fn key_from_env(
+    var: &str,
+    expected: &[u8; 32],
+    max_len: usize,
+) -> Result<Zeroizing<Vec<u8>>, ImportError> {
+    let value = std::env::var(var).map_err(|_| ImportError::NotSet)?;
+    let value = Zeroizing::new(value.into_bytes());
+    // The bytes came from a String, so they are valid UTF-8
+    let text = std::str::from_utf8(&value).map_err(|_| ImportError::NotSet)?;
+    decode_key(text, expected, max_len)
+}
+
+/// Constant-time equality for equal-length byte strings
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn decode_hex(text: &str) -> Option<Zeroizing<Vec<u8>>> {
+    let digits = text.as_bytes();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let mut out = Zeroizing::new(Vec::with_capacity(digits.len() / 2));
+    for pair in digits.chunks_exact(2) {
+        let hi = (pair[0] as char).to_digit(16)?;
+        let lo = (pair[1] as char).to_digit(16)?;
+        out.push((hi << 4 | lo) as u8);
+    }
+    Some(out)
+}
+
+fn decode_base64(text: &str) -> Option<Zeroizing<Vec<u8>>> {
+    let text = text.trim_end_matches('=');
+    if text.is_empty() || text.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut out = Zeroizing::new(Vec::with_capacity(text.len() * 3 / 4));
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6 | value as u32) & 0xFFFFFF;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Checksum constants for bech32 (BIP 173) and bech32m (BIP 350)
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+fn bech32_polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GEN: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk = 1u32;
+    for v in values {
+        let top = chk >> 25;
+        chk = (chk & 0x1ff_ffff) << 5 ^ v as u32;
+        for (i, g) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+fn decode_bech32(text: &str) -> Option<Zeroizing<Vec<u8>>> {
+    // Either all lowercase or all uppercase
+    if text.bytes().any(|c| c.is_ascii_lowercase()) && text.bytes().any(|c| c.is_ascii_uppercase())
+    {
+        return None;
+    }
+    let text = Zeroizing::new(text.to_ascii_lowercase().into_bytes());
+
+    let sep = text.iter().rposition(|&c| c == b'1')?;
+    let (hrp, data) = (&text[..sep], &text[sep + 1..]);
+    if hrp.is_empty() || data.len() < 6 || hrp.iter().any(|&c| !(33..=126).contains(&c)) {
+        return None;
+    }
+
+    let mut values = Zeroizing::new(Vec::with_capacity(data.len()));
+    for &c in data {
+        values.push(BECH32_CHARSET.iter().position(|&x| x == c)? as u8);
+    }
+
+    let expanded = hrp
+        .iter()
+        .map(|c| c >> 5)
+        .chain(std::iter::once(0))
+        .chain(hrp.iter().map(|c| c & 31))
+        .chain(values.iter().copied());
+    let chk = bech32_polymod(expanded);
+    if chk != BECH32_CONST && chk != BECH32M_CONST {
+        return None;
+    }
+
+    // Regroup 5-bit values (minus the checksum) into bytes
+    let mut out = Zeroizing::new(Vec::with_capacity(values.len() * 5 / 8));
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &v in &values[..values.len() - 6] {
+        acc = (acc << 5 | v as u32) & 0xFFF;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    // Leftover bits must be zero padding
+    if bits >= 5 || acc & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> Vec<u8> {
+        (0u8..32).collect()
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let expected =
+            decode_hex("630dcd2966c4336691125448bbb25b4ff412a49c732db2c8abc1b8581bd710dd").unwrap();
+        assert_eq!(fingerprint(&key()).to_vec(), *expected);
+    }
+
+    #[test]
+    fn test_decoders() {
+        let hex = "000102030405060708090a0b0c0d0e0f101112131415161718191A1B1C1D1E1F";
+        assert_eq!(*decode_hex(hex).unwrap(), key());
+
+        for b64 in [
+            "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=",
+            "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8",
+        ] {
+            assert_eq!(*decode_base64(b64).unwrap(), key());
+        }
+        assert_eq!(*decode_base64("-_-_").unwrap(), vec![0xFB, 0xFF, 0xBF]);
+
+        for b32 in [
+            "gitfoil1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0sg3xshc",
+            "gitfoil1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0sadkuj6",
+            "GITFOIL1QQQSYQCYQ5RQWZQFPG9SCRGWPUGPZYSNZS23V9CCRYDPK8QARC0SG3XSHC",
+        ] {
+            assert_eq!(*decode_bech32(b32).unwrap(), key());
+        }
+    }
+
+    #[test]
+    fn test_bech32_rejects_bad_checksum_and_mixed_case() {
+        assert!(decode_bech32(
+            "gitfoil1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0sg3xshq"
+        )
+        .is_none());
+        assert!(decode_bech32(
+            "Gitfoil1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0sg3xshc"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_decode_key_picks_matching_encoding() {
+        let expected = fingerprint(&key());
+        for value in [
+            " 000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\n",
+            "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=",
+            "gitfoil1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0sg3xshc",
+        ] {
+            assert_eq!(*decode_key(value, &expected, 1024).unwrap(), key());
+        }
+
+        // All-hex text that is meant as base64
+        let b64_key = decode_base64("deadbeef").unwrap();
+        let got = decode_key("deadbeef", &fingerprint(&b64_key), 1024).unwrap();
+        assert_eq!(*got, *b64_key);
+    }
+
+    #[test]
+    fn test_decode_key_errors() {
+        let expected = fingerprint(&key());
+        assert_eq!(
+            decode_key("not a key!", &expected, 1024).err(),
+            Some(ImportError::InvalidEncoding)
+        );
+        assert_eq!(
+            decode_key("00112233", &expected, 1024).err(),
+            Some(ImportError::FingerprintMismatch)
+        );
+        assert_eq!(
+            decode_key("00112233", &expected, 2).err(),
+            Some(ImportError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_parse_fingerprint() {
+        let fp = fingerprint(&key());
+        assert_eq!(parse_fingerprint(&fp), Some(fp));
+        assert_eq!(
+            parse_fingerprint(b"630DCD2966C4336691125448BBB25B4FF412A49C732DB2C8ABC1B8581BD710DD"),
+            Some(fp)
+        );
+        assert_eq!(parse_fingerprint(b"630dcd"), None);
+    }
+}
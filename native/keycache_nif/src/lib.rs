@@ -0,0 +1,423 @@
+//! Key cache NIF for GitFoil
+//!
+//! Holds unwrapped repository keys in locked memory for a limited time,
+//! supporting a "repo auto-locks after N minutes" workflow.
+//!
+//! **Behaviour:**
+//! - Keys are copied into `mlock`ed buffers (best effort) and zeroized on removal
+//! - Each entry expires a fixed TTL after `put/3`; expired keys are wiped by a
+//!   background reaper, not just hidden
+//! - `lock/1` wipes every entry immediately
+//! - `wipe/1` wipes every entry and invalidates the cache for good
+//! - `status/1` reports entries and remaining time, never key bytes
+//! - `import_key_from_env/3` loads a key straight from an environment
+//!   variable, checked against its expected fingerprint
+//! - `usage_*` functions count operations and bytes per key and warn when
+//!   a key is due for rotation; see `usage` for the thresholds
+
+mod cache;
+mod import;
+mod secret;
+mod usage;
+
+use cache::KeyCache;
+use rustler::{
+    Atom, Binary, Encoder, Env, Error, NifMap, NifResult, OwnedBinary, Resource, ResourceArc, Term,
+};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use usage::{Exceeded, Thresholds, UsageTracker};
+
+rustler::init!("Elixir.GitFoil.Native.KeyCacheNif");
+
+mod atoms {
+    rustler::atoms! {
+        ok,
+        locked,
+        not_set,
+        invalid_encoding,
+        invalid_length,
+        fingerprint_mismatch,
+        nil,
+        warning,
+        rotation_recommended,
+        ops,
+        bytes,
+        age,
+        max_ops,
+        max_bytes,
+        max_age_s,
+        invalid_state,
+    }
+}
+
+/// Largest key accepted by `put/3` (bytes)
+const MAX_KEY_BYTES: usize = 1024;
+
+/// Key cache resource
+struct KeyCacheResource {
+    cache: KeyCache,
+}
+
+#[rustler::resource_impl]
+impl Resource for KeyCacheResource {}
+
+#[derive(NifMap)]
+struct EntryInfo<'a> {
+    id: Binary<'a>,
+    expires_in_ms: u64,
+    mlocked: bool,
+}
+
+#[derive(NifMap)]
+struct CacheStatus<'a> {
+    ttl_ms: u64,
+    wiped: bool,
+    entries: Vec<EntryInfo<'a>>,
+}
+
+/// Key usage tracker resource
+struct UsageResource {
+    tracker: Mutex<UsageTracker>,
+}
+
+#[rustler::resource_impl]
+impl Resource for UsageResource {}
+
+#[derive(NifMap)]
+struct UsageInfo<'a> {
+    id: Binary<'a>,
+    ops: u64,
+    bytes: u64,
+    age_s: u64,
+    exceeded: Vec<Atom>,
+}
+
+fn copy_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env)
+}
+
+/// Create a key cache
+///
+/// ## Parameters
+/// - ttl_ms: Lifetime of each entry in milliseconds (must be > 0)
+///
+/// ## Returns
+/// - Ok(cache): Key cache resource
+/// - Err: Invalid TTL
+#[rustler::nif]
+fn new(ttl_ms: u64) -> Result<ResourceArc<KeyCacheResource>, Error> {
+    if ttl_ms == 0 {
+        return Err(Error::BadArg);
+    }
+
+    Ok(ResourceArc::new(KeyCacheResource {
+        cache: KeyCache::new(Duration::from_millis(ttl_ms)),
+    }))
+}
+
+/// Store a key
+///
+/// Replaces and wipes any key already stored under `id`. The TTL starts now.
+///
+/// ## Parameters
+/// - cache: Resource from `new/1`
+/// - id: Key identifier (e.g. repo id or key fingerprint)
+/// - key: Key material (1..=1024 bytes)
+///
+/// ## Returns
+/// - :ok
+/// - Err: The cache has been wiped
+#[rustler::nif]
+fn put(cache: ResourceArc<KeyCacheResource>, id: Binary, key: Binary) -> Result<Atom, Error> {
+    if key.is_empty() || key.len() > MAX_KEY_BYTES {
+        return Err(Error::BadArg);
+    }
+
+    if !cache.cache.put(id.as_slice(), key.as_slice()) {
+        return Err(Error::RaiseTerm(Box::new("key cache wiped")));
+    }
+    Ok(atoms::ok())
+}
+
+/// Load a key from an environment variable
+///
+/// The value may be hex, base64 (standard or URL-safe) or bech32/bech32m.
+/// The decoded key is checked against `expected_fingerprint` and stored
+/// under its fingerprint; the key bytes never reach the caller.
+///
+/// ## Parameters
+/// - cache: Resource from `new/1`
+/// - var: Environment variable name
+/// - expected_fingerprint: SHA-256 of the key, as 64 hex digits or 32 raw bytes
+///
+/// ## Returns
+/// - {:ok, id}: Lowercase hex fingerprint, usable with `get/2`
+/// - {:error, :not_set}: The variable is unset or not valid UTF-8
+/// - {:error, :invalid_encoding}: Not hex, base64 or bech32
+/// - {:error, :invalid_length}: Decoded key is empty or over 1024 bytes
+/// - {:error, :fingerprint_mismatch}: Decoded key has a different fingerprint
+/// - Err: Malformed fingerprint, or the cache has been wiped
+#[rustler::nif]
+fn import_key_from_env(
+    cache: ResourceArc<KeyCacheResource>,
+    var: String,
+    expected_fingerprint: Binary,
+) -> Result<Result<String, Atom>, Error> {
+    let expected =
+        import::parse_fingerprint(expected_fingerprint.as_slice()).ok_or(Error::BadArg)?;
+
+    let key = match import::key_from_env(&var, &expected, MAX_KEY_BYTES) {
+        Ok(key) => key,
+        Err(e) => {
+            return Ok(Err(match e {
+                import::ImportError::NotSet => atoms::not_set(),
+                import::ImportError::InvalidEncoding => atoms::invalid_encoding(),
+                import::ImportError::InvalidLength => atoms::invalid_length(),
+                import::ImportError::FingerprintMismatch => atoms::fingerprint_mismatch(),
+            }))
+        }
+    };
+
+    let id: String = expected.iter().map(|b| format!("{:02x}", b)).collect();
+    if !cache.cache.put(id.as_bytes(), &key) {
+        return Err(Error::RaiseTerm(Box::new("key cache wiped")));
+    }
+    Ok(Ok(id))
+}
+
+/// Fetch a key
+///
+/// ## Parameters
+/// - cache: Resource from `new/1`
+/// - id: Key identifier
+///
+/// ## Returns
+/// - {:ok, key}
+/// - {:error, :locked}: No key under `id`, or it has expired
+#[rustler::nif]
+fn get<'a>(
+    env: Env<'a>,
+    cache: ResourceArc<KeyCacheResource>,
+    id: Binary,
+) -> Result<Binary<'a>, Atom> {
+    cache
+        .cache
+        .with_key(id.as_slice(), |key| copy_binary(env, key))
+        .ok_or_else(atoms::locked)
+}
+
+/// Wipe a single key
+///
+/// ## Returns
+/// - :ok (also when nothing was stored under `id`)
+#[rustler::nif]
+fn evict(cache: ResourceArc<KeyCacheResource>, id: Binary) -> Atom {
+    cache.cache.evict(id.as_slice());
+    atoms::ok()
+}
+
+/// Wipe every key immediately
+///
+/// ## Returns
+/// - :ok
+#[rustler::nif]
+fn lock(cache: ResourceArc<KeyCacheResource>) -> Atom {
+    cache.cache.lock();
+    atoms::ok()
+}
+
+/// Wipe every key and invalidate the cache immediately
+///
+/// Unlike `lock/1` the cache cannot be reused: later `put/3` calls raise
+/// and `get/2` always returns {:error, :locked}.
+///
+/// ## Returns
+/// - :ok
+#[rustler::nif]
+fn wipe(cache: ResourceArc<KeyCacheResource>) -> Atom {
+    cache.cache.close();
+    atoms::ok()
+}
+
+/// Report cache state
+///
+/// ## Returns
+/// - %{ttl_ms: ttl, wiped: bool, entries: [%{id: id, expires_in_ms: ms, mlocked: bool}]}
+///   with entries sorted by id. Key bytes are never included.
+#[rustler::nif]
+fn status<'a>(env: Env<'a>, cache: ResourceArc<KeyCacheResource>) -> CacheStatus<'a> {
+    let entries = cache
+        .cache
+        .status()
+        .into_iter()
+        .map(|e| EntryInfo {
+            id: copy_binary(env, &e.id),
+            expires_in_ms: e.expires_in.as_millis() as u64,
+            mlocked: e.locked,
+        })
+        .collect();
+
+    CacheStatus {
+        ttl_ms: cache.cache.ttl().as_millis() as u64,
+        wiped: cache.cache.is_closed(),
+        entries,
+    }
+}
+
+/// Current Unix time in seconds
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Decode an optional non-negative integer from a thresholds map
+fn threshold(map: Term, key: Atom) -> NifResult<Option<u64>> {
+    match map.map_get(key) {
+        Err(_) => Ok(None),
+        Ok(value) if value.decode::<Atom>().is_ok_and(|a| a == atoms::nil()) => Ok(None),
+        Ok(value) => value.decode().map(Some),
+    }
+}
+
+fn decode_thresholds(map: Term) -> NifResult<Thresholds> {
+    if !map.is_map() {
+        return Err(Error::BadArg);
+    }
+    Ok(Thresholds {
+        max_ops: threshold(map, atoms::max_ops())?,
+        max_bytes: threshold(map, atoms::max_bytes())?,
+        max_age_secs: threshold(map, atoms::max_age_s())?,
+    })
+}
+
+fn exceeded_atoms(exceeded: Exceeded) -> Vec<Atom> {
+    [
+        (exceeded.ops, atoms::ops()),
+        (exceeded.bytes, atoms::bytes()),
+        (exceeded.age, atoms::age()),
+    ]
+    .into_iter()
+    .filter_map(|(hit, atom)| hit.then_some(atom))
+    .collect()
+}
+
+/// Create a key usage tracker
+///
+/// ## Parameters
+/// - thresholds: `%{max_ops: n, max_bytes: n, max_age_s: n}`; missing or
+///   nil keys disable that limit
+///
+/// ## Returns
+/// - Tracker resource
+/// - Err: Thresholds are not a map of non-negative integers
+#[rustler::nif]
+fn usage_new(thresholds: Term) -> NifResult<ResourceArc<UsageResource>> {
+    Ok(ResourceArc::new(UsageResource {
+        tracker: Mutex::new(UsageTracker::new(decode_thresholds(thresholds)?)),
+    }))
+}
+
+/// Record one operation with a key
+///
+/// ## Parameters
+/// - tracker: Resource from `usage_new/1` or `usage_import/2`
+/// - id: Key identifier (e.g. fingerprint)
+/// - bytes: Bytes processed by this operation
+///
+/// ## Returns
+/// - :ok
+/// - {:warning, :rotation_recommended}: The key has passed a threshold;
+///   `usage_stats/1` says which. The use is still counted.
+#[rustler::nif]
+fn usage_record<'a>(
+    env: Env<'a>,
+    tracker: ResourceArc<UsageResource>,
+    id: Binary,
+    bytes: u64,
+) -> NifResult<Term<'a>> {
+    if id.len() > usage::MAX_ID_BYTES {
+        return Err(Error::BadArg);
+    }
+
+    let exceeded = tracker
+        .tracker
+        .lock()
+        .unwrap()
+        .record(id.as_slice(), bytes, unix_now());
+    Ok(if exceeded.any() {
+        (atoms::warning(), atoms::rotation_recommended()).encode(env)
+    } else {
+        atoms::ok().encode(env)
+    })
+}
+
+/// Report counters for every tracked key
+///
+/// ## Returns
+/// - [%{id: id, ops: n, bytes: n, age_s: n, exceeded: [:ops | :bytes | :age]}]
+///   sorted by id
+#[rustler::nif]
+fn usage_stats<'a>(env: Env<'a>, tracker: ResourceArc<UsageResource>) -> Vec<UsageInfo<'a>> {
+    let tracker = tracker.tracker.lock().unwrap();
+    let now = unix_now();
+    tracker
+        .entries()
+        .into_iter()
+        .map(|(id, usage)| UsageInfo {
+            id: copy_binary(env, id),
+            ops: usage.ops,
+            bytes: usage.bytes,
+            age_s: now.saturating_sub(usage.first_used),
+            exceeded: exceeded_atoms(tracker.check(&usage, now)),
+        })
+        .collect()
+}
+
+/// Forget a key's counters, e.g. after rotating it
+///
+/// ## Returns
+/// - :ok (also when the key was not tracked)
+#[rustler::nif]
+fn usage_reset(tracker: ResourceArc<UsageResource>, id: Binary) -> Atom {
+    tracker.tracker.lock().unwrap().reset(id.as_slice());
+    atoms::ok()
+}
+
+/// Serialize the counters for the caller's state file
+///
+/// ## Returns
+/// - Binary for `usage_import/2` (thresholds are not included)
+#[rustler::nif]
+fn usage_export<'a>(env: Env<'a>, tracker: ResourceArc<UsageResource>) -> Binary<'a> {
+    copy_binary(env, &tracker.tracker.lock().unwrap().export())
+}
+
+/// Restore a tracker from `usage_export/1` output
+///
+/// ## Parameters
+/// - state: Exported counters
+/// - thresholds: As for `usage_new/1`
+///
+/// ## Returns
+/// - {:ok, tracker}
+/// - {:error, :invalid_state}: The state is truncated or malformed
+/// - Err: Invalid thresholds
+#[rustler::nif]
+fn usage_import(
+    state: Binary,
+    thresholds: Term,
+) -> NifResult<Result<ResourceArc<UsageResource>, Atom>> {
+    let thresholds = decode_thresholds(thresholds)?;
+    Ok(UsageTracker::import(state.as_slice(), thresholds)
+        .map(|tracker| {
+            ResourceArc::new(UsageResource {
+                tracker: Mutex::new(tracker),
+            })
+        })
+        .ok_or_else(atoms::invalid_state))
+}
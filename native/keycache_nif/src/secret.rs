@@ -0,0 +1,147 @@
+//! Locked, self-wiping key buffer
+//!
+//! Key bytes live in a heap buffer that is `mlock`ed where the platform
+//! allows it (so it is never written to swap) and zeroized before the
+//! memory is released.
+//!
+//! Page locks are not reference counted: one `munlock` releases the whole
+//! page, whoever else locked it. Each key therefore gets a page-aligned
+//! allocation of its own, so unlocking it can never unlock another key.
+
+use std::alloc::{self, Layout};
+use std::ptr::NonNull;
+use zeroize::Zeroize;
+
+/// Key material held in locked memory
+pub struct LockedKey {
+    ptr: NonNull<u8>,
+    len: usize,
+    layout: Layout,
+    locked: bool,
+}
+
+// SAFETY: the allocation is owned exclusively by the `LockedKey` and only
+// mutated through `&mut self`
+unsafe impl Send for LockedKey {}
+unsafe impl Sync for LockedKey {}
+
+impl LockedKey {
+    /// Copy `key` into a freshly locked buffer
+    ///
+    /// Locking is best effort: if the process has exhausted its
+    /// RLIMIT_MEMLOCK the key is still stored, and `is_locked` reports false.
+    pub fn new(key: &[u8]) -> Self {
+        let page = page_size();
+        let size = key.len().max(1).next_multiple_of(page);
+        let layout = Layout::from_size_align(size, page).expect("page-sized layout");
+        // SAFETY: the layout has a non-zero size
+        let ptr = NonNull::new(unsafe { alloc::alloc_zeroed(layout) })
+            .unwrap_or_else(|| alloc::handle_alloc_error(layout));
+
+        let mut locked_key = LockedKey {
+            ptr,
+            len: key.len(),
+            layout,
+            locked: false,
+        };
+        // Lock before copying so the key never sits in swappable memory
+        locked_key.locked = lock_pages(locked_key.pages());
+        locked_key.pages_mut()[..key.len()].copy_from_slice(key);
+        locked_key
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.pages()[..self.len]
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// The whole allocation, key plus zero padding up to the page boundary
+    fn pages(&self) -> &[u8] {
+        // SAFETY: `ptr` is a live allocation of `layout.size()` bytes
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.layout.size()) }
+    }
+
+    fn pages_mut(&mut self) -> &mut [u8] {
+        // SAFETY: as in `pages`, and `&mut self` makes the access exclusive
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.layout.size()) }
+    }
+}
+
+impl Drop for LockedKey {
+    fn drop(&mut self) {
+        self.pages_mut().zeroize();
+        if self.locked {
+            unlock_pages(self.pages());
+        }
+        // SAFETY: allocated in `new` with this same layout
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
+
+#[cfg(unix)]
+fn page_size() -> usize {
+    // SAFETY: sysconf has no preconditions
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 {
+        size as usize
+    } else {
+        4096
+    }
+}
+
+#[cfg(not(unix))]
+fn page_size() -> usize {
+    4096
+}
+
+#[cfg(unix)]
+fn lock_pages(buf: &[u8]) -> bool {
+    if buf.is_empty() {
+        return false;
+    }
+    // SAFETY: the range is a live allocation owned by the caller
+    unsafe { libc::mlock(buf.as_ptr() as *const libc::c_void, buf.len()) == 0 }
+}
+
+#[cfg(unix)]
+fn unlock_pages(buf: &[u8]) {
+    // SAFETY: the range was locked by `lock_pages` and is still allocated
+    unsafe {
+        libc::munlock(buf.as_ptr() as *const libc::c_void, buf.len());
+    }
+}
+
+#[cfg(not(unix))]
+fn lock_pages(_buf: &[u8]) -> bool {
+    false
+}
+
+#[cfg(not(unix))]
+fn unlock_pages(_buf: &[u8]) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_do_not_share_pages() {
+        let page = page_size();
+        let a = LockedKey::new(&[1u8; 32]);
+        let b = LockedKey::new(&[2u8; 32]);
+        for key in [&a, &b] {
+            assert_eq!(key.as_slice().as_ptr() as usize % page, 0);
+        }
+        assert_eq!(a.as_slice(), &[1u8; 32]);
+        assert_eq!(b.as_slice(), &[2u8; 32]);
+    }
+
+    #[test]
+    fn test_long_and_empty_keys() {
+        let long = vec![7u8; page_size() + 1];
+        assert_eq!(LockedKey::new(&long).as_slice(), &long[..]);
+        assert!(LockedKey::new(&[]).as_slice().is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+//! Per-key usage counters and rotation thresholds
+//!
+//! Counts operations and bytes per key id and remembers when each key was
+//! first used. When a key passes any configured threshold, recording
+//! another use reports that rotation is due; the counters keep going, the
+//! warning is advisory.
+//!
+//! Pick thresholds from the nonce scheme, not just policy: AES-GCM with
+//! random 96-bit nonces should stop well before 2^32 messages per key.
+//!
+//! The tracker lives in memory. `export`/`import` give a compact binary
+//! form for the caller to keep in its state file across runs.
+
+use std::collections::HashMap;
+
+/// Limits after which a key should be rotated; `None` disables a limit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Thresholds {
+    pub max_ops: Option<u64>,
+    pub max_bytes: Option<u64>,
+    pub max_age_secs: Option<u64>,
+}
+
+/// Counters for one key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Usage {
+    pub ops: u64,
+    pub bytes: u64,
+    /// Unix time of the first recorded use (seconds)
+    pub first_used: u64,
+}
+
+/// Thresholds a key has passed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Exceeded {
+    pub ops: bool,
+    pub bytes: bool,
+    pub age: bool,
+}
+
+impl Exceeded {
+    pub fn any(&self) -> bool {
+        self.ops || self.bytes || self.age
+    }
+}
+
+/// Magic and version of the exported form
+const EXPORT_MAGIC: &[u8; 4] = b"GFU1";
+
+/// Longest key id accepted (the export stores the length as u16)
+pub const MAX_ID_BYTES: usize = u16::MAX as usize;
+
+/// Usage counters for a set of keys
+pub struct UsageTracker {
+    thresholds: Thresholds,
+    keys: HashMap<Vec<u8>, Usage>,
+}
+
+impl UsageTracker {
+    pub fn new(thresholds: Thresholds) -> Self {
+        UsageTracker {
+            thresholds,
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Record one operation over `bytes` bytes with key `id` at time `now`
+    ///
+    /// Counters saturate rather than wrap.
+    pub fn record(&mut self, id: &[u8], bytes: u64, now: u64) -> Exceeded {
+        let usage = self.keys.entry(id.to_vec()).or_insert(Usage {
+            ops: 0,
+            bytes: 0,
+            first_used: now,
+        });
+        usage.ops = usage.ops.saturating_add(1);
+        usage.bytes = usage.bytes.saturating_add(bytes);
+
+        let usage = *usage;
+        self.check(&usage, now)
+    }
+
+    /// Which thresholds `usage` has passed at time `now`
+    pub fn check(&self, usage: &Usage, now: u64) -> Exceeded {
+        let over = |limit: Option<u64>, value: u64| limit.is_some_and(|limit| value > limit);
+        Exceeded {
+            ops: over(self.thresholds.max_ops, usage.ops),
+            bytes: over(self.thresholds.max_bytes, usage.bytes),
+            age: over(
+                self.thresholds.max_age_secs,
+                now.saturating_sub(usage.first_used),
+            ),
+        }
+    }
+
+    /// Forget a key's counters (after rotating it)
+    pub fn reset(&mut self, id: &[u8]) -> bool {
+        self.keys.remove(id).is_some()
+    }
+
+    /// All counters, sorted by id
+    pub fn entries(&self) -> Vec<(&[u8], Usage)> {
+        let mut entries: Vec<(&[u8], Usage)> = self
+            .keys
+            .iter()
+            .map(|(id, usage)| (id.as_slice(), *usage))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries
+    }
+
+    /// Serialize the counters (thresholds are not included)
+    ///
+    /// Layout: "GFU1", u32 count, then per key u16 id length, id, u64 ops,
+    /// u64 bytes, u64 first_used; integers big-endian, keys sorted by id.
+    pub fn export(&self) -> Vec<u8> {
+        let entries = self.entries();
+        let mut out = Vec::with_capacity(8 + entries.len() * 40);
+        out.extend_from_slice(EXPORT_MAGIC);
+        out.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        for (id, usage) in entries {
+            out.extend_from_slice(&(id.len() as u16).to_be_bytes());
+            out.extend_from_slice(id);
+            out.extend_from_slice(&usage.ops.to_be_bytes());
+            out.extend_from_slice(&usage.bytes.to_be_bytes());
+            out.extend_from_slice(&usage.first_used.to_be_bytes());
+        }
+        out
+    }
+
+    /// Rebuild a tracker from `export` output; `None` if it is malformed
+    pub fn import(data: &[u8], thresholds: Thresholds) -> Option<Self> {
+        let mut reader = Reader(data);
+        if reader.take(4)? != EXPORT_MAGIC {
+            return None;
+        }
+
+        let count = u32::from_be_bytes(reader.take(4)?.try_into().ok()?);
+        let mut tracker = UsageTracker::new(thresholds);
+        for _ in 0..count {
+            let id_len = u16::from_be_bytes(reader.take(2)?.try_into().ok()?);
+            let id = reader.take(id_len as usize)?.to_vec();
+            let usage = Usage {
+                ops: reader.u64()?,
+                bytes: reader.u64()?,
+                first_used: reader.u64()?,
+            };
+            if tracker.keys.insert(id, usage).is_some() {
+                return None;
+            }
+        }
+
+        reader.0.is_empty().then_some(tracker)
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_be_bytes(self.take(8)?.try_into().ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> Thresholds {
+        Thresholds {
+            max_ops: Some(2),
+            max_bytes: Some(100),
+            max_age_secs: Some(60),
+        }
+    }
+
+    #[test]
+    fn test_counts_and_thresholds() {
+        let mut tracker = UsageTracker::new(limits());
+        assert!(!tracker.record(b"k", 10, 1000).any());
+        assert!(!tracker.record(b"k", 10, 1000).any());
+
+        let over = tracker.record(b"k", 10, 1000);
+        assert_eq!(
+            over,
+            Exceeded {
+                ops: true,
+                bytes: false,
+                age: false
+            }
+        );
+
+        let over = tracker.record(b"other", 101, 1000);
+        assert!(over.bytes && !over.ops);
+
+        let usage = tracker.entries()[0].1;
+        assert_eq!((usage.ops, usage.bytes, usage.first_used), (3, 30, 1000));
+    }
+
+    #[test]
+    fn test_age_threshold() {
+        let mut tracker = UsageTracker::new(limits());
+        tracker.record(b"k", 0, 1000);
+        let usage = tracker.entries()[0].1;
+        assert!(!tracker.check(&usage, 1060).age);
+        assert!(tracker.check(&usage, 1061).age);
+    }
+
+    #[test]
+    fn test_no_thresholds_never_warn() {
+        let mut tracker = UsageTracker::new(Thresholds::default());
+        for _ in 0..10 {
+            assert!(!tracker.record(b"k", u64::MAX, 0).any());
+        }
+        assert_eq!(tracker.entries()[0].1.bytes, u64::MAX);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut tracker = UsageTracker::new(limits());
+        tracker.record(b"k", 1, 0);
+        assert!(tracker.reset(b"k"));
+        assert!(!tracker.reset(b"k"));
+        assert!(tracker.entries().is_empty());
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let mut tracker = UsageTracker::new(limits());
+        tracker.record(b"b", 5, 20);
+        tracker.record(b"a", 7, 10);
+        tracker.record(b"a", 1, 30);
+
+        let exported = tracker.export();
+        let restored = UsageTracker::import(&exported, limits()).unwrap();
+        assert_eq!(restored.entries(), tracker.entries());
+        assert_eq!(restored.export(), exported);
+    }
+
+    #[test]
+    fn test_import_rejects_malformed() {
+        let mut tracker = UsageTracker::new(limits());
+        tracker.record(b"a", 1, 1);
+        let exported = tracker.export();
+
+        assert!(UsageTracker::import(b"", limits()).is_none());
+        assert!(UsageTracker::import(b"XXXX\0\0\0\0", limits()).is_none());
+        assert!(UsageTracker::import(&exported[..exported.len() - 1], limits()).is_none());
+
+        let mut trailing = exported.clone();
+        trailing.push(0);
+        assert!(UsageTracker::import(&trailing, limits()).is_none());
+
+        // Same id twice
+        let mut duplicate = exported.clone();
+        duplicate[7] = 2;
+        duplicate.extend_from_slice(&exported[8..]);
+        assert!(UsageTracker::import(&duplicate, limits()).is_none());
+    }
+}
@@ -0,0 +1,155 @@
+//! Kuznyechik-MGM NIF for GitFoil
+//!
+//! Provides GOST Kuznyechik in Multilinear Galois Mode via Rustler NIF, for
+//! deployments that must use Russian national algorithms.
+//!
+//! **Algorithm:** Kuznyechik (GOST R 34.12-2015) in MGM (RFC 9058)
+//! - Key size: 256 bits (32 bytes)
+//! - Nonce size: 127 bits (16 bytes, most significant bit must be 0)
+//! - Tag size: 128 bits (16 bytes)
+//!
+//! Every NIF that takes a nonce, and STREAM's nonce prefix, rejects a
+//! nonce with the most significant bit set.
+
+use kuznyechik::Kuznyechik;
+use mgm::Mgm;
+use nif_support::cipher_nifs::{cipher, BackendInfo};
+use nif_support::stream::SegmentCipher;
+use rustler::Error;
+
+pub mod bench;
+
+rustler::init!("Elixir.GitFoil.Native.KuznyechikMgmNif");
+
+mod atoms {
+    rustler::atoms! {
+        sse2,
+        soft,
+    }
+}
+
+type KuznyechikMgm = Mgm<Kuznyechik>;
+
+/// Kuznyechik-MGM under one key, the cipher behind the shared NIFs
+///
+/// MGM encrypts the nonce to derive its counters with the top bit forced,
+/// so a nonce with the top bit set is rejected rather than silently
+/// colliding with another one.
+struct KuznyechikMgmKey(KuznyechikMgm);
+
+impl KuznyechikMgmKey {
+    fn new(key: &[u8]) -> Result<Self, Error> {
+        cipher::<KuznyechikMgm>(key).map(KuznyechikMgmKey)
+    }
+}
+
+impl SegmentCipher for KuznyechikMgmKey {
+    const NONCE_SIZE: usize = 16;
+    const TAG_SIZE: usize = 16;
+
+    fn valid_nonce(nonce: &[u8]) -> bool {
+        nonce.len() == Self::NONCE_SIZE && nonce[0] & 0x80 == 0
+    }
+
+    fn seal_in_place(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8]) -> Option<Vec<u8>> {
+        self.0.seal_in_place(nonce, aad, buffer)
+    }
+
+    fn open_in_place(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8], tag: &[u8]) -> bool {
+        self.0.open_in_place(nonce, aad, buffer, tag)
+    }
+}
+
+// encrypt/decrypt, the batch, blob, context and file NIFs, STREAM in all
+// its forms, benchmark/2 and fastest_algorithms/1
+nif_support::cipher_nifs! {
+    cipher: KuznyechikMgmKey,
+    name: "Kuznyechik-MGM",
+    new: KuznyechikMgmKey::new,
+    sizes: { key: 32, nonce: 16, tag: 16, prefix: 11 },
+    subjects: bench::subjects,
+}
+
+/// Report which hardware path Kuznyechik-MGM takes on the running CPU
+///
+/// The `kuznyechik` crate picks its SSE2 table implementation at compile
+/// time; SSE2 is part of the x86-64 baseline, so x86-64 builds always use
+/// it and other targets run the portable one.
+///
+/// Returns:
+/// - %{backend: atom, features: [atom]} where backend is :sse2 or :soft,
+///   and features lists the CPU features it uses
+#[rustler::nif]
+fn backend_info() -> BackendInfo {
+    if cfg!(all(target_arch = "x86_64", target_feature = "sse2")) {
+        BackendInfo {
+            backend: atoms::sse2(),
+            features: vec![atoms::sse2()],
+        }
+    } else {
+        BackendInfo {
+            backend: atoms::soft(),
+            features: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mgm::aead::{generic_array::GenericArray, Aead, KeyInit, Payload};
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// RFC 9058, Appendix A (Kuznyechik example)
+    #[test]
+    fn test_rfc9058_kat() {
+        let key = unhex("8899aabbccddeeff0011223344556677fedcba98765432100123456789abcdef");
+        let nonce = unhex("1122334455667700ffeeddccbbaa9988");
+        let aad = unhex(
+            "0202020202020202010101010101010104040404040404040303030303030303\
+             ea0505050505050505",
+        );
+        let plaintext = unhex(
+            "1122334455667700ffeeddccbbaa998800112233445566778899aabbcceeff0a\
+             112233445566778899aabbcceeff0a002233445566778899aabbcceeff0a0011\
+             aabbcc",
+        );
+        let expected = unhex(
+            "a9757b8147956e9055b8a33de89f42fc8075d2212bf9fd5bd3f7069aadc16b39\
+             497ab15915a6ba85936b5d0ea9f6851cc60c14d4d3f883d0ab94420695c76deb\
+             2c7552cf5d656f40c34f5c46e8bb0e29fcdb4c",
+        );
+
+        let cipher = KuznyechikMgm::new(GenericArray::from_slice(&key));
+        let payload = Payload {
+            msg: &plaintext,
+            aad: &aad,
+        };
+        let ciphertext = cipher
+            .encrypt(GenericArray::from_slice(&nonce), payload)
+            .unwrap();
+        assert_eq!(ciphertext, expected);
+
+        let payload = Payload {
+            msg: &expected,
+            aad: &aad,
+        };
+        let decrypted = cipher
+            .decrypt(GenericArray::from_slice(&nonce), payload)
+            .unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_top_bit_nonce_rejected() {
+        assert!(KuznyechikMgmKey::valid_nonce(&[0x7f; 16]));
+        assert!(!KuznyechikMgmKey::valid_nonce(&[0x80; 16]));
+        assert!(!KuznyechikMgmKey::valid_nonce(&[0x00; 15]));
+    }
+}
@@ -0,0 +1,118 @@
+//! Merkle tree NIF for GitFoil
+//!
+//! Builds a repo-wide integrity root over (path, digest) pairs and proves
+//! or checks that one file is covered by it, so a signed root can be
+//! checked one file at a time.
+//!
+//! **Functions:**
+//! - `root/1`: root of the tree over a list of `{path, digest}`
+//! - `prove/2`: inclusion proof for one path
+//! - `verify/4`: check a path, digest and proof against a root
+//!
+//! See `merkle` for the tree layout (RFC 9162 shape, SHA-256). Building
+//! hashes every entry, so `root/1` and `prove/2` run on dirty CPU
+//! schedulers.
+
+mod merkle;
+
+use merkle::{MerkleError, Proof, Tree};
+use rustler::{Atom, Binary, Env, Error, NifResult, OwnedBinary};
+
+rustler::init!("Elixir.GitFoil.Native.MerkleNif");
+
+mod atoms {
+    rustler::atoms! {
+        duplicate_path,
+        invalid_entry,
+        not_found,
+    }
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env)
+}
+
+fn build(entries: Vec<(Binary, Binary)>) -> Result<Tree, Atom> {
+    let entries = entries
+        .into_iter()
+        .map(|(path, digest)| (path.as_slice().to_vec(), digest.as_slice().to_vec()))
+        .collect();
+    Tree::build(entries).map_err(|e| match e {
+        MerkleError::DuplicatePath => atoms::duplicate_path(),
+        MerkleError::InvalidEntry => atoms::invalid_entry(),
+    })
+}
+
+fn hash_from(binary: &Binary) -> NifResult<merkle::Hash> {
+    binary.as_slice().try_into().map_err(|_| Error::BadArg)
+}
+
+/// Compute the Merkle root
+///
+/// ## Parameters
+/// - entries: List of `{path, digest}`; order doesn't matter. Paths are
+///   1..=4096 bytes, digests 1..=64 bytes.
+///
+/// ## Returns
+/// - {:ok, root}: 32-byte root
+/// - {:error, :duplicate_path | :invalid_entry}
+#[rustler::nif(schedule = "DirtyCpu")]
+fn root<'a>(env: Env<'a>, entries: Vec<(Binary, Binary)>) -> Result<Binary<'a>, Atom> {
+    let tree = build(entries)?;
+    Ok(to_binary(env, &tree.root()))
+}
+
+/// Build an inclusion proof for one path
+///
+/// ## Parameters
+/// - entries: As for `root/1`
+/// - path: Path to prove
+///
+/// ## Returns
+/// - {:ok, {index, size, siblings}}: `siblings` is a list of 32-byte
+///   hashes, bottom-up
+/// - {:error, :not_found | :duplicate_path | :invalid_entry}
+#[rustler::nif(schedule = "DirtyCpu")]
+fn prove<'a>(
+    env: Env<'a>,
+    entries: Vec<(Binary, Binary)>,
+    path: Binary,
+) -> Result<(u64, u64, Vec<Binary<'a>>), Atom> {
+    let tree = build(entries)?;
+    let proof = tree.prove(path.as_slice()).ok_or_else(atoms::not_found)?;
+    let siblings = proof.path.iter().map(|h| to_binary(env, h)).collect();
+    Ok((proof.index, proof.size, siblings))
+}
+
+/// Check an inclusion proof
+///
+/// ## Parameters
+/// - root: 32-byte root
+/// - path, digest: The entry being checked
+/// - proof: `{index, size, siblings}` from `prove/2`
+///
+/// ## Returns
+/// - true if the entry is in the tree with that root, false otherwise
+/// - Err: `root` or a sibling is not 32 bytes
+#[rustler::nif]
+fn verify(
+    root: Binary,
+    path: Binary,
+    digest: Binary,
+    proof: (u64, u64, Vec<Binary>),
+) -> NifResult<bool> {
+    let (index, size, siblings) = proof;
+    let proof = Proof {
+        index,
+        size,
+        path: siblings.iter().map(hash_from).collect::<NifResult<_>>()?,
+    };
+    Ok(merkle::verify(
+        &hash_from(&root)?,
+        path.as_slice(),
+        digest.as_slice(),
+        &proof,
+    ))
+}
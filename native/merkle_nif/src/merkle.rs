@@ -0,0 +1,317 @@
+//! Merkle tree over (path, digest) pairs
+//!
+//! The tree shape and proof format follow RFC 9162 (Certificate
+//! Transparency v2), section 2.1, with SHA-256:
+//!
+//! - Leaves are sorted by path, so the root depends only on the set of
+//!   pairs, not on the order they were listed in
+//! - Leaf hash: SHA-256(0x00 || u32 path length || path || u32 digest
+//!   length || digest), lengths big-endian
+//! - Node hash: SHA-256(0x01 || left || right)
+//! - A level with an odd number of nodes is split at the largest power of
+//!   two, never padded by duplicating a node, so two different sets of
+//!   leaves can't share a root
+//! - The empty tree's root is SHA-256 of the empty string
+//!
+//! An inclusion proof is the leaf index, the tree size and the audit path
+//! (sibling hashes, bottom-up).
+
+use sha2::{Digest, Sha256};
+
+pub type Hash = [u8; 32];
+
+/// Longest path and leaf digest accepted (bytes)
+pub const MAX_PATH_BYTES: usize = 4096;
+pub const MAX_DIGEST_BYTES: usize = 64;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MerkleError {
+    /// The same path appears twice
+    DuplicatePath,
+    /// A path or digest is empty or too long
+    InvalidEntry,
+}
+
+/// Inclusion proof for one leaf
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    pub index: u64,
+    pub size: u64,
+    pub path: Vec<Hash>,
+}
+
+fn sha256(parts: &[&[u8]]) -> Hash {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&hasher.finalize()[..32]);
+    out
+}
+
+pub fn leaf_hash(path: &[u8], digest: &[u8]) -> Hash {
+    sha256(&[
+        &[0x00],
+        &(path.len() as u32).to_be_bytes(),
+        path,
+        &(digest.len() as u32).to_be_bytes(),
+        digest,
+    ])
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    sha256(&[&[0x01], left, right])
+}
+
+/// Largest power of two strictly less than `n` (n >= 2)
+fn split(n: usize) -> usize {
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+/// Merkle tree hash of a run of leaf hashes
+fn subtree_root(leaves: &[Hash]) -> Hash {
+    match leaves.len() {
+        0 => sha256(&[]),
+        1 => leaves[0],
+        n => {
+            let k = split(n);
+            node_hash(&subtree_root(&leaves[..k]), &subtree_root(&leaves[k..]))
+        }
+    }
+}
+
+/// Audit path for leaf `m` within `leaves`, bottom-up
+fn audit_path(m: usize, leaves: &[Hash], out: &mut Vec<Hash>) {
+    let n = leaves.len();
+    if n <= 1 {
+        return;
+    }
+    let k = split(n);
+    if m < k {
+        audit_path(m, &leaves[..k], out);
+        out.push(subtree_root(&leaves[k..]));
+    } else {
+        audit_path(m - k, &leaves[k..], out);
+        out.push(subtree_root(&leaves[..k]));
+    }
+}
+
+/// A built tree: paths in sorted order with their leaf hashes
+pub struct Tree {
+    paths: Vec<Vec<u8>>,
+    leaves: Vec<Hash>,
+}
+
+impl Tree {
+    pub fn build(mut entries: Vec<(Vec<u8>, Vec<u8>)>) -> Result<Tree, MerkleError> {
+        for (path, digest) in &entries {
+            if path.is_empty()
+                || path.len() > MAX_PATH_BYTES
+                || digest.is_empty()
+                || digest.len() > MAX_DIGEST_BYTES
+            {
+                return Err(MerkleError::InvalidEntry);
+            }
+        }
+
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        if entries.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(MerkleError::DuplicatePath);
+        }
+
+        let leaves = entries
+            .iter()
+            .map(|(path, digest)| leaf_hash(path, digest))
+            .collect();
+        let paths = entries.into_iter().map(|(path, _)| path).collect();
+        Ok(Tree { paths, leaves })
+    }
+
+    pub fn root(&self) -> Hash {
+        subtree_root(&self.leaves)
+    }
+
+    /// Inclusion proof for `path`, or `None` if it isn't in the tree
+    pub fn prove(&self, path: &[u8]) -> Option<Proof> {
+        let index = self
+            .paths
+            .binary_search_by(|p| p.as_slice().cmp(path))
+            .ok()?;
+
+        let mut audit = Vec::new();
+        audit_path(index, &self.leaves, &mut audit);
+        Some(Proof {
+            index: index as u64,
+            size: self.leaves.len() as u64,
+            path: audit,
+        })
+    }
+}
+
+/// Check that (`path`, `digest`) is in the tree with root `root`
+///
+/// RFC 9162 section 2.1.3.2.
+pub fn verify(root: &Hash, path: &[u8], digest: &[u8], proof: &Proof) -> bool {
+    if proof.index >= proof.size {
+        return false;
+    }
+
+    let mut fnode = proof.index;
+    let mut snode = proof.size - 1;
+    let mut r = leaf_hash(path, digest);
+
+    for p in &proof.path {
+        if snode == 0 {
+            return false;
+        }
+        if fnode & 1 == 1 || fnode == snode {
+            r = node_hash(p, &r);
+            while fnode & 1 == 0 && fnode != 0 {
+                fnode >>= 1;
+                snode >>= 1;
+            }
+        } else {
+            r = node_hash(&r, p);
+        }
+        fnode >>= 1;
+        snode >>= 1;
+    }
+
+    snode == 0 && r == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(hash: &Hash) -> String {
+        hash.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Paths src/0.txt .. src/4.txt with digest SHA-256("0") .. SHA-256("4")
+    fn entries(n: usize) -> Vec<(Vec<u8>, Vec<u8>)> {
+        (0..n)
+            .map(|i| {
+                let path = format!("src/{}.txt", i).into_bytes();
+                let digest = sha256(&[i.to_string().as_bytes()]).to_vec();
+                (path, digest)
+            })
+            .collect()
+    }
+
+    /// Roots cross-checked against an independent Python implementation
+    #[test]
+    fn test_known_roots() {
+        let expected = [
+            (
+                0,
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                1,
+                "2f099f431ca0fae4cad4177c5ef83db1957e06d2a0c64589bdee4a48427c7057",
+            ),
+            (
+                2,
+                "de93dc36cd765bf8fb6e72190cc34c1bfe5d81e06f22a6117689807532fbd1c6",
+            ),
+            (
+                3,
+                "ee841fa85be83c8d0724be2d4c892013d33e93f8e76e799b55185ff8541aa413",
+            ),
+            (
+                5,
+                "f3e95286a5be74fb48849094a88d50e5d093fe582efca23207a44fcc152cd8a9",
+            ),
+        ];
+        for (n, root) in expected {
+            assert_eq!(hex(&Tree::build(entries(n)).unwrap().root()), root);
+        }
+    }
+
+    #[test]
+    fn test_root_ignores_input_order() {
+        let mut reversed = entries(5);
+        reversed.reverse();
+        assert_eq!(
+            Tree::build(reversed).unwrap().root(),
+            Tree::build(entries(5)).unwrap().root()
+        );
+    }
+
+    #[test]
+    fn test_every_proof_verifies() {
+        for n in 1..=17 {
+            let list = entries(n);
+            let tree = Tree::build(list.clone()).unwrap();
+            let root = tree.root();
+            for (path, digest) in &list {
+                let proof = tree.prove(path).unwrap();
+                assert!(
+                    verify(&root, path, digest, &proof),
+                    "n={} path={:?}",
+                    n,
+                    path
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_tampered_proofs_fail() {
+        let list = entries(7);
+        let tree = Tree::build(list.clone()).unwrap();
+        let root = tree.root();
+        let (path, digest) = &list[3];
+        let proof = tree.prove(path).unwrap();
+
+        assert!(!verify(&root, path, b"other digest", &proof));
+        assert!(!verify(&root, b"src/other.txt", digest, &proof));
+
+        let mut wrong_index = proof.clone();
+        wrong_index.index = 2;
+        assert!(!verify(&root, path, digest, &wrong_index));
+
+        let mut wrong_size = proof.clone();
+        wrong_size.size = 4;
+        assert!(!verify(&root, path, digest, &wrong_size));
+
+        let mut short = proof.clone();
+        short.path.pop();
+        assert!(!verify(&root, path, digest, &short));
+
+        let mut flipped = proof;
+        flipped.path[0][0] ^= 1;
+        assert!(!verify(&root, path, digest, &flipped));
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let mut list = entries(3);
+        list.push(list[1].clone());
+        assert_eq!(Tree::build(list).err(), Some(MerkleError::DuplicatePath));
+
+        let empty_path = vec![(Vec::new(), vec![1u8; 32])];
+        assert_eq!(
+            Tree::build(empty_path).err(),
+            Some(MerkleError::InvalidEntry)
+        );
+
+        let long_digest = vec![(b"a".to_vec(), vec![1u8; 65])];
+        assert_eq!(
+            Tree::build(long_digest).err(),
+            Some(MerkleError::InvalidEntry)
+        );
+    }
+
+    #[test]
+    fn test_missing_path_has_no_proof() {
+        let tree = Tree::build(entries(4)).unwrap();
+        assert!(tree.prove(b"src/9.txt").is_none());
+    }
+}
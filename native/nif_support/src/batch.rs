@@ -0,0 +1,94 @@
+//! Many independent messages under one key, in parallel
+//!
+//! `encrypt_many/2` and `decrypt_many/2` spread their items over rayon's
+//! global thread pool. Every item is a separate message with its own nonce,
+//! sealed exactly as `encrypt/4` would seal it, and results come back in
+//! input order.
+
+use crate::stream::SegmentCipher;
+use rayon::prelude::*;
+
+/// Nonce, plaintext and AAD of one message to seal
+pub type SealItem<'a> = (&'a [u8], &'a [u8], &'a [u8]);
+
+/// Nonce, ciphertext, tag and AAD of one message to open
+pub type OpenItem<'a> = (&'a [u8], &'a [u8], &'a [u8], &'a [u8]);
+
+/// Seal every item, returning `(ciphertext, tag)` pairs
+///
+/// Nonces must be `C::NONCE_SIZE` bytes.
+pub fn seal_many<C: SegmentCipher + Sync>(
+    cipher: &C,
+    items: &[SealItem],
+) -> Vec<(Vec<u8>, Vec<u8>)> {
+    items
+        .par_iter()
+        .map(|&(nonce, plaintext, aad)| {
+            let mut ciphertext = cipher.seal(nonce, plaintext, aad);
+            let tag = ciphertext.split_off(ciphertext.len() - C::TAG_SIZE);
+            (ciphertext, tag)
+        })
+        .collect()
+}
+
+/// Open every item, or `None` if any of them fails authentication
+///
+/// Nonces must be `C::NONCE_SIZE` bytes.
+pub fn open_many<C: SegmentCipher + Sync>(cipher: &C, items: &[OpenItem]) -> Option<Vec<Vec<u8>>> {
+    items
+        .par_iter()
+        .map(|&(nonce, ciphertext, tag, aad)| cipher.open(nonce, &[ciphertext, tag].concat(), aad))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::tests::{message, Toy};
+
+    fn nonce(i: u8) -> Vec<u8> {
+        vec![i; Toy::NONCE_SIZE]
+    }
+
+    #[test]
+    fn test_batch_matches_single() {
+        let nonces: Vec<_> = (0..5).map(nonce).collect();
+        let messages: Vec<_> = (0..5).map(|i| message(i * 1000)).collect();
+        let items: Vec<SealItem> = (0..5)
+            .map(|i| (&nonces[i][..], &messages[i][..], &b"aad"[..]))
+            .collect();
+
+        let sealed = seal_many(&Toy, &items);
+        for (i, (ciphertext, tag)) in sealed.iter().enumerate() {
+            let single = Toy.seal(&nonces[i], &messages[i], b"aad");
+            assert_eq!([&ciphertext[..], &tag[..]].concat(), single);
+        }
+
+        let items: Vec<OpenItem> = (0..5)
+            .map(|i| {
+                (
+                    &nonces[i][..],
+                    &sealed[i].0[..],
+                    &sealed[i].1[..],
+                    &b"aad"[..],
+                )
+            })
+            .collect();
+        assert_eq!(open_many(&Toy, &items), Some(messages));
+    }
+
+    #[test]
+    fn test_one_bad_item_fails_batch() {
+        let (good, bad) = (nonce(1), nonce(2));
+        let plaintext = message(100);
+        let sealed = seal_many(&Toy, &[(&good, &plaintext, b"aad")]);
+        let (ciphertext, tag) = &sealed[0];
+
+        let items = [
+            (&good[..], &ciphertext[..], &tag[..], &b"aad"[..]),
+            (&bad[..], &ciphertext[..], &tag[..], &b"aad"[..]),
+        ];
+        assert_eq!(open_many(&Toy, &items), None);
+        assert!(open_many(&Toy, &[]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+//! Throughput measurement, shared by `benchmark/2`, `fastest_algorithms/1`
+//! and the criterion benches
+//!
+//! A cipher crate lists the algorithms it exposes as `Subject`s under a
+//! fixed all-zero key and nonce; only the speed matters, not the
+//! ciphertext. `seal_bench!` turns that list into a criterion bench.
+
+use aead::{AeadInPlace, Key, KeyInit, Nonce};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Encrypts a buffer in place under the subject's key and nonce
+type SealFn = dyn Fn(&mut [u8]);
+
+/// An algorithm ready to encrypt buffers in place
+pub struct Subject {
+    /// Name reported by `benchmark/2`
+    pub name: &'static str,
+    /// Security level in bits: the key length, which bounds key search
+    pub security: u32,
+    seal: Box<SealFn>,
+}
+
+impl Subject {
+    /// An algorithm that encrypts with `seal`, discarding the tag
+    pub fn new(
+        name: &'static str,
+        security: u32,
+        seal: impl Fn(&mut [u8]) + 'static,
+    ) -> Self {
+        Subject {
+            name,
+            security,
+            seal: Box::new(seal),
+        }
+    }
+
+    /// Encrypt `buffer` in place, discarding the tag
+    pub fn seal(&self, buffer: &mut [u8]) {
+        (self.seal)(buffer)
+    }
+}
+
+/// An `AeadInPlace` cipher under an all-zero key and nonce
+pub fn aead<C>(name: &'static str, security: u32) -> Subject
+where
+    C: AeadInPlace + KeyInit + 'static,
+{
+    let cipher = C::new(&Key::<C>::default());
+    let nonce = Nonce::<C>::default();
+    Subject::new(name, security, move |buffer| {
+        cipher
+            .encrypt_in_place_detached(&nonce, b"", buffer)
+            .expect("message too long");
+    })
+}
+
+/// Seal `size`-byte messages back to back for about `duration`
+///
+/// Returns MB/s (10^6 bytes per second). One untimed message first warms
+/// the caches.
+pub fn throughput(subject: &Subject, size: usize, duration: Duration) -> f64 {
+    let mut buffer = vec![0u8; size];
+    subject.seal(&mut buffer);
+
+    let start = Instant::now();
+    let mut bytes = 0u64;
+    loop {
+        subject.seal(&mut buffer);
+        bytes += size as u64;
+        let elapsed = start.elapsed();
+        if elapsed >= duration {
+            return bytes as f64 / elapsed.as_secs_f64() / 1e6;
+        }
+    }
+}
+
+/// Message size for the calibration behind `fastest_algorithms/1`
+const CALIBRATION_SIZE: usize = 16 * 1024;
+
+/// Measuring time per sample for that calibration
+const CALIBRATION_TIME: Duration = Duration::from_millis(20);
+
+/// Samples per algorithm; the median is kept, so one preempted sample
+/// cannot reorder the ranking
+const CALIBRATION_SAMPLES: usize = 5;
+
+/// One algorithm's calibrated speed
+pub struct Measurement {
+    pub name: &'static str,
+    pub security: u32,
+    /// MB/s sealing `CALIBRATION_SIZE`-byte messages
+    pub mbps: f64,
+}
+
+/// Every cipher crate links its own copy of this crate, so each NIF
+/// library calibrates its own subjects once
+static CALIBRATION: OnceLock<Vec<Measurement>> = OnceLock::new();
+
+/// Throughput of every subject, measured on first use and then cached
+///
+/// The first caller runs the measurement on its own thread (a dirty
+/// scheduler, for `fastest_algorithms/1`); callers that arrive while it
+/// runs wait for it. A library always passes the same `subjects`.
+pub fn calibration(subjects: fn() -> Vec<Subject>) -> &'static [Measurement] {
+    CALIBRATION.get_or_init(|| {
+        subjects()
+            .iter()
+            .map(|subject| Measurement {
+                name: subject.name,
+                security: subject.security,
+                mbps: median_throughput(subject),
+            })
+            .collect()
+    })
+}
+
+/// Median of `CALIBRATION_SAMPLES` throughput samples
+fn median_throughput(subject: &Subject) -> f64 {
+    let mut samples: Vec<f64> = (0..CALIBRATION_SAMPLES)
+        .map(|_| throughput(subject, CALIBRATION_SIZE, CALIBRATION_TIME))
+        .collect();
+    samples.sort_by(f64::total_cmp);
+    samples[CALIBRATION_SAMPLES / 2]
+}
+
+/// Calibrated subjects with at least `security` bits, fastest first
+pub fn fastest(subjects: fn() -> Vec<Subject>, security: u32) -> Vec<&'static Measurement> {
+    let mut ranked: Vec<&Measurement> = calibration(subjects)
+        .iter()
+        .filter(|measurement| measurement.security >= security)
+        .collect();
+    ranked.sort_by(|a, b| b.mbps.total_cmp(&a.mbps));
+    ranked
+}
+
+/// Criterion bench of every subject's seal throughput across message sizes
+///
+/// Expands to the bench's `main`, so a cipher crate's
+/// `benches/throughput.rs` is just
+///
+/// ```ignore
+/// nif_support::seal_bench!(aes_gcm_nif::bench::subjects);
+/// ```
+///
+/// Sizes run from 64 B to 1 MiB: small Git objects up to large blobs.
+/// `benchmark/2` measures the same subjects from Elixir.
+#[macro_export]
+macro_rules! seal_bench {
+    ($subjects:path) => {
+        fn seal(c: &mut criterion::Criterion) {
+            let mut group = c.benchmark_group("seal");
+            for subject in $subjects() {
+                for size in [64, 1024, 16 * 1024, 256 * 1024, 1024 * 1024] {
+                    let mut buffer = vec![0u8; size];
+                    group.throughput(criterion::Throughput::Bytes(size as u64));
+                    group.bench_with_input(
+                        criterion::BenchmarkId::new(subject.name, size),
+                        &size,
+                        |b, _| b.iter(|| subject.seal(&mut buffer)),
+                    );
+                }
+            }
+            group.finish();
+        }
+
+        criterion::criterion_group!(benches, seal);
+        criterion::criterion_main!(benches);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subjects() -> Vec<Subject> {
+        vec![
+            Subject::new("slow", 256, |buffer| {
+                for _ in 0..8 {
+                    buffer.iter_mut().for_each(|b| *b = b.wrapping_add(1));
+                }
+            }),
+            Subject::new("fast", 128, |buffer| buffer[0] ^= 1),
+        ]
+    }
+
+    #[test]
+    fn test_throughput_measures() {
+        for subject in subjects() {
+            let mbps = throughput(&subject, 1000, Duration::from_millis(1));
+            assert!(mbps > 0.0, "{} measured {}", subject.name, mbps);
+        }
+    }
+
+    #[test]
+    fn test_fastest_filters_and_ranks() {
+        let ranked = fastest(subjects, 128);
+        assert_eq!(ranked.len(), 2);
+        assert!(ranked.windows(2).all(|pair| pair[0].mbps >= pair[1].mbps));
+        assert_eq!(fastest(subjects, 256).len(), 1);
+        assert!(fastest(subjects, 512).is_empty());
+    }
+}
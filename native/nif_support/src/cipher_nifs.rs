@@ -0,0 +1,1522 @@
+//! The NIFs every cipher library exports for its default AEAD
+//!
+//! One-shot `encrypt/4` and `decrypt/5`, `encrypt_many/2` and
+//! `decrypt_many/2`, `seal/4` and `open/3`, the `new_ctx/1` context NIFs,
+//! `encrypt_file/6` and `decrypt_file/7`, the STREAM NIFs in all their
+//! forms, `set_memory_limit/1`, `benchmark/2` and `fastest_algorithms/1`
+//! work the same for every AEAD. `cipher_nifs!` generates them for a
+//! `SegmentCipher` type; the functions here are the bodies they share, and
+//! cipher crates use them for their other variants too. Message-sized
+//! buffers are reserved against the library's `memory` budget and
+//! allocated fallibly, so an oversized call returns
+//! `{:error, :memory_limit}` instead of aborting the VM.
+
+use crate::batch;
+use crate::bench::{self, Subject};
+use crate::iodata::IoData;
+use crate::memory::{self, Reservation};
+use crate::stream::{SegmentCipher, StreamError};
+use crate::stream_file::FileError;
+use aead::KeyInit;
+use rustler::types::atom;
+use rustler::{Atom, Binary, Encoder, Env, Error, OwnedBinary, Term};
+use std::time::Duration;
+
+/// Input size from which the one-shot NIFs continue on a dirty scheduler
+pub const DIRTY_THRESHOLD: usize = 64 * 1024;
+
+/// Input handled between timeslice checks by the yielding STREAM NIFs
+pub const YIELD_SLICE: usize = 1024 * 1024;
+
+/// Largest message `benchmark/2` accepts (bytes)
+pub const MAX_BENCHMARK_MESSAGE: usize = 64 * 1024 * 1024;
+
+/// Longest `benchmark/2` measuring time per algorithm (milliseconds)
+pub const MAX_BENCHMARK_MS: u64 = 10_000;
+
+mod atoms {
+    rustler::atoms! {
+        io_error,
+    }
+}
+
+/// Hardware path a cipher takes on the running CPU, for `backend_info/0`
+#[derive(rustler::NifMap)]
+pub struct BackendInfo {
+    pub backend: Atom,
+    pub features: Vec<Atom>,
+}
+
+/// Copy bytes into an Elixir binary
+pub fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env)
+}
+
+/// Build a RustCrypto cipher, checking the key against its key size
+pub fn cipher<C: KeyInit>(key: &[u8]) -> Result<C, Error> {
+    if key.len() != C::key_size() {
+        return Err(Error::BadArg);
+    }
+    C::new_from_slice(key).map_err(|_| Error::BadArg)
+}
+
+/// Encrypt into a new binary and return it with the tag
+pub fn seal<'a, C: SegmentCipher>(
+    env: Env<'a>,
+    cipher: &C,
+    nonce: &[u8],
+    plaintext: &IoData,
+    aad: &[u8],
+) -> Result<(Binary<'a>, Binary<'a>), Error> {
+    if !C::valid_nonce(nonce) {
+        return Err(Error::BadArg);
+    }
+
+    // Encrypt straight into the output binary, with the tag kept apart
+    let _reservation = memory::reserve(plaintext.len())?;
+    let mut ciphertext = memory::binary(plaintext.len())?;
+    plaintext.copy_to(ciphertext.as_mut_slice());
+    let tag = cipher
+        .seal_in_place(nonce, aad, ciphertext.as_mut_slice())
+        .ok_or_else(|| Error::RaiseTerm(Box::new("encryption failed")))?;
+
+    Ok((ciphertext.release(env), to_binary(env, &tag)))
+}
+
+/// Verify and decrypt into a new binary
+pub fn open<'a, C: SegmentCipher>(
+    env: Env<'a>,
+    cipher: &C,
+    nonce: &[u8],
+    ciphertext: &IoData,
+    tag: &[u8],
+    aad: &[u8],
+) -> Result<Binary<'a>, Error> {
+    if !C::valid_nonce(nonce) || tag.len() != C::TAG_SIZE {
+        return Err(Error::BadArg);
+    }
+
+    // Decrypt straight into the output binary; it is dropped unreleased
+    // if the tag doesn't verify
+    let _reservation = memory::reserve(ciphertext.len())?;
+    let mut plaintext = memory::binary(ciphertext.len())?;
+    ciphertext.copy_to(plaintext.as_mut_slice());
+    if !cipher.open_in_place(nonce, aad, plaintext.as_mut_slice(), tag) {
+        return Err(Error::RaiseTerm(Box::new("authentication failed")));
+    }
+
+    Ok(plaintext.release(env))
+}
+
+/// Encrypt into a single `nonce || ciphertext || tag` binary
+pub fn seal_blob<'a, C: SegmentCipher>(
+    env: Env<'a>,
+    cipher: &C,
+    nonce: &[u8],
+    plaintext: &IoData,
+    aad: &[u8],
+) -> Result<Binary<'a>, Error> {
+    if !C::valid_nonce(nonce) {
+        return Err(Error::BadArg);
+    }
+
+    // Encrypt straight into the blob, between the nonce and the tag
+    let len = nonce.len() + plaintext.len() + C::TAG_SIZE;
+    let _reservation = memory::reserve(len)?;
+    let mut blob = memory::binary(len)?;
+    let (nonce_out, sealed_out) = blob.as_mut_slice().split_at_mut(nonce.len());
+    let (ciphertext_out, tag_out) = sealed_out.split_at_mut(plaintext.len());
+    nonce_out.copy_from_slice(nonce);
+    plaintext.copy_to(ciphertext_out);
+    let tag = cipher
+        .seal_in_place(nonce, aad, ciphertext_out)
+        .ok_or_else(|| Error::RaiseTerm(Box::new("encryption failed")))?;
+    tag_out.copy_from_slice(&tag);
+    Ok(blob.release(env))
+}
+
+/// Verify and decrypt a blob from `seal_blob`
+pub fn open_blob<'a, C: SegmentCipher>(
+    env: Env<'a>,
+    cipher: &C,
+    blob: &[u8],
+    aad: &[u8],
+) -> Result<Binary<'a>, Error> {
+    if blob.len() < C::NONCE_SIZE + C::TAG_SIZE {
+        return Err(Error::BadArg);
+    }
+    let (nonce, sealed) = blob.split_at(C::NONCE_SIZE);
+    let (ciphertext, tag) = sealed.split_at(sealed.len() - C::TAG_SIZE);
+    if !C::valid_nonce(nonce) {
+        return Err(Error::BadArg);
+    }
+
+    // Decrypt straight into the output binary; it is dropped unreleased if
+    // the tag doesn't verify
+    let _reservation = memory::reserve(ciphertext.len())?;
+    let mut plaintext = memory::binary(ciphertext.len())?;
+    plaintext.as_mut_slice().copy_from_slice(ciphertext);
+    if !cipher.open_in_place(nonce, aad, plaintext.as_mut_slice(), tag) {
+        return Err(Error::RaiseTerm(Box::new("authentication failed")));
+    }
+
+    Ok(plaintext.release(env))
+}
+
+/// Encrypt many `{nonce, plaintext, aad}` items in parallel (see `batch`)
+pub fn seal_many<'a, C: SegmentCipher + Sync>(
+    env: Env<'a>,
+    cipher: &C,
+    items: &[(Binary, Binary, Binary)],
+) -> Result<Vec<(Binary<'a>, Binary<'a>)>, Error> {
+    let items: Vec<batch::SealItem> = items
+        .iter()
+        .map(|(nonce, plaintext, aad)| (nonce.as_slice(), plaintext.as_slice(), aad.as_slice()))
+        .collect();
+    if items.iter().any(|(nonce, _, _)| !C::valid_nonce(nonce)) {
+        return Err(Error::BadArg);
+    }
+    // The results are held in full before being copied out
+    let _reservation = memory::reserve(batch_len(
+        items.iter().map(|(_, plaintext, _)| plaintext.len()),
+    )?)?;
+
+    Ok(batch::seal_many(cipher, &items)
+        .iter()
+        .map(|(ciphertext, tag)| (to_binary(env, ciphertext), to_binary(env, tag)))
+        .collect())
+}
+
+/// Decrypt many `{nonce, ciphertext, tag, aad}` items in parallel
+pub fn open_many<'a, C: SegmentCipher + Sync>(
+    env: Env<'a>,
+    cipher: &C,
+    items: &[(Binary, Binary, Binary, Binary)],
+) -> Result<Vec<Binary<'a>>, Error> {
+    let items: Vec<batch::OpenItem> = items
+        .iter()
+        .map(|(nonce, ciphertext, tag, aad)| {
+            (
+                nonce.as_slice(),
+                ciphertext.as_slice(),
+                tag.as_slice(),
+                aad.as_slice(),
+            )
+        })
+        .collect();
+    let valid = |&(nonce, _, tag, _): &batch::OpenItem| {
+        C::valid_nonce(nonce) && tag.len() == C::TAG_SIZE
+    };
+    if !items.iter().all(valid) {
+        return Err(Error::BadArg);
+    }
+    let _reservation = memory::reserve(batch_len(
+        items.iter().map(|(_, ciphertext, _, _)| ciphertext.len()),
+    )?)?;
+
+    let plaintexts = batch::open_many(cipher, &items)
+        .ok_or_else(|| Error::RaiseTerm(Box::new("authentication failed")))?;
+    Ok(plaintexts
+        .iter()
+        .map(|plaintext| to_binary(env, plaintext))
+        .collect())
+}
+
+/// Total length of a batch's messages, or the memory error if it overflows
+fn batch_len(mut lengths: impl Iterator<Item = usize>) -> Result<usize, Error> {
+    lengths
+        .try_fold(0usize, usize::checked_add)
+        .ok_or_else(memory::limit_error)
+}
+
+/// Read a whole file into a buffer reserved against the memory budget
+///
+/// The outer error is the read failing; the inner one is the budget or
+/// the allocation.
+fn read_input(path: &str) -> std::io::Result<Result<(Vec<u8>, Reservation), Error>> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len() as usize;
+    let reservation = match memory::reserve(size) {
+        Ok(reservation) => reservation,
+        Err(e) => return Ok(Err(e)),
+    };
+    let mut buffer = match memory::buffer(size) {
+        Ok(buffer) => buffer,
+        Err(e) => return Ok(Err(e)),
+    };
+    file.read_to_end(&mut buffer)?;
+    Ok(Ok((buffer, reservation)))
+}
+
+/// `{:error, {:io_error, reason}}` for a failed read or write
+pub fn file_error<'a>(env: Env<'a>, path: &str, e: std::io::Error) -> Term<'a> {
+    (
+        atom::error(),
+        (atoms::io_error(), format!("{}: {}", path, e)),
+    )
+        .encode(env)
+}
+
+/// Encrypt one file into another, returning `{:ok, tag}`
+///
+/// The whole file is held in memory and encrypted in place: one copy, not
+/// two.
+pub fn seal_file<'a, C: SegmentCipher>(
+    env: Env<'a>,
+    cipher: &C,
+    nonce: &[u8],
+    input_path: &str,
+    output_path: &str,
+    aad: &[u8],
+) -> Result<Term<'a>, Error> {
+    if !C::valid_nonce(nonce) {
+        return Err(Error::BadArg);
+    }
+
+    let (mut buffer, _reservation) = match read_input(input_path) {
+        Ok(read) => read?,
+        Err(e) => return Ok(file_error(env, input_path, e)),
+    };
+
+    let tag = cipher
+        .seal_in_place(nonce, aad, &mut buffer)
+        .ok_or_else(|| Error::RaiseTerm(Box::new("encryption failed")))?;
+
+    if let Err(e) = std::fs::write(output_path, &buffer) {
+        return Ok(file_error(env, output_path, e));
+    }
+
+    Ok((atom::ok(), to_binary(env, &tag)).encode(env))
+}
+
+/// Decrypt one file into another, writing it only once the tag verifies
+pub fn open_file<'a, C: SegmentCipher>(
+    env: Env<'a>,
+    cipher: &C,
+    nonce: &[u8],
+    input_path: &str,
+    output_path: &str,
+    tag: &[u8],
+    aad: &[u8],
+) -> Result<Term<'a>, Error> {
+    if !C::valid_nonce(nonce) || tag.len() != C::TAG_SIZE {
+        return Err(Error::BadArg);
+    }
+
+    let (mut buffer, _reservation) = match read_input(input_path) {
+        Ok(read) => read?,
+        Err(e) => return Ok(file_error(env, input_path, e)),
+    };
+
+    if !cipher.open_in_place(nonce, aad, &mut buffer, tag) {
+        return Err(Error::RaiseTerm(Box::new("authentication failed")));
+    }
+
+    if let Err(e) = std::fs::write(output_path, &buffer) {
+        return Ok(file_error(env, output_path, e));
+    }
+
+    Ok(atom::ok().encode(env))
+}
+
+pub fn stream_error(error: StreamError) -> Error {
+    match error {
+        StreamError::Authentication => Error::RaiseTerm(Box::new("authentication failed")),
+        StreamError::TooLong => Error::RaiseTerm(Box::new("stream too long")),
+    }
+}
+
+/// `:ok` or the error for a `stream_file` call
+pub fn stream_file_result<'a>(
+    env: Env<'a>,
+    result: Result<(), FileError>,
+    input_path: &str,
+    output_path: &str,
+) -> Result<Term<'a>, Error> {
+    match result {
+        Ok(()) => Ok(atom::ok().encode(env)),
+        Err(FileError::Read(e)) => Ok(file_error(env, input_path, e)),
+        Err(FileError::Write(e)) => Ok(file_error(env, output_path, e)),
+        Err(FileError::Stream(e)) => Err(stream_error(e)),
+    }
+}
+
+/// `%{algorithm => MB/s}` for every subject (see `bench::throughput`)
+pub fn benchmark<'a>(
+    env: Env<'a>,
+    subjects: fn() -> Vec<Subject>,
+    message_size: usize,
+    duration_ms: u64,
+) -> Result<Term<'a>, Error> {
+    if message_size == 0 || message_size > MAX_BENCHMARK_MESSAGE {
+        return Err(Error::BadArg);
+    }
+    if duration_ms == 0 || duration_ms > MAX_BENCHMARK_MS {
+        return Err(Error::BadArg);
+    }
+
+    let duration = Duration::from_millis(duration_ms);
+    let mut results = Term::map_new(env);
+    for subject in subjects() {
+        let mbps = bench::throughput(&subject, message_size, duration);
+        results = results.map_put(Atom::from_str(env, subject.name)?, mbps)?;
+    }
+    Ok(results)
+}
+
+/// `[{algorithm, MB/s}]` fastest first (see `bench::fastest`)
+pub fn fastest_algorithms(
+    env: Env,
+    subjects: fn() -> Vec<Subject>,
+    security_level: u32,
+) -> Result<Vec<(Atom, f64)>, Error> {
+    bench::fastest(subjects, security_level)
+        .into_iter()
+        .map(|measurement| Ok((Atom::from_str(env, measurement.name)?, measurement.mbps)))
+        .collect()
+}
+
+/// Generate a cipher library's shared NIFs for its default AEAD
+///
+/// `cipher` implements `SegmentCipher` (every RustCrypto AEAD does) and is
+/// `Send + Sync`; `new` builds one from a key binary's bytes, as
+/// `Result<cipher, rustler::Error>`, and `subjects` lists the library's
+/// algorithms for `benchmark/2` (see `bench`). The sizes are for the docs
+/// and are checked against the cipher's at compile time.
+///
+/// ```ignore
+/// nif_support::cipher_nifs! {
+///     cipher: Aes256Gcm,
+///     name: "AES-256-GCM",
+///     new: cipher::<Aes256Gcm>,
+///     sizes: { key: 32, nonce: 12, tag: 16, prefix: 7 },
+///     subjects: bench::subjects,
+/// }
+/// ```
+///
+/// Inputs of `DIRTY_THRESHOLD` bytes or more continue on a dirty CPU
+/// scheduler (see `reschedule`), and messages and AAD may be given as
+/// iodata (see `iodata`).
+#[macro_export]
+macro_rules! cipher_nifs {
+    (
+        cipher: $cipher:ty,
+        name: $name:literal,
+        new: $new:expr,
+        sizes: { key: $key:literal, nonce: $nonce:literal, tag: $tag:literal, prefix: $prefix:literal },
+        subjects: $subjects:path $(,)?
+    ) => {
+        const _: () = {
+            use $crate::stream::SegmentCipher;
+            assert!(<$cipher as SegmentCipher>::NONCE_SIZE == $nonce);
+            assert!(<$cipher as SegmentCipher>::TAG_SIZE == $tag);
+            assert!($crate::stream::NONCE_OVERHEAD + $prefix == $nonce);
+        };
+
+        /// Body of `encrypt/4`, run inline or as its dirty continuation
+        fn encrypt_impl<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            nonce: rustler::Binary,
+            plaintext: $crate::iodata::IoData,
+            aad: $crate::iodata::IoData,
+        ) -> Result<(rustler::Binary<'a>, rustler::Binary<'a>), rustler::Error> {
+            let cipher: $cipher = ($new)(key.as_slice())?;
+            $crate::cipher_nifs::seal(env, &cipher, nonce.as_slice(), &plaintext, &aad.to_cow())
+        }
+
+        #[doc = concat!($name, " encryption")]
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce: ", $nonce, " bytes")]
+        /// - plaintext: variable length
+        /// - aad: variable length (additional authenticated data)
+        ///
+        /// Returns:
+        #[doc = concat!("- Ok({ciphertext, tag}) where tag is ", $tag, " bytes")]
+        /// - Err for invalid parameters
+        ///
+        /// The message and AAD may be given as iodata (see `iodata`). Inputs
+        /// under `DIRTY_THRESHOLD` bytes run inline; larger ones continue on a
+        /// dirty CPU scheduler.
+        #[rustler::nif]
+        fn encrypt<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary<'a>,
+            nonce: rustler::Binary<'a>,
+            plaintext: $crate::iodata::IoData<'a>,
+            aad: $crate::iodata::IoData<'a>,
+        ) -> Result<
+            $crate::reschedule::Dispatch<'a, (rustler::Binary<'a>, rustler::Binary<'a>)>,
+            rustler::Error,
+        > {
+            use rustler::Encoder;
+            if plaintext.len() >= $crate::cipher_nifs::DIRTY_THRESHOLD {
+                let args = vec![
+                    key.encode(env),
+                    nonce.encode(env),
+                    plaintext.encode(env),
+                    aad.encode(env),
+                ];
+                return Ok($crate::reschedule::Dispatch::dirty("encrypt", encrypt_dirty, args));
+            }
+            encrypt_impl(env, key, nonce, plaintext, aad).map($crate::reschedule::Dispatch::Done)
+        }
+
+        /// `encrypt/4` continued on a dirty CPU scheduler
+        unsafe extern "C" fn encrypt_dirty(
+            env: rustler::codegen_runtime::NIF_ENV,
+            argc: rustler::codegen_runtime::c_int,
+            argv: *const rustler::codegen_runtime::NIF_TERM,
+        ) -> rustler::codegen_runtime::NIF_TERM {
+            $crate::reschedule::run(env, argc, argv, |env, args| {
+                let result = encrypt_impl(
+                    env,
+                    args[0].decode()?,
+                    args[1].decode()?,
+                    args[2].decode()?,
+                    args[3].decode()?,
+                );
+                Ok($crate::reschedule::returned(env, result))
+            })
+        }
+
+        /// Body of `decrypt/5`, run inline or as its dirty continuation
+        fn decrypt_impl<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            nonce: rustler::Binary,
+            ciphertext: $crate::iodata::IoData,
+            tag: rustler::Binary,
+            aad: $crate::iodata::IoData,
+        ) -> Result<rustler::Binary<'a>, rustler::Error> {
+            let cipher: $cipher = ($new)(key.as_slice())?;
+            $crate::cipher_nifs::open(
+                env,
+                &cipher,
+                nonce.as_slice(),
+                &ciphertext,
+                tag.as_slice(),
+                &aad.to_cow(),
+            )
+        }
+
+        #[doc = concat!($name, " decryption")]
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce: ", $nonce, " bytes")]
+        /// - ciphertext: variable length
+        #[doc = concat!("- tag: ", $tag, " bytes (authentication tag)")]
+        /// - aad: variable length (additional authenticated data)
+        ///
+        /// Returns:
+        /// - Ok(plaintext)
+        /// - Err if authentication fails, or for invalid parameters
+        ///
+        /// The message and AAD may be given as iodata (see `iodata`). Inputs
+        /// under `DIRTY_THRESHOLD` bytes run inline; larger ones continue on a
+        /// dirty CPU scheduler.
+        #[rustler::nif]
+        fn decrypt<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary<'a>,
+            nonce: rustler::Binary<'a>,
+            ciphertext: $crate::iodata::IoData<'a>,
+            tag: rustler::Binary<'a>,
+            aad: $crate::iodata::IoData<'a>,
+        ) -> Result<$crate::reschedule::Dispatch<'a, rustler::Binary<'a>>, rustler::Error> {
+            use rustler::Encoder;
+            if ciphertext.len() >= $crate::cipher_nifs::DIRTY_THRESHOLD {
+                let args = vec![
+                    key.encode(env),
+                    nonce.encode(env),
+                    ciphertext.encode(env),
+                    tag.encode(env),
+                    aad.encode(env),
+                ];
+                return Ok($crate::reschedule::Dispatch::dirty("decrypt", decrypt_dirty, args));
+            }
+            decrypt_impl(env, key, nonce, ciphertext, tag, aad)
+                .map($crate::reschedule::Dispatch::Done)
+        }
+
+        /// `decrypt/5` continued on a dirty CPU scheduler
+        unsafe extern "C" fn decrypt_dirty(
+            env: rustler::codegen_runtime::NIF_ENV,
+            argc: rustler::codegen_runtime::c_int,
+            argv: *const rustler::codegen_runtime::NIF_TERM,
+        ) -> rustler::codegen_runtime::NIF_TERM {
+            $crate::reschedule::run(env, argc, argv, |env, args| {
+                let result = decrypt_impl(
+                    env,
+                    args[0].decode()?,
+                    args[1].decode()?,
+                    args[2].decode()?,
+                    args[3].decode()?,
+                    args[4].decode()?,
+                );
+                Ok($crate::reschedule::returned(env, result))
+            })
+        }
+
+        #[doc = concat!($name, " encryption of many messages in parallel")]
+        ///
+        /// Each item is encrypted as by `encrypt/4`, spread over a thread pool so
+        /// a batch of files uses every core (see `batch`). Runs on a dirty CPU
+        /// scheduler.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        /// - items: list of `{nonce, plaintext, aad}`, each as for `encrypt/4`
+        ///
+        /// Returns:
+        /// - Ok([{ciphertext, tag}]) in the order of `items`
+        /// - Err for invalid parameters
+        #[rustler::nif(schedule = "DirtyCpu")]
+        fn encrypt_many<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            items: Vec<(rustler::Binary, rustler::Binary, rustler::Binary)>,
+        ) -> Result<Vec<(rustler::Binary<'a>, rustler::Binary<'a>)>, rustler::Error> {
+            let cipher: $cipher = ($new)(key.as_slice())?;
+            $crate::cipher_nifs::seal_many(env, &cipher, &items)
+        }
+
+        #[doc = concat!($name, " decryption of many messages in parallel")]
+        ///
+        /// Each item is decrypted as by `decrypt/5`, spread over a thread pool.
+        /// Runs on a dirty CPU scheduler.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        /// - items: list of `{nonce, ciphertext, tag, aad}`, each as for
+        ///   `decrypt/5`
+        ///
+        /// Returns:
+        /// - Ok([plaintext]) in the order of `items`
+        /// - Err if any item fails authentication, or for invalid parameters
+        #[rustler::nif(schedule = "DirtyCpu")]
+        fn decrypt_many<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            items: Vec<(rustler::Binary, rustler::Binary, rustler::Binary, rustler::Binary)>,
+        ) -> Result<Vec<rustler::Binary<'a>>, rustler::Error> {
+            let cipher: $cipher = ($new)(key.as_slice())?;
+            $crate::cipher_nifs::open_many(env, &cipher, &items)
+        }
+
+        /// Body of `seal/4`, run inline or as its dirty continuation
+        fn seal_impl<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            nonce: rustler::Binary,
+            plaintext: $crate::iodata::IoData,
+            aad: $crate::iodata::IoData,
+        ) -> Result<rustler::Binary<'a>, rustler::Error> {
+            let cipher: $cipher = ($new)(key.as_slice())?;
+            $crate::cipher_nifs::seal_blob(env, &cipher, nonce.as_slice(), &plaintext, &aad.to_cow())
+        }
+
+        #[doc = concat!($name, " encryption into a single binary")]
+        ///
+        /// Same as `encrypt/4`, but returns the nonce, ciphertext and tag
+        /// concatenated, ready to store as one blob and pass back to `open/3`.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce: ", $nonce, " bytes")]
+        /// - plaintext: variable length
+        /// - aad: variable length (additional authenticated data)
+        ///
+        /// Returns:
+        #[doc = concat!("- Ok(nonce || ciphertext || tag) where tag is ", $tag, " bytes")]
+        /// - Err for invalid parameters
+        ///
+        /// The message and AAD may be given as iodata (see `iodata`). Inputs
+        /// under `DIRTY_THRESHOLD` bytes run inline; larger ones continue on a
+        /// dirty CPU scheduler.
+        #[rustler::nif(name = "seal")]
+        fn seal_blob<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary<'a>,
+            nonce: rustler::Binary<'a>,
+            plaintext: $crate::iodata::IoData<'a>,
+            aad: $crate::iodata::IoData<'a>,
+        ) -> Result<$crate::reschedule::Dispatch<'a, rustler::Binary<'a>>, rustler::Error> {
+            use rustler::Encoder;
+            if plaintext.len() >= $crate::cipher_nifs::DIRTY_THRESHOLD {
+                let args = vec![
+                    key.encode(env),
+                    nonce.encode(env),
+                    plaintext.encode(env),
+                    aad.encode(env),
+                ];
+                return Ok($crate::reschedule::Dispatch::dirty("seal", seal_dirty, args));
+            }
+            seal_impl(env, key, nonce, plaintext, aad).map($crate::reschedule::Dispatch::Done)
+        }
+
+        /// `seal/4` continued on a dirty CPU scheduler
+        unsafe extern "C" fn seal_dirty(
+            env: rustler::codegen_runtime::NIF_ENV,
+            argc: rustler::codegen_runtime::c_int,
+            argv: *const rustler::codegen_runtime::NIF_TERM,
+        ) -> rustler::codegen_runtime::NIF_TERM {
+            $crate::reschedule::run(env, argc, argv, |env, args| {
+                let result = seal_impl(
+                    env,
+                    args[0].decode()?,
+                    args[1].decode()?,
+                    args[2].decode()?,
+                    args[3].decode()?,
+                );
+                Ok($crate::reschedule::returned(env, result))
+            })
+        }
+
+        /// Body of `open/3`, run inline or as its dirty continuation
+        fn open_impl<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            blob: rustler::Binary,
+            aad: $crate::iodata::IoData,
+        ) -> Result<rustler::Binary<'a>, rustler::Error> {
+            let cipher: $cipher = ($new)(key.as_slice())?;
+            $crate::cipher_nifs::open_blob(env, &cipher, blob.as_slice(), &aad.to_cow())
+        }
+
+        #[doc = concat!($name, " decryption of a single binary from `seal/4`")]
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        /// - blob: nonce || ciphertext || tag, as returned by `seal/4`
+        /// - aad: variable length (additional authenticated data)
+        ///
+        /// Returns:
+        /// - Ok(plaintext)
+        /// - Err if authentication fails, or if the blob is too short to hold a
+        ///   nonce and tag
+        ///
+        /// The AAD may be given as iodata (see `iodata`). Blobs under
+        /// `DIRTY_THRESHOLD` bytes run inline; larger ones continue on a dirty CPU
+        /// scheduler.
+        #[rustler::nif(name = "open")]
+        fn open_blob<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary<'a>,
+            blob: rustler::Binary<'a>,
+            aad: $crate::iodata::IoData<'a>,
+        ) -> Result<$crate::reschedule::Dispatch<'a, rustler::Binary<'a>>, rustler::Error> {
+            use rustler::Encoder;
+            if blob.len() >= $crate::cipher_nifs::DIRTY_THRESHOLD {
+                let args = vec![key.encode(env), blob.encode(env), aad.encode(env)];
+                return Ok($crate::reschedule::Dispatch::dirty("open", open_dirty, args));
+            }
+            open_impl(env, key, blob, aad).map($crate::reschedule::Dispatch::Done)
+        }
+
+        /// `open/3` continued on a dirty CPU scheduler
+        unsafe extern "C" fn open_dirty(
+            env: rustler::codegen_runtime::NIF_ENV,
+            argc: rustler::codegen_runtime::c_int,
+            argv: *const rustler::codegen_runtime::NIF_TERM,
+        ) -> rustler::codegen_runtime::NIF_TERM {
+            $crate::reschedule::run(env, argc, argv, |env, args| {
+                let result = open_impl(env, args[0].decode()?, args[1].decode()?, args[2].decode()?);
+                Ok($crate::reschedule::returned(env, result))
+            })
+        }
+
+        #[doc = concat!("A ", $name, " cipher set up once for a key, for `ctx_encrypt/4` and")]
+        /// `ctx_decrypt/5`
+        struct CipherContext {
+            cipher: $cipher,
+        }
+
+        #[rustler::resource_impl]
+        impl rustler::Resource for CipherContext {}
+
+        #[doc = concat!($name, " context for a key")]
+        ///
+        /// The cipher is set up once, so any key schedule is derived here
+        /// rather than on every call, which adds up over thousands of small
+        /// files.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        ///
+        /// Returns:
+        /// - Ok(ctx) for `ctx_encrypt/4` and `ctx_decrypt/5`
+        /// - Err for invalid parameters
+        #[rustler::nif]
+        fn new_ctx(
+            key: rustler::Binary,
+        ) -> Result<rustler::ResourceArc<CipherContext>, rustler::Error> {
+            let cipher: $cipher = ($new)(key.as_slice())?;
+            Ok(rustler::ResourceArc::new(CipherContext { cipher }))
+        }
+
+        /// Body of `ctx_encrypt/4`, run inline or as its dirty continuation
+        fn ctx_encrypt_impl<'a>(
+            env: rustler::Env<'a>,
+            ctx: rustler::ResourceArc<CipherContext>,
+            nonce: rustler::Binary,
+            plaintext: $crate::iodata::IoData,
+            aad: $crate::iodata::IoData,
+        ) -> Result<(rustler::Binary<'a>, rustler::Binary<'a>), rustler::Error> {
+            $crate::cipher_nifs::seal(env, &ctx.cipher, nonce.as_slice(), &plaintext, &aad.to_cow())
+        }
+
+        #[doc = concat!($name, " encryption with a context from `new_ctx/1`")]
+        ///
+        /// Same as `encrypt/4` with the context's key.
+        ///
+        /// Parameters:
+        /// - ctx: from `new_ctx/1`
+        #[doc = concat!("- nonce: ", $nonce, " bytes")]
+        /// - plaintext: variable length
+        /// - aad: variable length (additional authenticated data)
+        ///
+        /// Returns:
+        #[doc = concat!("- Ok({ciphertext, tag}) where tag is ", $tag, " bytes")]
+        /// - Err for invalid parameters
+        ///
+        /// The message and AAD may be given as iodata (see `iodata`). Inputs
+        /// under `DIRTY_THRESHOLD` bytes run inline; larger ones continue on a
+        /// dirty CPU scheduler.
+        #[rustler::nif]
+        fn ctx_encrypt<'a>(
+            env: rustler::Env<'a>,
+            ctx: rustler::ResourceArc<CipherContext>,
+            nonce: rustler::Binary<'a>,
+            plaintext: $crate::iodata::IoData<'a>,
+            aad: $crate::iodata::IoData<'a>,
+        ) -> Result<
+            $crate::reschedule::Dispatch<'a, (rustler::Binary<'a>, rustler::Binary<'a>)>,
+            rustler::Error,
+        > {
+            use rustler::Encoder;
+            if plaintext.len() >= $crate::cipher_nifs::DIRTY_THRESHOLD {
+                let args = vec![
+                    ctx.encode(env),
+                    nonce.encode(env),
+                    plaintext.encode(env),
+                    aad.encode(env),
+                ];
+                return Ok($crate::reschedule::Dispatch::dirty(
+                    "ctx_encrypt",
+                    ctx_encrypt_dirty,
+                    args,
+                ));
+            }
+            ctx_encrypt_impl(env, ctx, nonce, plaintext, aad).map($crate::reschedule::Dispatch::Done)
+        }
+
+        /// `ctx_encrypt/4` continued on a dirty CPU scheduler
+        unsafe extern "C" fn ctx_encrypt_dirty(
+            env: rustler::codegen_runtime::NIF_ENV,
+            argc: rustler::codegen_runtime::c_int,
+            argv: *const rustler::codegen_runtime::NIF_TERM,
+        ) -> rustler::codegen_runtime::NIF_TERM {
+            $crate::reschedule::run(env, argc, argv, |env, args| {
+                let result = ctx_encrypt_impl(
+                    env,
+                    args[0].decode()?,
+                    args[1].decode()?,
+                    args[2].decode()?,
+                    args[3].decode()?,
+                );
+                Ok($crate::reschedule::returned(env, result))
+            })
+        }
+
+        /// Body of `ctx_decrypt/5`, run inline or as its dirty continuation
+        fn ctx_decrypt_impl<'a>(
+            env: rustler::Env<'a>,
+            ctx: rustler::ResourceArc<CipherContext>,
+            nonce: rustler::Binary,
+            ciphertext: $crate::iodata::IoData,
+            tag: rustler::Binary,
+            aad: $crate::iodata::IoData,
+        ) -> Result<rustler::Binary<'a>, rustler::Error> {
+            $crate::cipher_nifs::open(
+                env,
+                &ctx.cipher,
+                nonce.as_slice(),
+                &ciphertext,
+                tag.as_slice(),
+                &aad.to_cow(),
+            )
+        }
+
+        #[doc = concat!($name, " decryption with a context from `new_ctx/1`")]
+        ///
+        /// Same as `decrypt/5` with the context's key.
+        ///
+        /// Parameters:
+        /// - ctx: from `new_ctx/1`
+        #[doc = concat!("- nonce: ", $nonce, " bytes")]
+        /// - ciphertext: variable length
+        #[doc = concat!("- tag: ", $tag, " bytes (authentication tag)")]
+        /// - aad: variable length (additional authenticated data)
+        ///
+        /// Returns:
+        /// - Ok(plaintext)
+        /// - Err if authentication fails, or for invalid parameters
+        ///
+        /// The message and AAD may be given as iodata (see `iodata`). Inputs
+        /// under `DIRTY_THRESHOLD` bytes run inline; larger ones continue on a
+        /// dirty CPU scheduler.
+        #[rustler::nif]
+        fn ctx_decrypt<'a>(
+            env: rustler::Env<'a>,
+            ctx: rustler::ResourceArc<CipherContext>,
+            nonce: rustler::Binary<'a>,
+            ciphertext: $crate::iodata::IoData<'a>,
+            tag: rustler::Binary<'a>,
+            aad: $crate::iodata::IoData<'a>,
+        ) -> Result<$crate::reschedule::Dispatch<'a, rustler::Binary<'a>>, rustler::Error> {
+            use rustler::Encoder;
+            if ciphertext.len() >= $crate::cipher_nifs::DIRTY_THRESHOLD {
+                let args = vec![
+                    ctx.encode(env),
+                    nonce.encode(env),
+                    ciphertext.encode(env),
+                    tag.encode(env),
+                    aad.encode(env),
+                ];
+                return Ok($crate::reschedule::Dispatch::dirty(
+                    "ctx_decrypt",
+                    ctx_decrypt_dirty,
+                    args,
+                ));
+            }
+            ctx_decrypt_impl(env, ctx, nonce, ciphertext, tag, aad)
+                .map($crate::reschedule::Dispatch::Done)
+        }
+
+        /// `ctx_decrypt/5` continued on a dirty CPU scheduler
+        unsafe extern "C" fn ctx_decrypt_dirty(
+            env: rustler::codegen_runtime::NIF_ENV,
+            argc: rustler::codegen_runtime::c_int,
+            argv: *const rustler::codegen_runtime::NIF_TERM,
+        ) -> rustler::codegen_runtime::NIF_TERM {
+            $crate::reschedule::run(env, argc, argv, |env, args| {
+                let result = ctx_decrypt_impl(
+                    env,
+                    args[0].decode()?,
+                    args[1].decode()?,
+                    args[2].decode()?,
+                    args[3].decode()?,
+                    args[4].decode()?,
+                );
+                Ok($crate::reschedule::returned(env, result))
+            })
+        }
+
+        #[doc = concat!($name, " encryption from one file to another")]
+        ///
+        /// Reads and writes the files in the NIF, so the plaintext and ciphertext
+        /// never pass through the BEAM heap. The output file holds exactly what
+        /// `encrypt/4` would return as the ciphertext. Runs on a dirty I/O
+        /// scheduler.
+        ///
+        /// The whole file is held in memory; `seal_stream_file/5` handles files
+        /// larger than RAM.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce: ", $nonce, " bytes")]
+        /// - input_path: plaintext file
+        /// - output_path: ciphertext file, created or truncated
+        /// - aad: variable length (additional authenticated data)
+        ///
+        /// Returns:
+        #[doc = concat!("- {:ok, tag} where tag is ", $tag, " bytes")]
+        /// - {:error, {:io_error, reason}} if a file can't be read or written
+        /// - Err for invalid parameters
+        #[rustler::nif(schedule = "DirtyIo")]
+        fn encrypt_file<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            nonce: rustler::Binary,
+            input_path: String,
+            output_path: String,
+            aad: rustler::Binary,
+        ) -> Result<rustler::Term<'a>, rustler::Error> {
+            let cipher: $cipher = ($new)(key.as_slice())?;
+            $crate::cipher_nifs::seal_file(
+                env,
+                &cipher,
+                nonce.as_slice(),
+                &input_path,
+                &output_path,
+                aad.as_slice(),
+            )
+        }
+
+        #[doc = concat!($name, " decryption from one file to another")]
+        ///
+        /// The output file is only written once the tag has verified, so a
+        /// forged or damaged input leaves no partial plaintext behind. Runs on a
+        /// dirty I/O scheduler.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce: ", $nonce, " bytes")]
+        /// - input_path: ciphertext file
+        /// - output_path: plaintext file, created or truncated
+        #[doc = concat!("- tag: ", $tag, " bytes (authentication tag)")]
+        /// - aad: variable length (additional authenticated data)
+        ///
+        /// Returns:
+        /// - :ok
+        /// - {:error, {:io_error, reason}} if a file can't be read or written
+        /// - Err if authentication fails or parameters invalid
+        #[rustler::nif(schedule = "DirtyIo")]
+        fn decrypt_file<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            nonce: rustler::Binary,
+            input_path: String,
+            output_path: String,
+            tag: rustler::Binary,
+            aad: rustler::Binary,
+        ) -> Result<rustler::Term<'a>, rustler::Error> {
+            let cipher: $cipher = ($new)(key.as_slice())?;
+            $crate::cipher_nifs::open_file(
+                env,
+                &cipher,
+                nonce.as_slice(),
+                &input_path,
+                &output_path,
+                tag.as_slice(),
+                aad.as_slice(),
+            )
+        }
+
+        #[doc = concat!("Streaming ", $name, " state; `None` once finished or failed")]
+        struct StreamResource {
+            state: std::sync::Mutex<Option<$crate::stream::Stream<$cipher>>>,
+        }
+
+        #[rustler::resource_impl]
+        impl rustler::Resource for StreamResource {}
+
+        fn stream_resource(
+            stream: Option<$crate::stream::Stream<$cipher>>,
+        ) -> Result<rustler::ResourceArc<StreamResource>, rustler::Error> {
+            let stream = stream.ok_or(rustler::Error::BadArg)?;
+            Ok(rustler::ResourceArc::new(StreamResource {
+                state: std::sync::Mutex::new(Some(stream)),
+            }))
+        }
+
+        /// STREAM encryptor for a key, or `BadArg`
+        fn stream_encryptor(
+            key: &[u8],
+            nonce_prefix: &[u8],
+            aad: &[u8],
+        ) -> Result<$crate::stream::Encryptor<$cipher>, rustler::Error> {
+            let cipher: $cipher = ($new)(key)?;
+            $crate::stream::Encryptor::new(cipher, nonce_prefix, aad).ok_or(rustler::Error::BadArg)
+        }
+
+        /// STREAM decryptor for a key, or `BadArg`
+        fn stream_decryptor(
+            key: &[u8],
+            nonce_prefix: &[u8],
+            aad: &[u8],
+        ) -> Result<$crate::stream::Decryptor<$cipher>, rustler::Error> {
+            let cipher: $cipher = ($new)(key)?;
+            $crate::stream::Decryptor::new(cipher, nonce_prefix, aad).ok_or(rustler::Error::BadArg)
+        }
+
+        #[doc = concat!("Start a streaming ", $name, " encryption")]
+        ///
+        /// Input is sealed in 64 KiB segments, each with its own tag; see
+        /// `stream` for the format.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce_prefix: ", $prefix, " bytes, unique per stream under a key")]
+        /// - aad: variable length, authenticated with every segment
+        ///
+        /// Returns:
+        /// - Ok(stream) to pass to `stream_update/2` and `stream_final/1`
+        /// - Err for invalid parameters
+        #[rustler::nif]
+        fn stream_encrypt_init(
+            key: rustler::Binary,
+            nonce_prefix: rustler::Binary,
+            aad: rustler::Binary,
+        ) -> Result<rustler::ResourceArc<StreamResource>, rustler::Error> {
+            let encryptor = stream_encryptor(key.as_slice(), nonce_prefix.as_slice(), aad.as_slice())?;
+            stream_resource(Some($crate::stream::Stream::Encrypt(encryptor)))
+        }
+
+        #[doc = concat!("Start a streaming ", $name, " decryption")]
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce_prefix: ", $prefix, " bytes, as given to `stream_encrypt_init/3`")]
+        /// - aad: variable length
+        ///
+        /// Returns:
+        /// - Ok(stream) to pass to `stream_update/2` and `stream_final/1`
+        /// - Err for invalid parameters
+        #[rustler::nif]
+        fn stream_decrypt_init(
+            key: rustler::Binary,
+            nonce_prefix: rustler::Binary,
+            aad: rustler::Binary,
+        ) -> Result<rustler::ResourceArc<StreamResource>, rustler::Error> {
+            let decryptor = stream_decryptor(key.as_slice(), nonce_prefix.as_slice(), aad.as_slice())?;
+            stream_resource(Some($crate::stream::Stream::Decrypt(decryptor)))
+        }
+
+        /// Feed the next piece of a stream
+        ///
+        /// Decrypted output has been authenticated segment by segment, but the
+        /// stream is only known to be complete once `stream_final/1` succeeds.
+        ///
+        /// Parameters:
+        /// - stream: Resource from `stream_encrypt_init/3` or `stream_decrypt_init/3`
+        /// - data: Next piece of input (any size)
+        ///
+        /// Returns:
+        /// - Ok(output) for every segment completed so far (may be empty)
+        /// - Err if a segment fails authentication (the stream can't be used
+        ///   again) or the stream was already finished
+        #[rustler::nif(schedule = "DirtyCpu")]
+        fn stream_update<'a>(
+            env: rustler::Env<'a>,
+            stream: rustler::ResourceArc<StreamResource>,
+            data: rustler::Binary,
+        ) -> Result<rustler::Binary<'a>, rustler::Error> {
+            let mut guard = stream.state.lock().unwrap();
+            let state = guard
+                .as_mut()
+                .ok_or_else(|| rustler::Error::RaiseTerm(Box::new("stream finished")))?;
+
+            let _reservation = $crate::memory::reserve(data.len())?;
+            let output = match state.update(data.as_slice()) {
+                Ok(output) => output,
+                Err(e) => {
+                    guard.take();
+                    return Err($crate::cipher_nifs::stream_error(e));
+                }
+            };
+
+            Ok($crate::cipher_nifs::to_binary(env, &output))
+        }
+
+        /// Finish a stream
+        ///
+        /// Parameters:
+        /// - stream: Resource from `stream_encrypt_init/3` or `stream_decrypt_init/3`
+        ///
+        /// Returns:
+        /// - Ok(output) for the final segment
+        /// - Err if the final segment fails authentication or the stream was
+        ///   truncated, or the stream was already finished
+        #[rustler::nif]
+        fn stream_final<'a>(
+            env: rustler::Env<'a>,
+            stream: rustler::ResourceArc<StreamResource>,
+        ) -> Result<rustler::Binary<'a>, rustler::Error> {
+            let state = stream
+                .state
+                .lock()
+                .unwrap()
+                .take()
+                .ok_or_else(|| rustler::Error::RaiseTerm(Box::new("stream finished")))?;
+
+            let output = state.finish().map_err($crate::cipher_nifs::stream_error)?;
+
+            Ok($crate::cipher_nifs::to_binary(env, &output))
+        }
+
+        #[doc = concat!($name, " STREAM encryption in one call")]
+        ///
+        /// Gives exactly the bytes `stream_encrypt_init/3`, `stream_update/2` and
+        /// `stream_final/1` would for the same input; see `stream` for the format.
+        /// Runs on a dirty CPU scheduler since the input is a whole file, and
+        /// the segments are sealed in parallel.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce_prefix: ", $prefix, " bytes, unique per stream under a key")]
+        /// - plaintext: variable length
+        /// - aad: variable length, authenticated with every segment
+        ///
+        /// Returns:
+        /// - Ok(sealed): the segments, each ciphertext followed by its tag
+        /// - Err for invalid parameters
+        #[rustler::nif(schedule = "DirtyCpu")]
+        fn seal_stream<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            nonce_prefix: rustler::Binary,
+            plaintext: rustler::Binary,
+            aad: rustler::Binary,
+        ) -> Result<rustler::Binary<'a>, rustler::Error> {
+            let encryptor = stream_encryptor(key.as_slice(), nonce_prefix.as_slice(), aad.as_slice())?;
+            let _reservation = $crate::memory::reserve(plaintext.len())?;
+            let output = encryptor
+                .seal_all(plaintext.as_slice())
+                .map_err($crate::cipher_nifs::stream_error)?;
+
+            Ok($crate::cipher_nifs::to_binary(env, &output))
+        }
+
+        #[doc = concat!($name, " STREAM decryption in one call")]
+        ///
+        /// Runs on a dirty CPU scheduler since the input is a whole file, and
+        /// the segments are opened in parallel.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce_prefix: ", $prefix, " bytes, as given to `seal_stream/4`")]
+        /// - sealed: output of `seal_stream/4` or the streaming NIFs
+        /// - aad: variable length
+        ///
+        /// Returns:
+        /// - Ok(plaintext)
+        /// - Err if any segment fails authentication, segments were reordered or
+        ///   the stream was truncated, or for invalid parameters
+        #[rustler::nif(schedule = "DirtyCpu")]
+        fn open_stream<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            nonce_prefix: rustler::Binary,
+            sealed: rustler::Binary,
+            aad: rustler::Binary,
+        ) -> Result<rustler::Binary<'a>, rustler::Error> {
+            let decryptor = stream_decryptor(key.as_slice(), nonce_prefix.as_slice(), aad.as_slice())?;
+            let _reservation = $crate::memory::reserve(sealed.len())?;
+            let output = decryptor
+                .open_all(sealed.as_slice())
+                .map_err($crate::cipher_nifs::stream_error)?;
+
+            Ok($crate::cipher_nifs::to_binary(env, &output))
+        }
+
+        #[doc = concat!($name, " STREAM decryption of a byte range")]
+        ///
+        /// Opens only the segments under the range, plus the final segment so a
+        /// truncated stream is still rejected.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce_prefix: ", $prefix, " bytes, as given to `seal_stream/4`")]
+        /// - sealed: output of `seal_stream/4` or the streaming NIFs
+        /// - aad: variable length
+        /// - range: `{offset, length}` in plaintext bytes; clipped to the
+        ///   plaintext, so reading past the end returns fewer bytes
+        ///
+        /// Returns:
+        /// - Ok(plaintext) for the range
+        /// - Err if a segment read fails authentication or the stream was
+        ///   truncated, or for invalid parameters
+        #[rustler::nif(schedule = "DirtyCpu")]
+        fn open_range<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            nonce_prefix: rustler::Binary,
+            sealed: rustler::Binary,
+            aad: rustler::Binary,
+            range: (usize, usize),
+        ) -> Result<rustler::Binary<'a>, rustler::Error> {
+            let decryptor = stream_decryptor(key.as_slice(), nonce_prefix.as_slice(), aad.as_slice())?;
+            let (offset, length) = range;
+            let _reservation = $crate::memory::reserve(length.min(sealed.len()))?;
+            let output = decryptor
+                .open_range(sealed.as_slice(), offset, length)
+                .map_err($crate::cipher_nifs::stream_error)?;
+
+            Ok($crate::cipher_nifs::to_binary(env, &output))
+        }
+
+        /// A `seal_stream_yielding/4` or `open_stream_yielding/4` call between slices
+        struct YieldResource {
+            state: std::sync::Mutex<
+                Option<(
+                    $crate::stream::Stream<$cipher>,
+                    Vec<u8>,
+                    $crate::memory::Reservation,
+                )>,
+            >,
+        }
+
+        #[rustler::resource_impl]
+        impl rustler::Resource for YieldResource {}
+
+        /// Run a yielding STREAM call over `input` from `offset` on
+        ///
+        /// Each slice is charged against the timeslice by the time it took. Once
+        /// the timeslice is used up the call yields, to be continued on a normal
+        /// scheduler from where it stopped.
+        fn stream_yield<'a>(
+            env: rustler::Env<'a>,
+            resource: rustler::ResourceArc<YieldResource>,
+            input: rustler::Binary<'a>,
+            mut offset: usize,
+        ) -> Result<$crate::reschedule::Dispatch<'a, rustler::Binary<'a>>, rustler::Error> {
+            use rustler::Encoder;
+            let mut guard = resource.state.lock().unwrap();
+
+            while offset < input.len() {
+                let started = std::time::Instant::now();
+                let end = input.len().min(offset + $crate::cipher_nifs::YIELD_SLICE);
+                let (stream, output, _) = guard.as_mut().ok_or(rustler::Error::BadArg)?;
+                match stream.update(&input[offset..end]) {
+                    Ok(piece) => output.extend_from_slice(&piece),
+                    Err(e) => {
+                        guard.take();
+                        return Err($crate::cipher_nifs::stream_error(e));
+                    }
+                }
+                offset = end;
+
+                // A timeslice is about a millisecond
+                let percent = (started.elapsed().as_micros() / 10).clamp(1, 100) as i32;
+                if rustler::schedule::consume_timeslice(env, percent) && offset < input.len() {
+                    drop(guard);
+                    let args = vec![resource.encode(env), input.to_term(env), offset.encode(env)];
+                    return Ok($crate::reschedule::Dispatch::yielding(
+                        "stream_yield",
+                        stream_yield_continue,
+                        args,
+                    ));
+                }
+            }
+
+            let (stream, mut output, _reservation) = guard.take().ok_or(rustler::Error::BadArg)?;
+            output.extend(stream.finish().map_err($crate::cipher_nifs::stream_error)?);
+
+            Ok($crate::reschedule::Dispatch::Done(
+                $crate::cipher_nifs::to_binary(env, &output),
+            ))
+        }
+
+        /// `stream_yield` picked up again after yielding
+        unsafe extern "C" fn stream_yield_continue(
+            env: rustler::codegen_runtime::NIF_ENV,
+            argc: rustler::codegen_runtime::c_int,
+            argv: *const rustler::codegen_runtime::NIF_TERM,
+        ) -> rustler::codegen_runtime::NIF_TERM {
+            $crate::reschedule::run(env, argc, argv, |env, args| {
+                let result = stream_yield(env, args[0].decode()?, args[1].decode()?, args[2].decode()?);
+                Ok($crate::reschedule::returned(env, result))
+            })
+        }
+
+        /// The output is reserved for the whole call, across yields
+        fn yield_resource(
+            stream: $crate::stream::Stream<$cipher>,
+            input_len: usize,
+        ) -> Result<rustler::ResourceArc<YieldResource>, rustler::Error> {
+            let reservation = $crate::memory::reserve(input_len)?;
+            Ok(rustler::ResourceArc::new(YieldResource {
+                state: std::sync::Mutex::new(Some((stream, Vec::new(), reservation))),
+            }))
+        }
+
+        #[doc = concat!($name, " STREAM encryption in one call, yielding instead of running dirty")]
+        ///
+        /// Returns the same as `seal_stream/4` but stays on a normal scheduler:
+        /// the input is sealed in 1 MiB slices and the call yields whenever its
+        /// timeslice is used up, so long inputs don't hold up other processes and
+        /// no dirty scheduler is needed.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce_prefix: ", $prefix, " bytes, unique per stream under a key")]
+        /// - plaintext: variable length
+        /// - aad: variable length, authenticated with every segment
+        ///
+        /// Returns:
+        /// - Ok(sealed): the segments, each ciphertext followed by its tag
+        /// - Err for invalid parameters
+        #[rustler::nif]
+        fn seal_stream_yielding<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary<'a>,
+            nonce_prefix: rustler::Binary<'a>,
+            plaintext: rustler::Binary<'a>,
+            aad: rustler::Binary<'a>,
+        ) -> Result<$crate::reschedule::Dispatch<'a, rustler::Binary<'a>>, rustler::Error> {
+            let encryptor = stream_encryptor(key.as_slice(), nonce_prefix.as_slice(), aad.as_slice())?;
+            let resource = yield_resource($crate::stream::Stream::Encrypt(encryptor), plaintext.len())?;
+            stream_yield(env, resource, plaintext, 0)
+        }
+
+        #[doc = concat!($name, " STREAM decryption in one call, yielding instead of running dirty")]
+        ///
+        /// Returns the same as `open_stream/4`, opening 1 MiB slices on a normal
+        /// scheduler and yielding between them as `seal_stream_yielding/4` does.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce_prefix: ", $prefix, " bytes, as given to `seal_stream/4`")]
+        /// - sealed: output of `seal_stream/4` or the streaming NIFs
+        /// - aad: variable length
+        ///
+        /// Returns:
+        /// - Ok(plaintext)
+        /// - Err if any segment fails authentication, segments were reordered or
+        ///   the stream was truncated, or for invalid parameters
+        #[rustler::nif]
+        fn open_stream_yielding<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary<'a>,
+            nonce_prefix: rustler::Binary<'a>,
+            sealed: rustler::Binary<'a>,
+            aad: rustler::Binary<'a>,
+        ) -> Result<$crate::reschedule::Dispatch<'a, rustler::Binary<'a>>, rustler::Error> {
+            let decryptor = stream_decryptor(key.as_slice(), nonce_prefix.as_slice(), aad.as_slice())?;
+            let resource = yield_resource($crate::stream::Stream::Decrypt(decryptor), sealed.len())?;
+            stream_yield(env, resource, sealed, 0)
+        }
+
+        #[doc = concat!($name, " STREAM encryption from one file to another")]
+        ///
+        /// The input is read and sealed one 64 KiB segment at a time, so
+        /// files larger than RAM work (see `stream_file`). The output file holds
+        /// exactly what `seal_stream/4` would return. Runs on a dirty I/O
+        /// scheduler.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce_prefix: ", $prefix, " bytes, unique per stream under a key")]
+        /// - input_path: plaintext file
+        /// - output_path: sealed file, created or truncated
+        /// - aad: variable length, authenticated with every segment
+        ///
+        /// Returns:
+        /// - :ok
+        /// - {:error, {:io_error, reason}} if a file can't be read or written
+        /// - Err for invalid parameters
+        #[rustler::nif(schedule = "DirtyIo")]
+        fn seal_stream_file<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            nonce_prefix: rustler::Binary,
+            input_path: String,
+            output_path: String,
+            aad: rustler::Binary,
+        ) -> Result<rustler::Term<'a>, rustler::Error> {
+            let encryptor = stream_encryptor(key.as_slice(), nonce_prefix.as_slice(), aad.as_slice())?;
+            let result = $crate::stream_file::seal_file(
+                encryptor,
+                std::path::Path::new(&input_path),
+                std::path::Path::new(&output_path),
+            );
+            $crate::cipher_nifs::stream_file_result(env, result, &input_path, &output_path)
+        }
+
+        #[doc = concat!($name, " STREAM decryption from one file to another")]
+        ///
+        /// The input is read and opened one segment at a time. If any
+        /// segment fails authentication or the stream was truncated, the output
+        /// file is removed. Runs on a dirty I/O scheduler.
+        ///
+        /// Parameters:
+        #[doc = concat!("- key: ", $key, " bytes")]
+        #[doc = concat!("- nonce_prefix: ", $prefix, " bytes, as given when sealing")]
+        /// - input_path: sealed file
+        /// - output_path: plaintext file, created or truncated
+        /// - aad: variable length
+        ///
+        /// Returns:
+        /// - :ok
+        /// - {:error, {:io_error, reason}} if a file can't be read or written
+        /// - Err if authentication fails or parameters invalid
+        #[rustler::nif(schedule = "DirtyIo")]
+        fn open_stream_file<'a>(
+            env: rustler::Env<'a>,
+            key: rustler::Binary,
+            nonce_prefix: rustler::Binary,
+            input_path: String,
+            output_path: String,
+            aad: rustler::Binary,
+        ) -> Result<rustler::Term<'a>, rustler::Error> {
+            let decryptor = stream_decryptor(key.as_slice(), nonce_prefix.as_slice(), aad.as_slice())?;
+            let result = $crate::stream_file::open_file(
+                decryptor,
+                std::path::Path::new(&input_path),
+                std::path::Path::new(&output_path),
+            );
+            $crate::cipher_nifs::stream_file_result(env, result, &input_path, &output_path)
+        }
+
+        /// Cap the bytes this library's NIFs hold in buffers at once
+        ///
+        /// Counts the message-sized buffers of every call in progress. Calls
+        /// that would go past the cap return `{:error, :memory_limit}` instead
+        /// of allocating, as do calls the allocator can't serve. Each cipher
+        /// library has its own cap; there is none until this is called.
+        ///
+        /// Parameters:
+        /// - bytes: the cap, or 0 to remove it
+        ///
+        /// Returns:
+        /// - :ok
+        #[rustler::nif]
+        fn set_memory_limit(bytes: usize) -> rustler::Atom {
+            $crate::memory::set_limit(bytes);
+            rustler::types::atom::ok()
+        }
+
+        /// Measure encryption throughput of every algorithm in this library
+        ///
+        /// Each algorithm seals `message_size`-byte messages back to back for
+        /// about `duration_ms`, one after another, so the call takes roughly
+        /// `duration_ms` times the number of algorithms.
+        ///
+        /// Parameters:
+        /// - message_size: 1 byte to 64 MiB
+        /// - duration_ms: 1 to 10_000, per algorithm
+        ///
+        /// Returns:
+        /// - %{algorithm => MB/s}
+        /// - Err for out-of-range parameters
+        #[rustler::nif(schedule = "DirtyCpu")]
+        fn benchmark(
+            env: rustler::Env,
+            message_size: usize,
+            duration_ms: u64,
+        ) -> Result<rustler::Term, rustler::Error> {
+            $crate::cipher_nifs::benchmark(env, $subjects, message_size, duration_ms)
+        }
+
+        /// Rank this library's algorithms by speed on this machine
+        ///
+        /// The first call calibrates (16 KiB messages, median of five 20 ms
+        /// samples per algorithm, so a few hundred milliseconds) and later calls
+        /// reuse the result. An algorithm's security level is its key length in
+        /// bits. The Elixir side merges the lists from every cipher library to
+        /// pick a default cascade.
+        ///
+        /// Parameters:
+        /// - security_level: minimum bits, e.g. 128 or 256
+        ///
+        /// Returns:
+        /// - [{algorithm, MB/s}] fastest first
+        /// - [] if nothing in this library is strong enough
+        #[rustler::nif(schedule = "DirtyCpu")]
+        fn fastest_algorithms(
+            env: rustler::Env,
+            security_level: u32,
+        ) -> Result<Vec<(rustler::Atom, f64)>, rustler::Error> {
+            $crate::cipher_nifs::fastest_algorithms(env, $subjects, security_level)
+        }
+    };
+}
@@ -0,0 +1,98 @@
+//! iodata arguments
+//!
+//! `encrypt/4` and `decrypt/5` take the message and AAD as iodata: a
+//! binary, or a list of binaries, bytes and further such lists, possibly
+//! ending in a binary tail. The Elixir side can pass what the filter
+//! pipeline builds without flattening it first. The list is walked here
+//! and its binaries borrowed; the message is then copied piece by piece
+//! into the output binary, which has to be written anyway.
+
+use rustler::{Binary, Decoder, Encoder, Env, Error, NifResult, Term};
+use std::borrow::Cow;
+
+enum Piece<'a> {
+    Bytes(&'a [u8]),
+    Byte(u8),
+}
+
+/// A decoded iodata term, borrowing the binaries it is made of
+pub struct IoData<'a> {
+    term: Term<'a>,
+    pieces: Vec<Piece<'a>>,
+    len: usize,
+}
+
+impl<'a> IoData<'a> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Copy the contents into `out`, which must be `len()` bytes
+    pub fn copy_to(&self, out: &mut [u8]) {
+        let mut at = 0;
+        for piece in &self.pieces {
+            match *piece {
+                Piece::Bytes(bytes) => {
+                    out[at..at + bytes.len()].copy_from_slice(bytes);
+                    at += bytes.len();
+                }
+                Piece::Byte(byte) => {
+                    out[at] = byte;
+                    at += 1;
+                }
+            }
+        }
+    }
+
+    /// The contents as one slice; only a list is flattened into a copy
+    pub fn to_cow(&self) -> Cow<'a, [u8]> {
+        match self.pieces.as_slice() {
+            [] => Cow::Borrowed(&[]),
+            [Piece::Bytes(bytes)] => Cow::Borrowed(bytes),
+            _ => {
+                let mut out = vec![0; self.len];
+                self.copy_to(&mut out);
+                Cow::Owned(out)
+            }
+        }
+    }
+}
+
+impl<'a> Decoder<'a> for IoData<'a> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let mut pieces = Vec::new();
+        let mut len = 0;
+
+        // Explicit stack, so deeply nested lists can't overflow ours. The
+        // flag marks list elements, the only place a byte may appear.
+        let mut pending = vec![(term, false)];
+        while let Some((term, in_list)) = pending.pop() {
+            if let Ok(binary) = Binary::from_term(term) {
+                len += binary.len();
+                pieces.push(Piece::Bytes(binary.as_slice()));
+            } else if term.is_empty_list() {
+                continue;
+            } else if let Ok((head, tail)) = term.list_get_cell() {
+                pending.push((tail, false));
+                pending.push((head, true));
+            } else if let (true, Ok(byte)) = (in_list, term.decode::<u8>()) {
+                len += 1;
+                pieces.push(Piece::Byte(byte));
+            } else {
+                return Err(Error::BadArg);
+            }
+        }
+
+        Ok(IoData { term, pieces, len })
+    }
+}
+
+impl Encoder for IoData<'_> {
+    fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
+        self.term.encode(env)
+    }
+}
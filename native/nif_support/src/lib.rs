@@ -0,0 +1,16 @@
+//! Code shared by the GitFoil cipher NIFs
+//!
+//! The STREAM format and its file variant, batch sealing, iodata
+//! arguments, dirty-scheduler continuations, the memory budget and
+//! throughput measurement work the same for every AEAD. A cipher crate
+//! implements `stream::SegmentCipher` for its key type (RustCrypto AEADs
+//! get it for free) and generates its shared NIFs with `cipher_nifs!`.
+
+pub mod batch;
+pub mod bench;
+pub mod cipher_nifs;
+pub mod iodata;
+pub mod memory;
+pub mod reschedule;
+pub mod stream;
+pub mod stream_file;
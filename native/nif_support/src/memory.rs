@@ -0,0 +1,127 @@
+//! Budget for the large buffers a NIF call allocates
+//!
+//! A global allocator can't refuse an allocation softly: Rust calls
+//! `handle_alloc_error`, which aborts the whole VM. So the NIFs reserve
+//! the size of their message-sized buffers here before allocating them,
+//! and allocate those buffers fallibly. Past the limit, or if the
+//! allocation itself fails, the call returns `{:error, :memory_limit}`.
+//!
+//! The budget counts bytes held by live `Reservation`s across every
+//! concurrent call into one library. Each cipher library is its own
+//! cdylib, so each has its own budget. There is no limit until
+//! `set_limit` is called.
+
+use rustler::{Encoder, Env, Error, OwnedBinary, Term};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod atoms {
+    rustler::atoms! {
+        memory_limit,
+    }
+}
+
+/// Bytes held by live reservations
+static RESERVED: AtomicUsize = AtomicUsize::new(0);
+
+/// Most bytes reservations may hold at once
+static LIMIT: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Cap the bytes reservations may hold at once; 0 removes the cap
+///
+/// Calls already holding more keep their reservations; new ones fail
+/// until enough are released.
+pub fn set_limit(bytes: usize) {
+    let limit = if bytes == 0 { usize::MAX } else { bytes };
+    LIMIT.store(limit, Ordering::Relaxed);
+}
+
+/// The `:memory_limit` reason, made into an atom only when encoded
+struct MemoryLimit;
+
+impl Encoder for MemoryLimit {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        atoms::memory_limit().encode(env)
+    }
+}
+
+/// `{:error, :memory_limit}`
+pub fn limit_error() -> Error {
+    Error::Term(Box::new(MemoryLimit))
+}
+
+/// Bytes counted against the budget until dropped
+#[must_use = "the bytes are released as soon as the reservation is dropped"]
+pub struct Reservation {
+    bytes: usize,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        RESERVED.fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
+
+/// Reserve `bytes` for the rest of the call
+pub fn reserve(bytes: usize) -> Result<Reservation, Error> {
+    let limit = LIMIT.load(Ordering::Relaxed);
+    RESERVED
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |reserved| {
+            reserved.checked_add(bytes).filter(|&total| total <= limit)
+        })
+        .map_err(|_| limit_error())?;
+    Ok(Reservation { bytes })
+}
+
+/// Allocate an output binary, failing softly if the VM can't
+pub fn binary(len: usize) -> Result<OwnedBinary, Error> {
+    OwnedBinary::new(len).ok_or_else(limit_error)
+}
+
+/// Allocate an empty buffer with room for `capacity` bytes, failing
+/// softly if the allocator can't
+pub fn buffer(capacity: usize) -> Result<Vec<u8>, Error> {
+    let mut buffer = Vec::new();
+    buffer
+        .try_reserve_exact(capacity)
+        .map_err(|_| limit_error())?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// The budget is process-wide; keep these tests from overlapping
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_reservations_count_against_the_limit() {
+        let _serial = SERIAL.lock().unwrap();
+        set_limit(100);
+
+        let first = reserve(60).ok().unwrap();
+        assert!(reserve(60).is_err());
+        drop(first);
+        assert!(reserve(60).is_ok());
+
+        set_limit(0);
+    }
+
+    #[test]
+    fn test_no_limit_by_default() {
+        let _serial = SERIAL.lock().unwrap();
+        set_limit(0);
+
+        let held = reserve(usize::MAX / 2).ok().unwrap();
+        assert!(reserve(usize::MAX / 4).is_ok());
+        assert!(reserve(usize::MAX).is_err());
+        drop(held);
+    }
+
+    #[test]
+    fn test_buffer_fails_softly() {
+        assert!(buffer(1024).ok().unwrap().capacity() >= 1024);
+        assert!(buffer(usize::MAX).is_err());
+    }
+}
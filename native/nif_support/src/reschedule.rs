@@ -0,0 +1,105 @@
+//! Continuing a NIF call in another function
+//!
+//! A NIF can hand its arguments to a plain `extern "C"` function through
+//! `enif_schedule_nif` instead of returning. `encrypt/4` and `decrypt/5` do
+//! this to move large inputs onto a dirty CPU scheduler, and the yielding
+//! STREAM NIFs to give up the scheduler between slices and carry on later.
+//! rustler 0.34 only exposes that call through its codegen runtime, so the
+//! glue `#[rustler::nif]` would generate for a continuation lives here.
+
+use rustler::codegen_runtime::{
+    c_int, handle_nif_result, NifReturnable, NifReturned, NIF_ENV, NIF_TERM,
+};
+use rustler::{Env, Error, SchedulerFlags, Term};
+use std::ffi::CString;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Raw NIF signature `enif_schedule_nif` expects
+pub type RawNif = unsafe extern "C" fn(NIF_ENV, c_int, *const NIF_TERM) -> NIF_TERM;
+
+/// Result of a NIF that either finished or continues elsewhere
+pub enum Dispatch<'a, T> {
+    Done(T),
+    Continue {
+        name: &'static str,
+        flags: SchedulerFlags,
+        fun: RawNif,
+        args: Vec<Term<'a>>,
+    },
+}
+
+impl<'a, T> Dispatch<'a, T> {
+    /// Continue in `fun` on a dirty CPU scheduler with `args`
+    pub fn dirty(name: &'static str, fun: RawNif, args: Vec<Term<'a>>) -> Self {
+        Dispatch::Continue {
+            name,
+            flags: SchedulerFlags::DirtyCpu,
+            fun,
+            args,
+        }
+    }
+
+    /// Yield, then continue in `fun` on a normal scheduler with `args`
+    pub fn yielding(name: &'static str, fun: RawNif, args: Vec<Term<'a>>) -> Self {
+        Dispatch::Continue {
+            name,
+            flags: SchedulerFlags::Normal,
+            fun,
+            args,
+        }
+    }
+}
+
+unsafe impl<T: NifReturnable> NifReturnable for Dispatch<'_, T> {
+    unsafe fn into_returned(self, env: Env) -> NifReturned {
+        match self {
+            Dispatch::Done(value) => value.into_returned(env),
+            Dispatch::Continue {
+                name,
+                flags,
+                fun,
+                args,
+            } => NifReturned::Reschedule {
+                fun_name: CString::new(name).unwrap(),
+                flags,
+                fun,
+                args: args.iter().map(|arg| arg.as_c_arg()).collect(),
+            },
+        }
+    }
+}
+
+/// Turn a NIF body's result into what the VM gets back
+pub fn returned<T: NifReturnable>(env: Env, result: Result<T, Error>) -> NifReturned {
+    handle_nif_result(Ok(result), env)
+}
+
+/// Body of a continuation: run `f` on its arguments
+///
+/// An `Err` from `f`, such as a failed decode, is raised, and a panic in
+/// `f` is caught and raised like in any other NIF.
+///
+/// # Safety
+///
+/// `env`, `argc` and `argv` must be the ones the VM called the continuation
+/// with.
+pub unsafe fn run(
+    env: NIF_ENV,
+    argc: c_int,
+    argv: *const NIF_TERM,
+    f: impl for<'a> FnOnce(Env<'a>, &[Term<'a>]) -> Result<NifReturned, Error>,
+) -> NIF_TERM {
+    let lifetime = ();
+    let env = Env::new(&lifetime, env);
+    let args = std::slice::from_raw_parts(argv, argc as usize)
+        .iter()
+        .map(|&term| Term::new(env, term))
+        .collect::<Vec<_>>();
+
+    match panic::catch_unwind(AssertUnwindSafe(|| f(env, &args))) {
+        Ok(Ok(returned)) => returned,
+        Ok(Err(error)) => returned::<Term>(env, Err(error)),
+        Err(panic) => handle_nif_result::<Term>(Err(panic), env),
+    }
+    .apply(env)
+}
@@ -0,0 +1,175 @@
+//! STREAM format between files
+//!
+//! The input is read a segment's worth at a time and fed through the
+//! `Encryptor` or `Decryptor`, and each result is written out as soon as
+//! it is ready. Memory use stays at a few segments whatever the file size,
+//! so files larger than RAM work. The output holds the same bytes
+//! `Encryptor::seal_all` or `Decryptor::open_all` would return.
+//!
+//! The input is read rather than memory-mapped: a mapped file that another
+//! process truncates mid-run faults with SIGBUS and takes the whole VM
+//! down, while a short read just ends the stream, which then fails to
+//! authenticate like any other truncation.
+//!
+//! Opening writes plaintext before the final segment has been checked. If
+//! anything fails after the output file was created it is removed, so no
+//! partial plaintext is left behind.
+
+use crate::stream::{Decryptor, Encryptor, SegmentCipher, Stream, StreamError, SEGMENT_SIZE};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum FileError {
+    /// Opening or reading the input failed
+    Read(io::Error),
+    /// Creating or writing the output failed
+    Write(io::Error),
+    Stream(StreamError),
+}
+
+impl From<StreamError> for FileError {
+    fn from(error: StreamError) -> Self {
+        FileError::Stream(error)
+    }
+}
+
+/// Create `path`, let `f` write to it, and remove it again if `f` fails
+fn with_output(
+    path: &Path,
+    f: impl FnOnce(&mut BufWriter<File>) -> Result<(), FileError>,
+) -> Result<(), FileError> {
+    let mut out = BufWriter::new(File::create(path).map_err(FileError::Write)?);
+    let result = f(&mut out).and_then(|()| out.flush().map_err(FileError::Write));
+    drop(out);
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+/// Feed the file at `input` through `stream` into `output`
+fn run<C: SegmentCipher>(
+    mut stream: Stream<C>,
+    input: &Path,
+    output: &Path,
+) -> Result<(), FileError> {
+    let mut file = File::open(input).map_err(FileError::Read)?;
+    with_output(output, |out| {
+        let mut chunk = vec![0u8; SEGMENT_SIZE];
+        loop {
+            let read = match file.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(FileError::Read(e)),
+            };
+            let processed = stream.update(&chunk[..read])?;
+            out.write_all(&processed).map_err(FileError::Write)?;
+        }
+        out.write_all(&stream.finish()?).map_err(FileError::Write)
+    })
+}
+
+/// Seal the file at `input` into `output`
+pub fn seal_file<C: SegmentCipher>(
+    encryptor: Encryptor<C>,
+    input: &Path,
+    output: &Path,
+) -> Result<(), FileError> {
+    run(Stream::Encrypt(encryptor), input, output)
+}
+
+/// Open the sealed file at `input` into `output`
+pub fn open_file<C: SegmentCipher>(
+    decryptor: Decryptor<C>,
+    input: &Path,
+    output: &Path,
+) -> Result<(), FileError> {
+    run(Stream::Decrypt(decryptor), input, output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::tests::{message, Toy, PREFIX};
+    use crate::stream::SEGMENT_SIZE;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "gitfoil-stream-file-{}-{}",
+            std::process::id(),
+            name
+        ))
+    }
+
+    fn encryptor() -> Encryptor<Toy> {
+        Encryptor::new(Toy, PREFIX, b"aad").unwrap()
+    }
+
+    fn decryptor() -> Decryptor<Toy> {
+        Decryptor::new(Toy, PREFIX, b"aad").unwrap()
+    }
+
+    #[test]
+    fn test_file_roundtrip_matches_in_memory() {
+        for (name, len) in [
+            ("empty", 0),
+            ("small", 100),
+            ("multi", 2 * SEGMENT_SIZE + 7),
+        ] {
+            let plain = temp_path(&format!("{}.plain", name));
+            let sealed = temp_path(&format!("{}.sealed", name));
+            let opened = temp_path(&format!("{}.opened", name));
+            let data = message(len);
+            std::fs::write(&plain, &data).unwrap();
+
+            seal_file(encryptor(), &plain, &sealed).unwrap();
+            let sealed_bytes = std::fs::read(&sealed).unwrap();
+            assert_eq!(sealed_bytes, encryptor().seal_all(&data).unwrap());
+
+            open_file(decryptor(), &sealed, &opened).unwrap();
+            assert_eq!(std::fs::read(&opened).unwrap(), data);
+
+            for path in [plain, sealed, opened] {
+                std::fs::remove_file(path).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_failed_open_removes_output() {
+        let plain = temp_path("tamper.plain");
+        let sealed = temp_path("tamper.sealed");
+        let opened = temp_path("tamper.opened");
+        std::fs::write(&plain, message(2 * SEGMENT_SIZE)).unwrap();
+        seal_file(encryptor(), &plain, &sealed).unwrap();
+
+        // Drop the final segment: the first one verifies and is written
+        // before the truncation shows up
+        let bytes = std::fs::read(&sealed).unwrap();
+        std::fs::write(&sealed, &bytes[..SEGMENT_SIZE + Toy::TAG_SIZE]).unwrap();
+
+        let result = open_file(decryptor(), &sealed, &opened);
+        assert!(matches!(
+            result,
+            Err(FileError::Stream(StreamError::Authentication))
+        ));
+        assert!(!opened.exists());
+
+        for path in [plain, sealed] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_missing_input() {
+        let output = temp_path("missing.out");
+        let result = seal_file(encryptor(), &temp_path("does-not-exist"), &output);
+        assert!(matches!(result, Err(FileError::Read(_))));
+        assert!(!output.exists());
+    }
+}
@@ -0,0 +1,119 @@
+//! Plaintext file descriptor NIF for GitFoil
+//!
+//! Hands decrypted content to external tools (diff and merge helpers)
+//! without writing it to a named temporary file.
+//!
+//! **Behaviour:**
+//! - `open/1` copies the plaintext into an anonymous memory-backed file
+//!   (Linux `memfd`) and returns a `/proc/<pid>/fd/<fd>` path that a child
+//!   process can read like any other file
+//! - `close/1` wipes the contents and releases the file immediately; if the
+//!   handle is simply dropped the same happens when it is garbage collected
+//! - Platforms without memfd get `{:error, :unsupported}`; callers decide
+//!   whether to fall back to a temporary file
+
+#[cfg(target_os = "linux")]
+mod memfd;
+
+use rustler::{Atom, Binary, Resource, ResourceArc};
+use std::sync::Mutex;
+
+rustler::init!("Elixir.GitFoil.Native.PlaintextFdNif");
+
+mod atoms {
+    rustler::atoms! {
+        ok,
+        closed,
+        unsupported,
+        io_error,
+    }
+}
+
+/// Name shown for the file in `/proc/<pid>/fd` listings
+#[cfg(target_os = "linux")]
+const MEMFD_NAME: &str = "gitfoil-plaintext";
+
+#[cfg(target_os = "linux")]
+type Backing = memfd::MemFile;
+
+#[cfg(not(target_os = "linux"))]
+type Backing = std::convert::Infallible;
+
+/// Plaintext file resource; `None` once closed
+struct PlaintextFdResource {
+    file: Mutex<Option<Backing>>,
+}
+
+#[rustler::resource_impl]
+impl Resource for PlaintextFdResource {}
+
+#[cfg(target_os = "linux")]
+fn create(plaintext: &[u8]) -> Result<Backing, (Atom, String)> {
+    memfd::MemFile::new(MEMFD_NAME, plaintext).map_err(|e| match e.kind() {
+        std::io::ErrorKind::Unsupported => (atoms::unsupported(), e.to_string()),
+        _ => (atoms::io_error(), e.to_string()),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn create(_plaintext: &[u8]) -> Result<Backing, (Atom, String)> {
+    Err((atoms::unsupported(), "memfd is only available on Linux".to_string()))
+}
+
+#[cfg(target_os = "linux")]
+fn backing_path(file: &Backing) -> String {
+    file.path().to_string()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn backing_path(file: &Backing) -> String {
+    match *file {}
+}
+
+/// Expose plaintext as a readable path
+///
+/// ## Parameters
+/// - plaintext: Decrypted content
+///
+/// ## Returns
+/// - {:ok, {handle, path}}: Keep `handle` alive until the child has exited
+/// - {:error, {:unsupported, reason}}: No memfd on this platform, or /proc is missing
+/// - {:error, {:io_error, reason}}: Creating or filling the file failed
+#[rustler::nif(schedule = "DirtyIo")]
+fn open(plaintext: Binary) -> Result<(ResourceArc<PlaintextFdResource>, String), (Atom, String)> {
+    let file = create(plaintext.as_slice())?;
+    let path = backing_path(&file);
+
+    let handle = ResourceArc::new(PlaintextFdResource {
+        file: Mutex::new(Some(file)),
+    });
+    Ok((handle, path))
+}
+
+/// Wipe the contents and release the file now
+///
+/// Safe to call more than once.
+///
+/// ## Returns
+/// - :ok
+#[rustler::nif]
+fn close(handle: ResourceArc<PlaintextFdResource>) -> Atom {
+    handle.file.lock().unwrap().take();
+    atoms::ok()
+}
+
+/// Path of an open handle
+///
+/// ## Returns
+/// - {:ok, path}
+/// - {:error, :closed}
+#[rustler::nif]
+fn path(handle: ResourceArc<PlaintextFdResource>) -> Result<String, Atom> {
+    handle
+        .file
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(backing_path)
+        .ok_or_else(atoms::closed)
+}
@@ -0,0 +1,133 @@
+//! Anonymous in-memory files (Linux `memfd_create`)
+//!
+//! A memfd lives only in RAM and has no directory entry, so plaintext
+//! written to it never reaches a filesystem. Other processes running as
+//! the same user open it through `/proc/<pid>/fd/<fd>`, which is how
+//! external diff and merge tools receive it as an ordinary path.
+//!
+//! Once filled, the file is sealed against writes and growth, so a tool
+//! holding the path can read the plaintext but cannot change it.
+//! Shrinking stays allowed for the wipe below.
+//!
+//! On drop the file is truncated to zero before the descriptor is closed.
+//! Truncation frees the pages, so a child that is still holding the file
+//! open sees an empty file instead of keeping the plaintext alive.
+
+use std::ffi::CString;
+use std::fs::File;
+use std::io::{self, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::path::Path;
+
+/// Plaintext held in an anonymous memory-backed file
+pub struct MemFile {
+    file: File,
+    path: String,
+}
+
+impl MemFile {
+    /// Create a memfd holding `data`
+    ///
+    /// `name` only shows up in `/proc/<pid>/fd` listings as `memfd:<name>`.
+    pub fn new(name: &str, data: &[u8]) -> io::Result<Self> {
+        let name = CString::new(name).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+
+        // CLOEXEC: children reach the file through its /proc path, never by
+        // inheriting the descriptor
+        // SAFETY: name is a valid NUL-terminated string
+        let fd = unsafe {
+            libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING)
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: fd is a freshly created descriptor that nothing else owns
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        let path = format!("/proc/{}/fd/{}", std::process::id(), fd);
+
+        if !Path::new(&path).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "/proc is not mounted",
+            ));
+        }
+
+        file.write_all(data)?;
+
+        // No F_SEAL_SHRINK: drop still has to truncate the file
+        // SAFETY: the descriptor is owned by file and still open
+        let sealed = unsafe {
+            libc::fcntl(
+                file.as_raw_fd(),
+                libc::F_ADD_SEALS,
+                libc::F_SEAL_WRITE | libc::F_SEAL_GROW,
+            )
+        };
+        if sealed < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(MemFile { file, path })
+    }
+
+    /// Path other processes can open to read the contents
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Drop for MemFile {
+    fn drop(&mut self) {
+        // Free the pages even if a child still holds the file open
+        // SAFETY: the descriptor is owned by self.file and still open
+        unsafe {
+            libc::ftruncate(self.file.as_raw_fd(), 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_contents_readable_through_path() {
+        let file = MemFile::new("test", b"secret plaintext").unwrap();
+        assert!(file.path().starts_with("/proc/"));
+        assert_eq!(fs::read(file.path()).unwrap(), b"secret plaintext");
+    }
+
+    #[test]
+    fn test_empty_contents() {
+        let file = MemFile::new("test", b"").unwrap();
+        assert_eq!(fs::read(file.path()).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_drop_wipes_open_readers() {
+        let file = MemFile::new("test", &[0x5A; 8192]).unwrap();
+        let reader = File::open(file.path()).unwrap();
+        drop(file);
+
+        // The still-open reader sees nothing
+        assert_eq!(reader.metadata().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_sealed_against_writes() {
+        let file = MemFile::new("test", b"secret plaintext").unwrap();
+        let written = fs::OpenOptions::new()
+            .write(true)
+            .open(file.path())
+            .and_then(|mut writer| writer.write_all(b"tampered"));
+
+        assert!(written.is_err());
+        assert_eq!(fs::read(file.path()).unwrap(), b"secret plaintext");
+    }
+
+    #[test]
+    fn test_rejects_nul_in_name() {
+        assert!(MemFile::new("bad\0name", b"x").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! Shannon entropy helpers
+//!
+//! Used to spot random-looking tokens (API keys, passwords, base64 blobs)
+//! that no pattern rule knows about, and to classify whole buffers as
+//! already encrypted/compressed before spending time on them.
+
+/// Shortest token considered for entropy detection
+pub const MIN_TOKEN_LEN: usize = 20;
+
+/// Longest token considered; longer runs are usually embedded data, not secrets
+pub const MAX_TOKEN_LEN: usize = 512;
+
+/// Minimum bits/char for a base64-like token to be reported
+pub const BASE64_THRESHOLD: f64 = 4.5;
+
+/// Minimum bits/char for a hex token to be reported
+pub const HEX_THRESHOLD: f64 = 3.0;
+
+/// Shortest hex token considered (128-bit keys and up)
+const MIN_HEX_LEN: usize = 32;
+
+/// Smallest buffer `estimate` will classify
+pub const MIN_ESTIMATE_LEN: usize = 256;
+
+/// Chi-square upper bound for uniform bytes: 255 degrees of freedom,
+/// mean 255, standard deviation sqrt(510) ~ 22.6, so about 4 sigma
+const CHI_SQUARE_RANDOM: f64 = 345.0;
+
+/// Shannon entropy above which non-uniform data is still treated as compressed
+const COMPRESSED_THRESHOLD: f64 = 7.5;
+
+fn histogram(data: &[u8]) -> [usize; 256] {
+    let mut counts = [0usize; 256];
+    for &b in data {
+        counts[b as usize] += 1;
+    }
+    counts
+}
+
+fn shannon_from(counts: &[usize; 256], len: usize) -> f64 {
+    if len == 0 {
+        return 0.0;
+    }
+
+    let len = len as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Shannon entropy of `data` in bits per byte (0.0 for empty input)
+pub fn shannon(data: &[u8]) -> f64 {
+    shannon_from(&histogram(data), data.len())
+}
+
+/// What a buffer most likely contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    /// Too short to tell
+    Unknown,
+    /// Byte distribution indistinguishable from uniform: ciphertext or random
+    Random,
+    /// Near-maximal entropy but measurably non-uniform: compressed data
+    Compressed,
+    /// Anything else: text, source code, structured binaries
+    Plain,
+}
+
+/// Byte-distribution statistics for a whole buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// Shannon entropy in bits per byte (0.0..=8.0)
+    pub shannon: f64,
+    /// Pearson chi-square statistic against a uniform byte distribution
+    pub chi_square: f64,
+    pub class: Class,
+}
+
+/// Estimate how random `data` is from its byte histogram
+///
+/// One pass over the data. Encrypted output passes the chi-square test for
+/// uniformity; compressed streams have close to 8 bits/byte of entropy but
+/// fail it because of headers and Huffman tables.
+pub fn estimate(data: &[u8]) -> Estimate {
+    let counts = histogram(data);
+    let shannon = shannon_from(&counts, data.len());
+
+    let expected = data.len() as f64 / 256.0;
+    let chi_square = if data.is_empty() {
+        0.0
+    } else {
+        counts
+            .iter()
+            .map(|&c| {
+                let d = c as f64 - expected;
+                d * d / expected
+            })
+            .sum()
+    };
+
+    let class = if data.len() < MIN_ESTIMATE_LEN {
+        Class::Unknown
+    } else if chi_square < CHI_SQUARE_RANDOM {
+        Class::Random
+    } else if shannon >= COMPRESSED_THRESHOLD {
+        Class::Compressed
+    } else {
+        Class::Plain
+    };
+
+    Estimate {
+        shannon,
+        chi_square,
+        class,
+    }
+}
+
+/// Base64/base64url/hex alphabet; `=` is left out so `key=value` splits
+/// into two tokens (base64 padding carries no entropy anyway)
+fn is_token_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'_' | b'-')
+}
+
+/// A random-looking token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub start: usize,
+    pub len: usize,
+    pub entropy: f64,
+}
+
+/// Find base64/hex-like tokens whose entropy exceeds the thresholds
+pub fn high_entropy_tokens(data: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < data.len() {
+        if !is_token_byte(data[i]) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < data.len() && is_token_byte(data[i]) {
+            i += 1;
+        }
+
+        let token = &data[start..i];
+        if token.len() < MIN_TOKEN_LEN || token.len() > MAX_TOKEN_LEN {
+            continue;
+        }
+
+        let entropy = shannon(token);
+        let is_hex = token.iter().all(u8::is_ascii_hexdigit);
+        let hit = if is_hex {
+            token.len() >= MIN_HEX_LEN && entropy >= HEX_THRESHOLD
+        } else {
+            entropy >= BASE64_THRESHOLD
+        };
+
+        if hit {
+            tokens.push(Token {
+                start,
+                len: token.len(),
+                entropy,
+            });
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shannon_bounds() {
+        assert_eq!(shannon(b""), 0.0);
+        assert_eq!(shannon(b"aaaaaaaa"), 0.0);
+        assert!((shannon(b"abab") - 1.0).abs() < 1e-12);
+
+        let all: Vec<u8> = (0..=255).collect();
+        assert!((shannon(&all) - 8.0).abs() < 1e-12);
+    }
+
+    /// xorshift64 stream, good enough to look uniform to a chi-square test
+    fn pseudo_random(len: usize) -> Vec<u8> {
+        let mut state = 0x9E3779B97F4A7C15u64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_estimate_random() {
+        let e = estimate(&pseudo_random(65536));
+        assert_eq!(e.class, Class::Random);
+        assert!(e.shannon > 7.99);
+        assert!(e.chi_square < CHI_SQUARE_RANDOM);
+    }
+
+    #[test]
+    fn test_estimate_plain_text() {
+        let text = b"The quick brown fox jumps over the lazy dog. ".repeat(100);
+        let e = estimate(&text);
+        assert_eq!(e.class, Class::Plain);
+        assert!(e.shannon < 5.0);
+    }
+
+    #[test]
+    fn test_estimate_compressed_like() {
+        // Uniform except for a run of zeros, like a header: high entropy,
+        // but far from uniform
+        let mut data = pseudo_random(65536);
+        data[..2048].fill(0);
+        let e = estimate(&data);
+        assert_eq!(e.class, Class::Compressed);
+    }
+
+    #[test]
+    fn test_estimate_small_and_empty() {
+        assert_eq!(estimate(b"").class, Class::Unknown);
+        assert_eq!(estimate(b"").shannon, 0.0);
+        assert_eq!(estimate(&pseudo_random(100)).class, Class::Unknown);
+    }
+
+    #[test]
+    fn test_detects_random_base64() {
+        let text = b"token: 7fQ2xZ9pL4mW8rT1vB6nK3cY5hJ0gD+s/ end";
+        let tokens = high_entropy_tokens(text);
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(&text[tokens[0].start..tokens[0].start + tokens[0].len], b"7fQ2xZ9pL4mW8rT1vB6nK3cY5hJ0gD+s/");
+    }
+
+    #[test]
+    fn test_detects_hex_key() {
+        let text = b"key=9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        let tokens = high_entropy_tokens(text);
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].start, 4);
+    }
+
+    #[test]
+    fn test_ignores_prose_and_repetition() {
+        assert!(high_entropy_tokens(b"the quick brown fox jumps over the lazy dog").is_empty());
+        assert!(high_entropy_tokens(b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa").is_empty());
+        assert!(high_entropy_tokens(b"some_long_identifier_name_in_code").is_empty());
+        assert!(high_entropy_tokens(b"short1Xy9").is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! Secrets scanner NIF for GitFoil
+//!
+//! Flags likely credentials in file contents before they are committed,
+//! powering `git veil audit`. The scanner only reports; deciding whether a
+//! file is already covered by an encryption rule is up to the caller.
+//!
+//! **Detectors:**
+//! - Known credential formats (AWS, GitHub, GitLab, Slack, Stripe, Google,
+//!   private key headers, JWTs) plus caller-supplied regexes
+//! - `password = ...`-style assignments
+//! - High-entropy base64/hex tokens
+//!
+//! `estimate_entropy/1` classifies whole buffers (random, compressed, plain)
+//! so callers can skip content that is already encrypted or compressed.
+//!
+//! Findings carry byte offsets only; matched secrets are never copied into
+//! the returned terms.
+
+mod entropy;
+mod rules;
+
+use entropy::Class;
+use nif_support::reschedule::{self, Dispatch};
+use rules::{Kind, Ruleset};
+use rustler::codegen_runtime::{c_int, NIF_ENV, NIF_TERM};
+use rustler::{Atom, Binary, Encoder, Env, NifMap, Resource, ResourceArc};
+use std::sync::Arc;
+
+rustler::init!("Elixir.GitFoil.Native.ScannerNif");
+
+/// Input size from which `estimate_entropy` continues on a dirty scheduler
+const DIRTY_THRESHOLD: usize = 64 * 1024;
+
+mod atoms {
+    rustler::atoms! {
+        pattern,
+        assignment,
+        entropy,
+        invalid_rule,
+        unknown,
+        random,
+        compressed,
+        plain,
+    }
+}
+
+/// Compiled ruleset resource
+struct RulesetResource {
+    rules: Arc<Ruleset>,
+}
+
+#[rustler::resource_impl]
+impl Resource for RulesetResource {}
+
+#[derive(NifMap)]
+struct Finding {
+    rule: String,
+    kind: Atom,
+    start: usize,
+    length: usize,
+    entropy: f64,
+}
+
+#[derive(NifMap)]
+struct EntropyEstimate {
+    shannon: f64,
+    chi_square: f64,
+    class: Atom,
+}
+
+/// Built-in ruleset
+///
+/// ## Returns
+/// - Ruleset resource for `scan/2`
+#[rustler::nif]
+fn default_rules() -> ResourceArc<RulesetResource> {
+    ResourceArc::new(RulesetResource {
+        rules: Ruleset::builtin(),
+    })
+}
+
+/// Compile the built-in rules plus custom patterns
+///
+/// ## Parameters
+/// - rules: List of `{name, regex}` tuples; regexes use Rust `regex` syntax
+///
+/// ## Returns
+/// - {:ok, ruleset}
+/// - {:error, {:invalid_rule, name}}: A pattern failed to compile
+#[rustler::nif]
+fn compile_rules(
+    rules: Vec<(String, String)>,
+) -> Result<ResourceArc<RulesetResource>, (Atom, String)> {
+    let ruleset = Ruleset::new(&rules).map_err(|name| (atoms::invalid_rule(), name))?;
+    Ok(ResourceArc::new(RulesetResource {
+        rules: Arc::new(ruleset),
+    }))
+}
+
+/// Names of the pattern rules in a ruleset
+#[rustler::nif]
+fn rule_names(ruleset: ResourceArc<RulesetResource>) -> Vec<String> {
+    ruleset.rules.rule_names().to_vec()
+}
+
+/// Scan data for secrets
+///
+/// ## Parameters
+/// - ruleset: Resource from `default_rules/0` or `compile_rules/1`
+/// - data: File contents
+///
+/// ## Returns
+/// - List of `%{rule: name, kind: :pattern | :assignment | :entropy,
+///   start: offset, length: bytes, entropy: bits_per_byte}`, sorted by offset
+#[rustler::nif(schedule = "DirtyCpu")]
+fn scan(ruleset: ResourceArc<RulesetResource>, data: Binary) -> Vec<Finding> {
+    ruleset
+        .rules
+        .scan(data.as_slice())
+        .into_iter()
+        .map(|f| Finding {
+            rule: f.rule,
+            kind: match f.kind {
+                Kind::Pattern => atoms::pattern(),
+                Kind::Assignment => atoms::assignment(),
+                Kind::Entropy => atoms::entropy(),
+            },
+            start: f.start,
+            length: f.len,
+            entropy: f.entropy,
+        })
+        .collect()
+}
+
+/// Estimate how random a buffer is
+///
+/// Byte-histogram based: Shannon entropy plus a chi-square test against a
+/// uniform distribution, in a single pass.
+///
+/// ## Parameters
+/// - data: Buffer to classify (pass a sample for large files)
+///
+/// ## Returns
+/// - %{shannon: bits_per_byte, chi_square: statistic, class: class} where
+///   class is :random (encrypted or random), :compressed, :plain, or
+///   :unknown for inputs under 256 bytes
+///
+/// Inputs under `DIRTY_THRESHOLD` bytes run inline; larger ones continue on
+/// a dirty CPU scheduler.
+#[rustler::nif]
+fn estimate_entropy<'a>(env: Env<'a>, data: Binary<'a>) -> Dispatch<'a, EntropyEstimate> {
+    if data.len() >= DIRTY_THRESHOLD {
+        let args = vec![data.encode(env)];
+        return Dispatch::dirty("estimate_entropy", estimate_entropy_dirty, args);
+    }
+    Dispatch::Done(estimate_entropy_impl(data))
+}
+
+/// `estimate_entropy/1` continued on a dirty CPU scheduler
+unsafe extern "C" fn estimate_entropy_dirty(
+    env: NIF_ENV,
+    argc: c_int,
+    argv: *const NIF_TERM,
+) -> NIF_TERM {
+    reschedule::run(env, argc, argv, |env, args| {
+        let estimate = estimate_entropy_impl(args[0].decode()?);
+        Ok(reschedule::returned(env, Ok(estimate)))
+    })
+}
+
+/// Body of `estimate_entropy/1`, run inline or as its dirty continuation
+fn estimate_entropy_impl(data: Binary) -> EntropyEstimate {
+    let estimate = entropy::estimate(data.as_slice());
+
+    EntropyEstimate {
+        shannon: estimate.shannon,
+        chi_square: estimate.chi_square,
+        class: match estimate.class {
+            Class::Unknown => atoms::unknown(),
+            Class::Random => atoms::random(),
+            Class::Compressed => atoms::compressed(),
+            Class::Plain => atoms::plain(),
+        },
+    }
+}
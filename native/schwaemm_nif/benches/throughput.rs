@@ -0,0 +1,6 @@
+//! Seal throughput of every Schwaemm variant across message sizes
+//!
+//! Run with `cargo bench`; `benchmark/2` measures the same subjects from
+//! Elixir.
+
+nif_support::seal_bench!(schwaemm_nif::bench::subjects);
@@ -0,0 +1,64 @@
+//! Benchmark subjects for `benchmark/2` and the criterion benches
+//!
+//! Every Schwaemm variant the NIF exposes is listed here under a fixed
+//! all-zero key and nonce; only the speed matters, not the ciphertext.
+//! The measuring itself is `nif_support::bench`.
+
+use crate::schwaemm_v2::{self, Variant, SCHWAEMM256_256};
+#[cfg(feature = "pending-kats")]
+use crate::schwaemm_v2::{SCHWAEMM128_128, SCHWAEMM192_192, SCHWAEMM256_128};
+use nif_support::bench::Subject;
+
+fn schwaemm(name: &'static str, v: &'static Variant) -> Subject {
+    let key = vec![0u8; v.key_bytes()];
+    let nonce = vec![0u8; v.nonce_bytes()];
+    Subject::new(name, (v.key_bytes() * 8) as u32, move |buffer| {
+        let (ciphertext, _tag) = schwaemm_v2::seal(v, &key, &nonce, buffer, b"");
+        buffer.copy_from_slice(&ciphertext);
+    })
+}
+
+/// Every AEAD in this library
+pub fn subjects() -> Vec<Subject> {
+    vec![
+        schwaemm("schwaemm_256_256", &SCHWAEMM256_256),
+        #[cfg(feature = "pending-kats")]
+        schwaemm("schwaemm_192_192", &SCHWAEMM192_192),
+        #[cfg(feature = "pending-kats")]
+        schwaemm("schwaemm_128_128", &SCHWAEMM128_128),
+        #[cfg(feature = "pending-kats")]
+        schwaemm("schwaemm_256_128", &SCHWAEMM256_128),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nif_support::bench::{fastest, throughput};
+    use std::time::Duration;
+
+    #[test]
+    fn test_every_subject_measures() {
+        for subject in subjects() {
+            let mbps = throughput(&subject, 1000, Duration::from_millis(1));
+            assert!(mbps > 0.0, "{} measured {}", subject.name, mbps);
+        }
+    }
+
+    #[test]
+    fn test_seal_encrypts_in_place() {
+        let subject = &subjects()[0];
+        let mut buffer = vec![0u8; 100];
+        subject.seal(&mut buffer);
+        assert!(buffer.iter().any(|&byte| byte != 0));
+    }
+
+    #[test]
+    fn test_fastest_filters_and_ranks() {
+        let ranked = fastest(subjects, 192);
+        assert!(!ranked.is_empty());
+        assert!(ranked.iter().all(|measurement| measurement.security >= 192));
+        assert!(ranked.windows(2).all(|pair| pair[0].mbps >= pair[1].mbps));
+        assert!(fastest(subjects, 512).is_empty());
+    }
+}
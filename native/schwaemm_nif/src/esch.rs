@@ -0,0 +1,232 @@
+//! Esch hash functions (Sparkle family)
+//!
+//! Follows the structure of the NIST LWC reference code (esch.c): a sponge
+//! with a 128-bit rate where each message block is mixed into the left
+//! half of the state through a Feistel-like linear map before the
+//! permutation.
+//!
+//! | Function | Permutation | Digest | Steps (slim/big) |
+//! |----------|-------------|--------|------------------|
+//! | Esch256  | Sparkle-384 | 256    | 7 / 11           |
+//! | Esch384  | Sparkle-512 | 384    | 8 / 12           |
+//!
+//! XOEsch256 is Esch256 with its own last-block constants (M1/M2 with the
+//! XOF bit set) and an output of any length.
+
+use crate::sparkle::{ell, sparkle_384, sparkle_512};
+
+/// Rate: 128 bits = 2 branches
+const RATE_BRANS: usize = 2;
+const RATE_BYTES: usize = 16;
+
+/// Largest state handled: Sparkle-512 has 8 branches
+const MAX_BRANS: usize = 8;
+
+/// Domain separation constants for a padded / full last block
+const CONST_M1: u32 = 1 << 24;
+const CONST_M2: u32 = 2 << 24;
+
+/// XOEsch variants of M1/M2
+const CONST_M1_XOF: u32 = 5 << 24;
+const CONST_M2_XOF: u32 = 6 << 24;
+
+/// Esch parameter set
+pub struct Variant {
+    state_brans: usize,
+    steps_slim: usize,
+    steps_big: usize,
+    digest_bytes: usize,
+}
+
+/// Esch256: Sparkle-384, 256-bit digest
+pub const ESCH256: Variant = Variant {
+    state_brans: 6,
+    steps_slim: 7,
+    steps_big: 11,
+    digest_bytes: 32,
+};
+
+/// Esch384: Sparkle-512, 384-bit digest
+pub const ESCH384: Variant = Variant {
+    state_brans: 8,
+    steps_slim: 8,
+    steps_big: 12,
+    digest_bytes: 48,
+};
+
+/// Sponge state, interleaved as x[0], y[0], x[1], y[1], ...
+pub(crate) struct EschState<'v> {
+    v: &'v Variant,
+    words: [u32; 2 * MAX_BRANS],
+}
+
+impl<'v> EschState<'v> {
+    pub(crate) fn new(v: &'v Variant) -> Self {
+        EschState {
+            v,
+            words: [0u32; 2 * MAX_BRANS],
+        }
+    }
+
+    pub(crate) fn permute(&mut self, steps: usize) {
+        match self.v.state_brans {
+            6 => sparkle_384((&mut self.words[..12]).try_into().unwrap(), steps),
+            8 => sparkle_512(&mut self.words, steps),
+            n => unreachable!("no Sparkle permutation with {} branches", n),
+        }
+    }
+
+    /// Mix one (possibly partial) block into the left half of the state
+    fn add_block(&mut self, block: &[u8]) {
+        let mut bytes = [0u8; RATE_BYTES];
+        bytes[..block.len()].copy_from_slice(block);
+        if block.len() < RATE_BYTES {
+            bytes[block.len()] = 0x80;
+        }
+
+        let mut buffer = [0u32; 2 * RATE_BRANS];
+        for (word, chunk) in buffer.iter_mut().zip(bytes.chunks_exact(4)) {
+            *word = u32::from_le_bytes(chunk.try_into().unwrap());
+        }
+
+        // Feistel-like construction
+        let mut tmpx = 0;
+        let mut tmpy = 0;
+        for i in 0..RATE_BRANS {
+            tmpx ^= buffer[2 * i];
+            tmpy ^= buffer[2 * i + 1];
+        }
+        let tmpx = ell(tmpx);
+        let tmpy = ell(tmpy);
+
+        for i in 0..RATE_BRANS {
+            self.words[2 * i] ^= buffer[2 * i] ^ tmpy;
+            self.words[2 * i + 1] ^= buffer[2 * i + 1] ^ tmpx;
+        }
+        for i in RATE_BRANS..self.v.state_brans / 2 {
+            self.words[2 * i] ^= tmpy;
+            self.words[2 * i + 1] ^= tmpx;
+        }
+    }
+
+    /// Absorb the whole message, leaving the state ready to squeeze
+    ///
+    /// `last_const` selects the domain separation constants for the last
+    /// block; XOEsch passes its own.
+    pub(crate) fn absorb(&mut self, data: &[u8], last_const: (u32, u32)) {
+        let mut rest = data;
+
+        // Main hashing loop; the last block is kept back even if full
+        while rest.len() > RATE_BYTES {
+            self.add_block(&rest[..RATE_BYTES]);
+            self.permute(self.v.steps_slim);
+            rest = &rest[RATE_BYTES..];
+        }
+
+        // Hashing of last block: constant goes into the last y-word of the left half
+        let (padded, full) = last_const;
+        let last_y = 2 * (self.v.state_brans / 2 - 1) + 1;
+        self.words[last_y] ^= if rest.len() < RATE_BYTES {
+            padded
+        } else {
+            full
+        };
+        self.add_block(rest);
+        self.permute(self.v.steps_big);
+    }
+
+    /// Squeeze `out.len()` bytes
+    pub(crate) fn squeeze(&mut self, out: &mut [u8]) {
+        let mut chunks = out.chunks_mut(RATE_BYTES);
+        if let Some(first) = chunks.next() {
+            self.copy_rate(first);
+        }
+        for chunk in chunks {
+            self.permute(self.v.steps_slim);
+            self.copy_rate(chunk);
+        }
+    }
+
+    fn copy_rate(&self, out: &mut [u8]) {
+        let mut rate = [0u8; RATE_BYTES];
+        for (chunk, word) in rate.chunks_exact_mut(4).zip(&self.words[..2 * RATE_BRANS]) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        out.copy_from_slice(&rate[..out.len()]);
+    }
+}
+
+/// Esch hash with any parameter set
+pub fn hash(v: &Variant, data: &[u8]) -> Vec<u8> {
+    let mut state = EschState::new(v);
+    state.absorb(data, (CONST_M1, CONST_M2));
+
+    let mut digest = vec![0u8; v.digest_bytes];
+    state.squeeze(&mut digest);
+    digest
+}
+
+/// XOEsch256 extendable-output function
+pub fn xoesch256(data: &[u8], out_len: usize) -> Vec<u8> {
+    let mut state = EschState::new(&ESCH256);
+    state.absorb(data, (CONST_M1_XOF, CONST_M2_XOF));
+
+    let mut output = vec![0u8; out_len];
+    state.squeeze(&mut output);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_sizes() {
+        assert_eq!(hash(&ESCH256, b"").len(), 32);
+        assert_eq!(hash(&ESCH384, b"").len(), 48);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let data = b"The quick brown fox jumps over the lazy dog";
+        assert_eq!(hash(&ESCH256, data), hash(&ESCH256, data));
+        assert_eq!(hash(&ESCH384, data), hash(&ESCH384, data));
+    }
+
+    #[test]
+    fn test_block_boundaries_are_distinct() {
+        // Padding and the M1/M2 constants must keep a full last block apart
+        // from the same bytes followed by padding
+        let data = [0x80u8; 33];
+        let mut digests: Vec<Vec<u8>> = [0usize, 1, 15, 16, 17, 32, 33]
+            .iter()
+            .map(|&len| hash(&ESCH256, &data[..len]))
+            .collect();
+        digests.push(hash(&ESCH256, &[0u8; 16]));
+        let count = digests.len();
+        digests.sort();
+        digests.dedup();
+        assert_eq!(digests.len(), count);
+    }
+
+    #[test]
+    fn test_variants_differ() {
+        let short = hash(&ESCH256, b"abc");
+        let long = hash(&ESCH384, b"abc");
+        assert_ne!(short[..], long[..32]);
+    }
+
+    #[test]
+    fn test_xof_prefix_consistent() {
+        let long = xoesch256(b"per-file nonce", 100);
+        for len in [1usize, 16, 17, 32, 64] {
+            assert_eq!(xoesch256(b"per-file nonce", len), long[..len]);
+        }
+    }
+
+    #[test]
+    fn test_xof_separated_from_hash() {
+        assert_ne!(xoesch256(b"abc", 32), hash(&ESCH256, b"abc"));
+        assert_ne!(xoesch256(&[0u8; 16], 32), hash(&ESCH256, &[0u8; 16]));
+    }
+}
@@ -1,81 +1,251 @@
-mod sparkle;
+pub mod bench;
+#[cfg(any(test, feature = "pending-kats"))]
+mod esch;
 mod schwaemm;
 mod schwaemm_v2;
+mod sparkle;
 
-use rustler::{Env, Binary, Error, OwnedBinary};
+use nif_support::cipher_nifs::BackendInfo;
+use nif_support::stream::SegmentCipher;
+use rustler::Error;
+#[cfg(feature = "pending-kats")]
+use rustler::{Binary, Env, OwnedBinary};
+#[cfg(feature = "pending-kats")]
+use schwaemm_v2::{SCHWAEMM128_128, SCHWAEMM192_192, SCHWAEMM256_128};
 
 rustler::init!("Elixir.GitFoil.Native.SchwaemmNif");
 
-/// Schwaemm256-256 Encryption
+mod atoms {
+    rustler::atoms! {
+        soft,
+    }
+}
+
+/// Schwaemm256-256 under one key, the cipher behind the shared NIFs
+struct Schwaemm256Key([u8; 32]);
+
+impl Schwaemm256Key {
+    fn new(key: &[u8]) -> Result<Self, Error> {
+        let key_array: [u8; 32] = key.try_into().map_err(|_| Error::BadArg)?;
+        Ok(Schwaemm256Key(key_array))
+    }
+}
+
+impl SegmentCipher for Schwaemm256Key {
+    const NONCE_SIZE: usize = 32;
+    const TAG_SIZE: usize = 32;
+
+    fn seal_in_place(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8]) -> Option<Vec<u8>> {
+        let nonce_array: &[u8; 32] = nonce.try_into().ok()?;
+        let (ciphertext, tag) = schwaemm_v2::encrypt(&self.0, nonce_array, buffer, aad);
+        buffer.copy_from_slice(&ciphertext);
+        Some(tag.to_vec())
+    }
+
+    fn open_in_place(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8], tag: &[u8]) -> bool {
+        let (Ok(nonce_array), Ok(tag_array)) = (nonce.try_into(), tag.try_into()) else {
+            return false;
+        };
+        match schwaemm_v2::decrypt(&self.0, nonce_array, buffer, tag_array, aad) {
+            Ok(plaintext) => {
+                buffer.copy_from_slice(&plaintext);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+// encrypt/decrypt, the batch, blob, context and file NIFs, STREAM in all
+// its forms, benchmark/2 and fastest_algorithms/1
+nif_support::cipher_nifs! {
+    cipher: Schwaemm256Key,
+    name: "Schwaemm256-256",
+    new: Schwaemm256Key::new,
+    sizes: { key: 32, nonce: 32, tag: 32, prefix: 27 },
+    subjects: bench::subjects,
+}
+
+/// Schwaemm192-192 Encryption
+///
+/// Built on Sparkle-384: fewer steps and a smaller state than
+/// Schwaemm256-256, trading security margin (192-bit) for speed.
+///
+/// Only built with the `pending-kats` feature until it is checked
+/// against the NIST LWC_AEAD_KAT_192_192 vectors.
 ///
 /// Parameters:
-/// - key: 32 bytes
-/// - nonce: 32 bytes
+/// - key: 24 bytes
+/// - nonce: 24 bytes
 /// - plaintext: variable length
 /// - aad: variable length (additional authenticated data)
 ///
 /// Returns:
-/// - Ok({ciphertext, tag}) where tag is 32 bytes
+/// - Ok({ciphertext, tag}) where tag is 24 bytes
 /// - Err for errors
-#[rustler::nif]
-fn encrypt<'a>(
+#[cfg(feature = "pending-kats")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_192<'a>(
     env: Env<'a>,
     key: Binary,
     nonce: Binary,
     plaintext: Binary,
     aad: Binary,
 ) -> Result<(Binary<'a>, Binary<'a>), Error> {
-    // Validate key length (32 bytes = 256 bits)
-    if key.len() != 32 {
+    // Validate key and nonce length (24 bytes = 192 bits)
+    if key.len() != SCHWAEMM192_192.key_bytes() {
+        return Err(Error::BadArg);
+    }
+    if nonce.len() != SCHWAEMM192_192.nonce_bytes() {
         return Err(Error::BadArg);
     }
 
-    // Validate nonce length (32 bytes = 256 bits)
-    if nonce.len() != 32 {
+    let (ciphertext, tag) = schwaemm_v2::seal(
+        &SCHWAEMM192_192,
+        key.as_slice(),
+        nonce.as_slice(),
+        plaintext.as_slice(),
+        aad.as_slice(),
+    );
+
+    // Copy to Elixir binaries
+    let mut ciphertext_binary = OwnedBinary::new(ciphertext.len()).unwrap();
+    ciphertext_binary
+        .as_mut_slice()
+        .copy_from_slice(&ciphertext);
+
+    let mut tag_binary = OwnedBinary::new(tag.len()).unwrap();
+    tag_binary.as_mut_slice().copy_from_slice(&tag);
+
+    Ok((ciphertext_binary.release(env), tag_binary.release(env)))
+}
+
+/// Schwaemm192-192 Decryption
+///
+/// Only built with the `pending-kats` feature until it is checked
+/// against the NIST LWC_AEAD_KAT_192_192 vectors.
+///
+/// Parameters:
+/// - key: 24 bytes
+/// - nonce: 24 bytes
+/// - ciphertext: variable length
+/// - tag: 24 bytes (authentication tag)
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok(plaintext)
+/// - Err if authentication fails
+#[cfg(feature = "pending-kats")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_192<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    ciphertext: Binary,
+    tag: Binary,
+    aad: Binary,
+) -> Result<Binary<'a>, Error> {
+    // Validate input sizes
+    if key.len() != SCHWAEMM192_192.key_bytes() {
+        return Err(Error::BadArg);
+    }
+    if nonce.len() != SCHWAEMM192_192.nonce_bytes() {
+        return Err(Error::BadArg);
+    }
+    if tag.len() != SCHWAEMM192_192.tag_bytes() {
         return Err(Error::BadArg);
     }
 
-    // Convert to fixed-size arrays
-    let key_array: &[u8; 32] = key.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
-    let nonce_array: &[u8; 32] = nonce.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
+    let plaintext = schwaemm_v2::open(
+        &SCHWAEMM192_192,
+        key.as_slice(),
+        nonce.as_slice(),
+        ciphertext.as_slice(),
+        tag.as_slice(),
+        aad.as_slice(),
+    )
+    .map_err(|_| Error::RaiseTerm(Box::new("authentication failed")))?;
+
+    // Copy to Elixir binary
+    let mut plaintext_binary = OwnedBinary::new(plaintext.len()).unwrap();
+    plaintext_binary.as_mut_slice().copy_from_slice(&plaintext);
+
+    Ok(plaintext_binary.release(env))
+}
+
+/// Schwaemm128-128 Encryption
+///
+/// Built on Sparkle-256, the smallest Sparkle state: the fastest
+/// variant, and the best fit for the many small files in a typical repo.
+/// 128-bit security.
+///
+/// Only built with the `pending-kats` feature until it is checked
+/// against the NIST LWC_AEAD_KAT_128_128 vectors.
+///
+/// Parameters:
+/// - key: 16 bytes
+/// - nonce: 16 bytes
+/// - plaintext: variable length
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok({ciphertext, tag}) where tag is 16 bytes
+/// - Err for errors
+#[cfg(feature = "pending-kats")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_128<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    plaintext: Binary,
+    aad: Binary,
+) -> Result<(Binary<'a>, Binary<'a>), Error> {
+    // Validate key and nonce length (16 bytes = 128 bits)
+    if key.len() != SCHWAEMM128_128.key_bytes() {
+        return Err(Error::BadArg);
+    }
+    if nonce.len() != SCHWAEMM128_128.nonce_bytes() {
+        return Err(Error::BadArg);
+    }
 
-    // Encrypt using Schwaemm256-256 v2
-    let (ciphertext, tag) = schwaemm_v2::encrypt(
-        key_array,
-        nonce_array,
+    let (ciphertext, tag) = schwaemm_v2::seal(
+        &SCHWAEMM128_128,
+        key.as_slice(),
+        nonce.as_slice(),
         plaintext.as_slice(),
         aad.as_slice(),
     );
 
     // Copy to Elixir binaries
     let mut ciphertext_binary = OwnedBinary::new(ciphertext.len()).unwrap();
-    ciphertext_binary.as_mut_slice().copy_from_slice(&ciphertext);
+    ciphertext_binary
+        .as_mut_slice()
+        .copy_from_slice(&ciphertext);
 
     let mut tag_binary = OwnedBinary::new(tag.len()).unwrap();
     tag_binary.as_mut_slice().copy_from_slice(&tag);
 
-    Ok((
-        ciphertext_binary.release(env),
-        tag_binary.release(env),
-    ))
+    Ok((ciphertext_binary.release(env), tag_binary.release(env)))
 }
 
-/// Schwaemm256-256 Decryption
+/// Schwaemm128-128 Decryption
+///
+/// Only built with the `pending-kats` feature until it is checked
+/// against the NIST LWC_AEAD_KAT_128_128 vectors.
 ///
 /// Parameters:
-/// - key: 32 bytes
-/// - nonce: 32 bytes
+/// - key: 16 bytes
+/// - nonce: 16 bytes
 /// - ciphertext: variable length
-/// - tag: 32 bytes (authentication tag)
+/// - tag: 16 bytes (authentication tag)
 /// - aad: variable length (additional authenticated data)
 ///
 /// Returns:
 /// - Ok(plaintext)
 /// - Err if authentication fails
-#[rustler::nif]
-fn decrypt<'a>(
+#[cfg(feature = "pending-kats")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_128<'a>(
     env: Env<'a>,
     key: Binary,
     nonce: Binary,
@@ -84,32 +254,132 @@ fn decrypt<'a>(
     aad: Binary,
 ) -> Result<Binary<'a>, Error> {
     // Validate input sizes
-    if key.len() != 32 {
+    if key.len() != SCHWAEMM128_128.key_bytes() {
         return Err(Error::BadArg);
     }
-    if nonce.len() != 32 {
+    if nonce.len() != SCHWAEMM128_128.nonce_bytes() {
         return Err(Error::BadArg);
     }
-    if tag.len() != 32 {
+    if tag.len() != SCHWAEMM128_128.tag_bytes() {
         return Err(Error::BadArg);
     }
 
-    // Convert to fixed-size arrays
-    let key_array: &[u8; 32] = key.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
-    let nonce_array: &[u8; 32] = nonce.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
-    let tag_array: &[u8; 32] = tag.as_slice().try_into()
-        .map_err(|_| Error::BadArg)?;
+    let plaintext = schwaemm_v2::open(
+        &SCHWAEMM128_128,
+        key.as_slice(),
+        nonce.as_slice(),
+        ciphertext.as_slice(),
+        tag.as_slice(),
+        aad.as_slice(),
+    )
+    .map_err(|_| Error::RaiseTerm(Box::new("authentication failed")))?;
+
+    // Copy to Elixir binary
+    let mut plaintext_binary = OwnedBinary::new(plaintext.len()).unwrap();
+    plaintext_binary.as_mut_slice().copy_from_slice(&plaintext);
+
+    Ok(plaintext_binary.release(env))
+}
 
-    // Decrypt and verify using v2
-    let plaintext = schwaemm_v2::decrypt(
-        key_array,
-        nonce_array,
+/// Schwaemm256-128 Encryption
+///
+/// Built on Sparkle-384 with a 256-bit rate and a 128-bit key and tag,
+/// so its tag matches the other ciphers' 16-byte tags.
+///
+/// Only built with the `pending-kats` feature until it is checked
+/// against the NIST LWC_AEAD_KAT_128_256 vectors.
+///
+/// Parameters:
+/// - key: 16 bytes
+/// - nonce: 32 bytes
+/// - plaintext: variable length
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok({ciphertext, tag}) where tag is 16 bytes
+/// - Err for errors
+#[cfg(feature = "pending-kats")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt_256_128<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    plaintext: Binary,
+    aad: Binary,
+) -> Result<(Binary<'a>, Binary<'a>), Error> {
+    // Validate key (16 bytes) and nonce (32 bytes) length
+    if key.len() != SCHWAEMM256_128.key_bytes() {
+        return Err(Error::BadArg);
+    }
+    if nonce.len() != SCHWAEMM256_128.nonce_bytes() {
+        return Err(Error::BadArg);
+    }
+
+    let (ciphertext, tag) = schwaemm_v2::seal(
+        &SCHWAEMM256_128,
+        key.as_slice(),
+        nonce.as_slice(),
+        plaintext.as_slice(),
+        aad.as_slice(),
+    );
+
+    // Copy to Elixir binaries
+    let mut ciphertext_binary = OwnedBinary::new(ciphertext.len()).unwrap();
+    ciphertext_binary
+        .as_mut_slice()
+        .copy_from_slice(&ciphertext);
+
+    let mut tag_binary = OwnedBinary::new(tag.len()).unwrap();
+    tag_binary.as_mut_slice().copy_from_slice(&tag);
+
+    Ok((ciphertext_binary.release(env), tag_binary.release(env)))
+}
+
+/// Schwaemm256-128 Decryption
+///
+/// Only built with the `pending-kats` feature until it is checked
+/// against the NIST LWC_AEAD_KAT_128_256 vectors.
+///
+/// Parameters:
+/// - key: 16 bytes
+/// - nonce: 32 bytes
+/// - ciphertext: variable length
+/// - tag: 16 bytes (authentication tag)
+/// - aad: variable length (additional authenticated data)
+///
+/// Returns:
+/// - Ok(plaintext)
+/// - Err if authentication fails
+#[cfg(feature = "pending-kats")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt_256_128<'a>(
+    env: Env<'a>,
+    key: Binary,
+    nonce: Binary,
+    ciphertext: Binary,
+    tag: Binary,
+    aad: Binary,
+) -> Result<Binary<'a>, Error> {
+    // Validate input sizes
+    if key.len() != SCHWAEMM256_128.key_bytes() {
+        return Err(Error::BadArg);
+    }
+    if nonce.len() != SCHWAEMM256_128.nonce_bytes() {
+        return Err(Error::BadArg);
+    }
+    if tag.len() != SCHWAEMM256_128.tag_bytes() {
+        return Err(Error::BadArg);
+    }
+
+    let plaintext = schwaemm_v2::open(
+        &SCHWAEMM256_128,
+        key.as_slice(),
+        nonce.as_slice(),
         ciphertext.as_slice(),
-        tag_array,
+        tag.as_slice(),
         aad.as_slice(),
-    ).map_err(|_| Error::RaiseTerm(Box::new("authentication failed")))?;
+    )
+    .map_err(|_| Error::RaiseTerm(Box::new("authentication failed")))?;
 
     // Copy to Elixir binary
     let mut plaintext_binary = OwnedBinary::new(plaintext.len()).unwrap();
@@ -117,3 +387,95 @@ fn decrypt<'a>(
 
     Ok(plaintext_binary.release(env))
 }
+
+/// Esch256 Hash
+///
+/// Sparkle-384 based hash from the same family as Schwaemm.
+///
+/// Only built with the `pending-kats` feature until it is checked
+/// against the NIST LWC_HASH_KAT_256 vectors.
+///
+/// Parameters:
+/// - data: variable length
+///
+/// Returns:
+/// - 32-byte digest
+#[cfg(feature = "pending-kats")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn esch256<'a>(env: Env<'a>, data: Binary) -> Binary<'a> {
+    let digest = esch::hash(&esch::ESCH256, data.as_slice());
+
+    let mut digest_binary = OwnedBinary::new(digest.len()).unwrap();
+    digest_binary.as_mut_slice().copy_from_slice(&digest);
+    digest_binary.release(env)
+}
+
+/// Esch384 Hash
+///
+/// Sparkle-512 based hash from the same family as Schwaemm.
+///
+/// Only built with the `pending-kats` feature until it is checked
+/// against the NIST LWC_HASH_KAT_384 vectors.
+///
+/// Parameters:
+/// - data: variable length
+///
+/// Returns:
+/// - 48-byte digest
+#[cfg(feature = "pending-kats")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn esch384<'a>(env: Env<'a>, data: Binary) -> Binary<'a> {
+    let digest = esch::hash(&esch::ESCH384, data.as_slice());
+
+    let mut digest_binary = OwnedBinary::new(digest.len()).unwrap();
+    digest_binary.as_mut_slice().copy_from_slice(&digest);
+    digest_binary.release(env)
+}
+
+/// Largest XOEsch256 output accepted (bytes)
+#[cfg(feature = "pending-kats")]
+const MAX_XOF_OUTPUT: usize = 1 << 16;
+
+/// XOEsch256 Extendable-Output Function
+///
+/// For deriving nonces and subkeys entirely within the Sparkle family;
+/// put a distinct label in the input for each use.
+///
+/// Only built with the `pending-kats` feature until it is checked
+/// against the NIST XOEsch256 known-answer vectors.
+///
+/// Parameters:
+/// - data: variable length
+/// - out_len: output length in bytes (1..=65536)
+///
+/// Returns:
+/// - Ok(output) of `out_len` bytes
+/// - Err for an invalid output length
+#[cfg(feature = "pending-kats")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn xof<'a>(env: Env<'a>, data: Binary, out_len: usize) -> Result<Binary<'a>, Error> {
+    if out_len == 0 || out_len > MAX_XOF_OUTPUT {
+        return Err(Error::BadArg);
+    }
+
+    let output = esch::xoesch256(data.as_slice(), out_len);
+
+    let mut output_binary = OwnedBinary::new(output.len()).unwrap();
+    output_binary.as_mut_slice().copy_from_slice(&output);
+    Ok(output_binary.release(env))
+}
+
+/// Report which hardware path Schwaemm256-256 takes on the running CPU
+///
+/// Sparkle is an add-rotate-xor permutation, which runs at full speed in
+/// portable code; there is no hardware path.
+///
+/// Returns:
+/// - %{backend: :soft, features: []}
+#[rustler::nif]
+fn backend_info() -> BackendInfo {
+    BackendInfo {
+        backend: atoms::soft(),
+        features: vec![],
+    }
+}
@@ -8,16 +8,15 @@
 /// - Capacity: 256 bits (32 bytes / 8 words)
 /// - State: 512 bits (64 bytes / 16 words) using Sparkle-512
 /// - Sparkle steps: 8 (slim) and 12 (big)
-
 use crate::sparkle::sparkle_512;
 
-const RATE_WORDS: usize = 8;   // 256 bits
-const CAP_WORDS: usize = 8;    // 256 bits
+const RATE_WORDS: usize = 8; // 256 bits
+const CAP_WORDS: usize = 8; // 256 bits
 const STATE_WORDS: usize = 16; // 512 bits total
 
-const RATE_BYTES: usize = 32;  // 256 bits
-const TAG_BYTES: usize = 32;   // 256 bits
-const KEY_BYTES: usize = 32;   // 256 bits
+const RATE_BYTES: usize = 32; // 256 bits
+const TAG_BYTES: usize = 32; // 256 bits
+const KEY_BYTES: usize = 32; // 256 bits
 const NONCE_BYTES: usize = 32; // 256 bits
 
 const SPARKLE_STEPS_SLIM: usize = 8;
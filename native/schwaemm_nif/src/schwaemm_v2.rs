@@ -1,50 +1,124 @@
-/// Schwaemm256-256 AEAD implementation - Version 2
+/// Schwaemm AEAD implementation - Version 2
 ///
 /// Complete rewrite based on NIST reference implementation.
-/// Follows the exact structure from the C reference code.
+/// Follows the exact structure from the C reference code, with the
+/// parameter set (rate/capacity branches and step counts) passed in at
+/// runtime instead of fixed by the preprocessor.
+///
+/// | Variant         | Permutation | Rate | Capacity = key = tag | Steps (slim/big) |
+/// |-----------------|-------------|------|----------------------|------------------|
+/// | Schwaemm256-256 | Sparkle-512 | 256  | 256                  | 8 / 12           |
+/// | Schwaemm192-192 | Sparkle-384 | 192  | 192                  | 7 / 11           |
+/// | Schwaemm128-128 | Sparkle-256 | 128  | 128                  | 7 / 10           |
+/// | Schwaemm256-128 | Sparkle-384 | 256  | 128                  | 7 / 11           |
+use crate::sparkle::sparkle;
+
+/// Largest state handled: Sparkle-512 has 8 branches
+const MAX_BRANS: usize = 8;
+const MAX_RATE_BYTES: usize = 32;
+
+const TAG_BYTES: usize = 32; // 256 bits (Schwaemm256-256)
+const KEY_BYTES: usize = 32; // 256 bits (Schwaemm256-256)
+const NONCE_BYTES: usize = 32; // 256 bits (Schwaemm256-256)
+
+/// Schwaemm parameter set
+pub struct Variant {
+    rate_brans: usize,
+    cap_brans: usize,
+    steps_slim: usize,
+    steps_big: usize,
+}
 
-use crate::sparkle::sparkle_512;
+impl Variant {
+    fn state_brans(&self) -> usize {
+        self.rate_brans + self.cap_brans
+    }
 
-const RATE_WORDS: usize = 8;   // 256 bits
-const CAP_WORDS: usize = 8;    // 256 bits
-const STATE_WORDS: usize = 16; // 512 bits total
-const RATE_BRANS: usize = 4;   // 4 branches in rate
-const CAP_BRANS: usize = 4;    // 4 branches in capacity
+    fn rate_bytes(&self) -> usize {
+        self.rate_brans * 8
+    }
 
-const RATE_BYTES: usize = 32;  // 256 bits
-const TAG_BYTES: usize = 32;   // 256 bits
-const KEY_BYTES: usize = 32;   // 256 bits
-const NONCE_BYTES: usize = 32; // 256 bits
+    /// Key size in bytes (equal to the capacity)
+    pub fn key_bytes(&self) -> usize {
+        self.cap_brans * 8
+    }
 
-const SPARKLE_STEPS_SLIM: usize = 8;
-const SPARKLE_STEPS_BIG: usize = 12;
+    /// Nonce size in bytes (equal to the rate)
+    pub fn nonce_bytes(&self) -> usize {
+        self.rate_bytes()
+    }
+
+    /// Tag size in bytes (equal to the capacity)
+    pub fn tag_bytes(&self) -> usize {
+        self.cap_brans * 8
+    }
+
+    /// Domain separation constant, XORed into the last y-word
+    fn domain(&self, n: u32) -> u32 {
+        (n ^ (1 << self.cap_brans)) << 24
+    }
+}
 
-// Domain separation constants
-// For Schwaemm256-256: CAP_BRANS = 4, so (1 << 4) = 16
-const CONST_A0: u32 = ((0 ^ 16) as u32) << 24; // 0x10000000
-const CONST_A1: u32 = ((1 ^ 16) as u32) << 24; // 0x11000000
-const CONST_M2: u32 = ((2 ^ 16) as u32) << 24; // 0x12000000
-const CONST_M3: u32 = ((3 ^ 16) as u32) << 24; // 0x13000000
+/// Schwaemm256-256: Sparkle-512, 256-bit rate, 256-bit key and tag
+pub const SCHWAEMM256_256: Variant = Variant {
+    rate_brans: 4,
+    cap_brans: 4,
+    steps_slim: 8,
+    steps_big: 12,
+};
+
+/// Schwaemm192-192: Sparkle-384, 192-bit rate, 192-bit key and tag
+#[cfg(any(test, feature = "pending-kats"))]
+pub const SCHWAEMM192_192: Variant = Variant {
+    rate_brans: 3,
+    cap_brans: 3,
+    steps_slim: 7,
+    steps_big: 11,
+};
+
+/// Schwaemm128-128: Sparkle-256, 128-bit rate, 128-bit key and tag
+#[cfg(any(test, feature = "pending-kats"))]
+pub const SCHWAEMM128_128: Variant = Variant {
+    rate_brans: 2,
+    cap_brans: 2,
+    steps_slim: 7,
+    steps_big: 10,
+};
+
+/// Schwaemm256-128: Sparkle-384, 256-bit rate, 128-bit key and tag
+#[cfg(any(test, feature = "pending-kats"))]
+pub const SCHWAEMM256_128: Variant = Variant {
+    rate_brans: 4,
+    cap_brans: 2,
+    steps_slim: 7,
+    steps_big: 11,
+};
+
+// Domain separation selectors (0 ^ 1 << CAP_BRANS etc. in the reference)
+const DOMAIN_A0: u32 = 0;
+const DOMAIN_A1: u32 = 1;
+const DOMAIN_M2: u32 = 2;
+const DOMAIN_M3: u32 = 3;
 
 /// SparkleState structure matching the C reference
-/// Organized as x[] and y[] arrays, not flat
+/// Organized as x[] and y[] arrays, not flat; only the first
+/// `state_brans` entries are used
 #[derive(Clone)]
 struct SparkleState {
-    x: [u32; 8],
-    y: [u32; 8],
+    x: [u32; MAX_BRANS],
+    y: [u32; MAX_BRANS],
 }
 
 /// Convert bytes to words (little-endian)
 #[inline]
-fn bytes_to_words_le(bytes: &[u8]) -> Vec<u32> {
-    bytes
-        .chunks(4)
-        .map(|chunk| {
-            let mut buf = [0u8; 4];
-            buf[..chunk.len()].copy_from_slice(chunk);
-            u32::from_le_bytes(buf)
-        })
-        .collect()
+fn bytes_to_words_le(bytes: &[u8]) -> [u32; MAX_BRANS * 2] {
+    let mut words = [0u32; MAX_BRANS * 2];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks(4)) {
+        let mut buf = [0u8; 4];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        *word = u32::from_le_bytes(buf);
+    }
+    words
 }
 
 /// Convert words to bytes (little-endian)
@@ -60,25 +134,22 @@ fn words_to_bytes_le(words: &[u32], bytes: &mut [u8]) {
     }
 }
 
-/// Rho and rate-whitening for authentication of associated data
-fn rho_whi_aut(state: &mut SparkleState, input: &[u8]) {
-    // Create zero-padded buffer
-    let mut inbuf_bytes = [0u8; RATE_BYTES];
+/// Zero-pad a (possibly partial) rate block, adding the 0x80 marker if partial
+#[inline]
+fn pad_block(v: &Variant, input: &[u8]) -> [u32; MAX_BRANS * 2] {
+    let mut inbuf_bytes = [0u8; MAX_RATE_BYTES];
     inbuf_bytes[..input.len()].copy_from_slice(input);
-
-    // Add padding if partial block
-    if input.len() < RATE_BYTES {
+    if input.len() < v.rate_bytes() {
         inbuf_bytes[input.len()] = 0x80;
     }
+    bytes_to_words_le(&inbuf_bytes[..v.rate_bytes()])
+}
 
-    // Convert to words
-    let inbuf = bytes_to_words_le(&inbuf_bytes)
-        .try_into()
-        .unwrap_or([0u32; RATE_WORDS]);
-
-    // Rho1 part 1: Feistel swap of rate-part
-    // Swaps first half with second half of rate (x[0..1] with x[2..3], y[0..1] with y[2..3])
-    let b = RATE_BRANS / 2; // b = 2
+/// Rho1 part 1: Feistel swap of rate-part
+/// Swaps first half with second half of rate (branch-wise, as in the reference)
+#[inline]
+fn feistel_swap(v: &Variant, state: &mut SparkleState) {
+    let b = v.rate_brans / 2;
     for i in 0..b {
         // Swap x values
         let tmp = state.x[i];
@@ -90,359 +161,337 @@ fn rho_whi_aut(state: &mut SparkleState, input: &[u8]) {
         state.y[i] = state.y[i + b];
         state.y[i + b] ^= tmp;
     }
+}
+
+/// Rate-whitening: capacity XORed to rate
+#[inline]
+fn rate_whitening(v: &Variant, state: &mut SparkleState) {
+    for i in 0..v.rate_brans {
+        state.x[i] ^= state.x[v.rate_brans + (i % v.cap_brans)];
+        state.y[i] ^= state.y[v.rate_brans + (i % v.cap_brans)];
+    }
+}
+
+/// Rho and rate-whitening for authentication of associated data
+fn rho_whi_aut(v: &Variant, state: &mut SparkleState, input: &[u8]) {
+    let inbuf = pad_block(v, input);
+
+    feistel_swap(v, state);
 
     // Rho1 part 2: XOR associated data into rate
-    for i in 0..RATE_BRANS {
+    for i in 0..v.rate_brans {
         state.x[i] ^= inbuf[2 * i];
         state.y[i] ^= inbuf[2 * i + 1];
     }
 
-    // Rate-whitening: capacity XORed to rate
-    for i in 0..RATE_BRANS {
-        state.x[i] ^= state.x[RATE_BRANS + (i % CAP_BRANS)];
-        state.y[i] ^= state.y[RATE_BRANS + (i % CAP_BRANS)];
-    }
+    rate_whitening(v, state);
 }
 
 /// Rho and rate-whitening for encryption
-fn rho_whi_enc(state: &mut SparkleState, output: &mut [u8], input: &[u8]) {
-    // Create zero-padded buffer
-    let mut inbuf_bytes = [0u8; RATE_BYTES];
-    inbuf_bytes[..input.len()].copy_from_slice(input);
-
-    // Add padding if partial block
-    if input.len() < RATE_BYTES {
-        inbuf_bytes[input.len()] = 0x80;
-    }
-
-    // Convert to words
-    let inbuf = bytes_to_words_le(&inbuf_bytes)
-        .try_into()
-        .unwrap_or([0u32; RATE_WORDS]);
-    let mut outbuf = [0u32; RATE_WORDS];
+fn rho_whi_enc(v: &Variant, state: &mut SparkleState, output: &mut [u8], input: &[u8]) {
+    let inbuf = pad_block(v, input);
+    let mut outbuf = [0u32; MAX_BRANS * 2];
 
     // Rho2: ciphertext = plaintext XOR rate-part
-    for i in 0..RATE_BRANS {
+    for i in 0..v.rate_brans {
         outbuf[2 * i] = inbuf[2 * i] ^ state.x[i];
         outbuf[2 * i + 1] = inbuf[2 * i + 1] ^ state.y[i];
     }
 
-    // Rho1 part 1: Feistel swap of rate-part
-    let b = RATE_BRANS / 2;
-    for i in 0..b {
-        // Swap x values
-        let tmp = state.x[i];
-        state.x[i] = state.x[i + b];
-        state.x[i + b] ^= tmp;
-
-        // Swap y values
-        let tmp = state.y[i];
-        state.y[i] = state.y[i + b];
-        state.y[i + b] ^= tmp;
-    }
+    feistel_swap(v, state);
 
     // Rho1 part 2: XOR plaintext into rate
-    for i in 0..RATE_BRANS {
+    for i in 0..v.rate_brans {
         state.x[i] ^= inbuf[2 * i];
         state.y[i] ^= inbuf[2 * i + 1];
     }
 
-    // Rate-whitening
-    for i in 0..RATE_BRANS {
-        state.x[i] ^= state.x[RATE_BRANS + (i % CAP_BRANS)];
-        state.y[i] ^= state.y[RATE_BRANS + (i % CAP_BRANS)];
-    }
+    rate_whitening(v, state);
 
     // Extract ciphertext
     words_to_bytes_le(&outbuf, output);
 }
 
 /// Rho and rate-whitening for decryption
-fn rho_whi_dec(state: &mut SparkleState, output: &mut [u8], input: &[u8]) {
-    // Create zero-padded buffer
-    let mut inbuf_bytes = [0u8; RATE_BYTES];
-    inbuf_bytes[..input.len()].copy_from_slice(input);
-
+fn rho_whi_dec(v: &Variant, state: &mut SparkleState, output: &mut [u8], input: &[u8]) {
     // Save original state for full-block processing
     let statebuf = state.clone();
 
-    // Add padding if partial block
-    if input.len() < RATE_BYTES {
-        inbuf_bytes[input.len()] = 0x80;
-    }
-
-    // Convert to words
-    let inbuf = bytes_to_words_le(&inbuf_bytes)
-        .try_into()
-        .unwrap_or([0u32; RATE_WORDS]);
-    let mut outbuf = [0u32; RATE_WORDS];
+    let inbuf = pad_block(v, input);
+    let mut outbuf = [0u32; MAX_BRANS * 2];
 
     // Rho2': plaintext = ciphertext XOR rate-part
-    for i in 0..RATE_BRANS {
+    for i in 0..v.rate_brans {
         outbuf[2 * i] = inbuf[2 * i] ^ state.x[i];
         outbuf[2 * i + 1] = inbuf[2 * i + 1] ^ state.y[i];
     }
 
-    // Rho1' part 1: Feistel swap of rate-part
-    let b = RATE_BRANS / 2;
-    for i in 0..b {
-        let tmp = state.x[i];
-        state.x[i] = state.x[i + b];
-        state.x[i + b] ^= tmp;
-
-        let tmp = state.y[i];
-        state.y[i] = state.y[i + b];
-        state.y[i + b] ^= tmp;
-    }
+    feistel_swap(v, state);
 
     // Rho1' part 2: Different for partial vs full blocks
-    if input.len() < RATE_BYTES {
+    if input.len() < v.rate_bytes() {
         // Partial block: pad plaintext and XOR into state
-        let mut outbuf_bytes: Vec<u8> = outbuf.iter()
-            .flat_map(|&w| w.to_le_bytes().to_vec())
-            .collect();
-        outbuf_bytes[input.len()..].fill(0);
-        outbuf_bytes[input.len()] = 0x80;
+        let mut outbuf_bytes = [0u8; MAX_RATE_BYTES];
+        words_to_bytes_le(&outbuf, &mut outbuf_bytes);
+        let outbuf_padded = pad_block(v, &outbuf_bytes[..input.len()]);
 
-        let outbuf_padded = bytes_to_words_le(&outbuf_bytes)
-            .try_into()
-            .unwrap_or([0u32; RATE_WORDS]);
-
-        for i in 0..RATE_BRANS {
+        for i in 0..v.rate_brans {
             state.x[i] ^= outbuf_padded[2 * i];
             state.y[i] ^= outbuf_padded[2 * i + 1];
         }
     } else {
         // Full block: XOR with (original_state XOR ciphertext)
-        for i in 0..RATE_BRANS {
+        for i in 0..v.rate_brans {
             state.x[i] ^= statebuf.x[i] ^ inbuf[2 * i];
             state.y[i] ^= statebuf.y[i] ^ inbuf[2 * i + 1];
         }
     }
 
-    // Rate-whitening
-    for i in 0..RATE_BRANS {
-        state.x[i] ^= state.x[RATE_BRANS + (i % CAP_BRANS)];
-        state.y[i] ^= state.y[RATE_BRANS + (i % CAP_BRANS)];
-    }
+    rate_whitening(v, state);
 
     // Extract plaintext
     words_to_bytes_le(&outbuf, output);
 }
 
-/// Convert SparkleState to flat array for permutation
-fn state_to_flat(state: &SparkleState) -> [u32; STATE_WORDS] {
-    let mut flat = [0u32; STATE_WORDS];
-    for i in 0..8 {
-        flat[2 * i] = state.x[i];
-        flat[2 * i + 1] = state.y[i];
-    }
-    flat
-}
-
-/// Convert flat array back to SparkleState
-fn flat_to_state(flat: &[u32; STATE_WORDS]) -> SparkleState {
-    let mut state = SparkleState {
-        x: [0u32; 8],
-        y: [0u32; 8],
-    };
-    for i in 0..8 {
-        state.x[i] = flat[2 * i];
-        state.y[i] = flat[2 * i + 1];
+/// Apply the Sparkle permutation matching the variant's state size,
+/// directly on the x/y words
+fn sparkle_state(v: &Variant, state: &mut SparkleState, steps: usize) {
+    let SparkleState { x, y } = state;
+    match v.state_brans() {
+        4 => sparkle::<4>(first_brans(x), first_brans(y), steps),
+        6 => sparkle::<6>(first_brans(x), first_brans(y), steps),
+        8 => sparkle::<8>(x, y, steps),
+        n => unreachable!("no Sparkle permutation with {} branches", n),
     }
-    state
 }
 
-/// Apply Sparkle permutation to SparkleState
-fn sparkle_state(state: &mut SparkleState, steps: usize) {
-    let mut flat = state_to_flat(state);
-    sparkle_512(&mut flat, steps);
-    *state = flat_to_state(&flat);
+/// The first `N` branches of a state half
+#[inline]
+fn first_brans<const N: usize>(words: &mut [u32; MAX_BRANS]) -> &mut [u32; N] {
+    (&mut words[..N]).try_into().unwrap()
 }
 
 /// Initialize state with nonce and key
-fn initialize(key: &[u8; KEY_BYTES], nonce: &[u8; NONCE_BYTES]) -> SparkleState {
+fn initialize(v: &Variant, key: &[u8], nonce: &[u8]) -> SparkleState {
     let mut state = SparkleState {
-        x: [0u32; 8],
-        y: [0u32; 8],
+        x: [0u32; MAX_BRANS],
+        y: [0u32; MAX_BRANS],
     };
 
     let nonce_words = bytes_to_words_le(nonce);
     let key_words = bytes_to_words_le(key);
 
     // Load nonce into rate-part
-    for i in 0..4 {
+    for i in 0..v.rate_brans {
         state.x[i] = nonce_words[2 * i];
         state.y[i] = nonce_words[2 * i + 1];
     }
 
     // Load key into capacity-part
-    for i in 0..4 {
-        state.x[RATE_BRANS + i] = key_words[2 * i];
-        state.y[RATE_BRANS + i] = key_words[2 * i + 1];
+    for i in 0..v.cap_brans {
+        state.x[v.rate_brans + i] = key_words[2 * i];
+        state.y[v.rate_brans + i] = key_words[2 * i + 1];
     }
 
     // Execute SPARKLE with big number of steps
-    sparkle_state(&mut state, SPARKLE_STEPS_BIG);
+    sparkle_state(v, &mut state, v.steps_big);
 
     state
 }
 
 /// Process associated data
-fn process_assoc_data(state: &mut SparkleState, aad: &[u8]) {
+fn process_assoc_data(v: &Variant, state: &mut SparkleState, aad: &[u8]) {
     if aad.is_empty() {
         return;
     }
 
+    let rate = v.rate_bytes();
     let mut offset = 0;
 
     // Main authentication loop
-    while aad.len() - offset > RATE_BYTES {
-        rho_whi_aut(state, &aad[offset..offset + RATE_BYTES]);
-        sparkle_state(state, SPARKLE_STEPS_SLIM);
-        offset += RATE_BYTES;
+    while aad.len() - offset > rate {
+        rho_whi_aut(v, state, &aad[offset..offset + rate]);
+        sparkle_state(v, state, v.steps_slim);
+        offset += rate;
     }
 
     // Authentication of last block
     let remaining = &aad[offset..];
-    let const_val = if remaining.len() < RATE_BYTES {
-        CONST_A0
+    let domain = if remaining.len() < rate {
+        DOMAIN_A0
     } else {
-        CONST_A1
+        DOMAIN_A1
     };
-    state.y[7] ^= const_val; // XOR to last y-word (index 7)
+    state.y[v.state_brans() - 1] ^= v.domain(domain); // XOR to last y-word
 
-    rho_whi_aut(state, remaining);
-    sparkle_state(state, SPARKLE_STEPS_BIG);
+    rho_whi_aut(v, state, remaining);
+    sparkle_state(v, state, v.steps_big);
 }
 
 /// Process plaintext (encryption)
-fn process_plaintext(state: &mut SparkleState, plaintext: &[u8]) -> Vec<u8> {
+fn process_plaintext(v: &Variant, state: &mut SparkleState, plaintext: &[u8]) -> Vec<u8> {
     if plaintext.is_empty() {
         return Vec::new();
     }
 
-    let mut ciphertext = Vec::with_capacity(plaintext.len());
+    let rate = v.rate_bytes();
+    let mut ciphertext = vec![0u8; plaintext.len()];
     let mut offset = 0;
 
     // Main encryption loop
-    while plaintext.len() - offset > RATE_BYTES {
-        let mut ct_block = vec![0u8; RATE_BYTES];
-        rho_whi_enc(state, &mut ct_block, &plaintext[offset..offset + RATE_BYTES]);
-        ciphertext.extend_from_slice(&ct_block);
-        sparkle_state(state, SPARKLE_STEPS_SLIM);
-        offset += RATE_BYTES;
+    while plaintext.len() - offset > rate {
+        rho_whi_enc(
+            v,
+            state,
+            &mut ciphertext[offset..offset + rate],
+            &plaintext[offset..offset + rate],
+        );
+        sparkle_state(v, state, v.steps_slim);
+        offset += rate;
     }
 
     // Encryption of last block
     let remaining = &plaintext[offset..];
-    let const_val = if remaining.len() < RATE_BYTES {
-        CONST_M2
+    let domain = if remaining.len() < rate {
+        DOMAIN_M2
     } else {
-        CONST_M3
+        DOMAIN_M3
     };
-    state.y[7] ^= const_val; // XOR to last y-word
+    state.y[v.state_brans() - 1] ^= v.domain(domain); // XOR to last y-word
 
-    let mut ct_block = vec![0u8; remaining.len()];
-    rho_whi_enc(state, &mut ct_block, remaining);
-    ciphertext.extend_from_slice(&ct_block);
-    sparkle_state(state, SPARKLE_STEPS_BIG);
+    rho_whi_enc(v, state, &mut ciphertext[offset..], remaining);
+    sparkle_state(v, state, v.steps_big);
 
     ciphertext
 }
 
-/// Finalize by adding key to capacity
-fn finalize(state: &mut SparkleState, key: &[u8; KEY_BYTES]) {
-    let key_words = bytes_to_words_le(key);
-
-    for i in 0..4 {
-        state.x[RATE_BRANS + i] ^= key_words[2 * i];
-        state.y[RATE_BRANS + i] ^= key_words[2 * i + 1];
-    }
-}
-
-/// Generate authentication tag from capacity
-fn generate_tag(state: &SparkleState) -> [u8; TAG_BYTES] {
-    let mut tag_words = Vec::new();
-    for i in 0..4 {
-        tag_words.push(state.x[RATE_BRANS + i]);
-        tag_words.push(state.y[RATE_BRANS + i]);
-    }
-
-    let mut tag = [0u8; TAG_BYTES];
-    words_to_bytes_le(&tag_words, &mut tag);
-    tag
-}
-
-/// Schwaemm256-256 encrypt
-pub fn encrypt(
-    key: &[u8; KEY_BYTES],
-    nonce: &[u8; NONCE_BYTES],
-    plaintext: &[u8],
-    aad: &[u8],
-) -> (Vec<u8>, [u8; TAG_BYTES]) {
-    let mut state = initialize(key, nonce);
-    process_assoc_data(&mut state, aad);
-    let ciphertext = process_plaintext(&mut state, plaintext);
-    finalize(&mut state, key);
-    let tag = generate_tag(&state);
-
-    (ciphertext, tag)
-}
-
 /// Process ciphertext (decryption)
-fn process_ciphertext(state: &mut SparkleState, ciphertext: &[u8]) -> Vec<u8> {
+fn process_ciphertext(v: &Variant, state: &mut SparkleState, ciphertext: &[u8]) -> Vec<u8> {
     if ciphertext.is_empty() {
         return Vec::new();
     }
 
-    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    let rate = v.rate_bytes();
+    let mut plaintext = vec![0u8; ciphertext.len()];
     let mut offset = 0;
 
     // Main decryption loop
-    while ciphertext.len() - offset > RATE_BYTES {
-        let mut pt_block = vec![0u8; RATE_BYTES];
-        rho_whi_dec(state, &mut pt_block, &ciphertext[offset..offset + RATE_BYTES]);
-        plaintext.extend_from_slice(&pt_block);
-        sparkle_state(state, SPARKLE_STEPS_SLIM);
-        offset += RATE_BYTES;
+    while ciphertext.len() - offset > rate {
+        rho_whi_dec(
+            v,
+            state,
+            &mut plaintext[offset..offset + rate],
+            &ciphertext[offset..offset + rate],
+        );
+        sparkle_state(v, state, v.steps_slim);
+        offset += rate;
     }
 
     // Decryption of last block
     let remaining = &ciphertext[offset..];
-    let const_val = if remaining.len() < RATE_BYTES {
-        CONST_M2
+    let domain = if remaining.len() < rate {
+        DOMAIN_M2
     } else {
-        CONST_M3
+        DOMAIN_M3
     };
-    state.y[7] ^= const_val; // XOR to last y-word
+    state.y[v.state_brans() - 1] ^= v.domain(domain); // XOR to last y-word
 
-    let mut pt_block = vec![0u8; remaining.len()];
-    rho_whi_dec(state, &mut pt_block, remaining);
-    plaintext.extend_from_slice(&pt_block);
-    sparkle_state(state, SPARKLE_STEPS_BIG);
+    rho_whi_dec(v, state, &mut plaintext[offset..], remaining);
+    sparkle_state(v, state, v.steps_big);
 
     plaintext
 }
 
-/// Verify authentication tag (constant-time comparison)
-fn verify_tag(state: &SparkleState, tag: &[u8; TAG_BYTES]) -> bool {
-    let mut tag_words = Vec::new();
-    for i in 0..4 {
-        tag_words.push(state.x[RATE_BRANS + i]);
-        tag_words.push(state.y[RATE_BRANS + i]);
+/// Finalize by adding key to capacity
+fn finalize(v: &Variant, state: &mut SparkleState, key: &[u8]) {
+    let key_words = bytes_to_words_le(key);
+
+    for i in 0..v.cap_brans {
+        state.x[v.rate_brans + i] ^= key_words[2 * i];
+        state.y[v.rate_brans + i] ^= key_words[2 * i + 1];
     }
+}
+
+/// Generate authentication tag from capacity
+fn generate_tag(v: &Variant, state: &SparkleState) -> Vec<u8> {
+    let mut tag_words = [0u32; MAX_BRANS * 2];
+    for i in 0..v.cap_brans {
+        tag_words[2 * i] = state.x[v.rate_brans + i];
+        tag_words[2 * i + 1] = state.y[v.rate_brans + i];
+    }
+
+    let mut tag = vec![0u8; v.tag_bytes()];
+    words_to_bytes_le(&tag_words, &mut tag);
+    tag
+}
+
+/// Schwaemm encrypt with any parameter set
+///
+/// Panics if `key` or `nonce` has the wrong size for `v`.
+pub fn seal(
+    v: &Variant,
+    key: &[u8],
+    nonce: &[u8],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> (Vec<u8>, Vec<u8>) {
+    assert_eq!(key.len(), v.key_bytes(), "wrong Schwaemm key size");
+    assert_eq!(nonce.len(), v.nonce_bytes(), "wrong Schwaemm nonce size");
+
+    let mut state = initialize(v, key, nonce);
+    process_assoc_data(v, &mut state, aad);
+    let ciphertext = process_plaintext(v, &mut state, plaintext);
+    finalize(v, &mut state, key);
+    let tag = generate_tag(v, &state);
 
-    let mut computed_tag = [0u8; TAG_BYTES];
-    words_to_bytes_le(&tag_words, &mut computed_tag);
+    (ciphertext, tag)
+}
+
+/// Schwaemm decrypt with any parameter set
+///
+/// Panics if `key` or `nonce` has the wrong size for `v`.
+pub fn open(
+    v: &Variant,
+    key: &[u8],
+    nonce: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, &'static str> {
+    assert_eq!(key.len(), v.key_bytes(), "wrong Schwaemm key size");
+    assert_eq!(nonce.len(), v.nonce_bytes(), "wrong Schwaemm nonce size");
+
+    let mut state = initialize(v, key, nonce);
+    process_assoc_data(v, &mut state, aad);
+    let mut plaintext = process_ciphertext(v, &mut state, ciphertext);
+    finalize(v, &mut state, key);
 
     // Constant-time comparison
-    let mut diff = 0u8;
-    for i in 0..TAG_BYTES {
-        diff |= computed_tag[i] ^ tag[i];
+    let computed_tag = generate_tag(v, &state);
+    let diff = computed_tag
+        .iter()
+        .zip(tag)
+        .fold((computed_tag.len() ^ tag.len()) as u8, |acc, (a, b)| {
+            acc | (a ^ b)
+        });
+
+    if diff != 0 {
+        plaintext.fill(0);
+        return Err("authentication failed");
     }
 
-    diff == 0
+    Ok(plaintext)
+}
+
+/// Schwaemm256-256 encrypt
+pub fn encrypt(
+    key: &[u8; KEY_BYTES],
+    nonce: &[u8; NONCE_BYTES],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> (Vec<u8>, [u8; TAG_BYTES]) {
+    let (ciphertext, tag) = seal(&SCHWAEMM256_256, key, nonce, plaintext, aad);
+    (ciphertext, tag.try_into().unwrap())
 }
 
 /// Schwaemm256-256 decrypt
@@ -453,16 +502,7 @@ pub fn decrypt(
     tag: &[u8; TAG_BYTES],
     aad: &[u8],
 ) -> Result<Vec<u8>, &'static str> {
-    let mut state = initialize(key, nonce);
-    process_assoc_data(&mut state, aad);
-    let plaintext = process_ciphertext(&mut state, ciphertext);
-    finalize(&mut state, key);
-
-    if !verify_tag(&state, tag) {
-        return Err("authentication failed");
-    }
-
-    Ok(plaintext)
+    open(&SCHWAEMM256_256, key, nonce, ciphertext, tag, aad)
 }
 
 #[cfg(test)]
@@ -528,7 +568,8 @@ mod tests {
         let key_hex = "000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F";
         let nonce_hex = "000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F";
         let pt_hex = "00";
-        let expected_ct_tag_hex = "BBE3CED9AB9967846E9F39911BEBA2FFC4585C560043E4381E5FDAF8789265D791";
+        let expected_ct_tag_hex =
+            "BBE3CED9AB9967846E9F39911BEBA2FFC4585C560043E4381E5FDAF8789265D791";
 
         let key: [u8; 32] = hex_to_bytes(key_hex).try_into().unwrap();
         let nonce: [u8; 32] = hex_to_bytes(nonce_hex).try_into().unwrap();
@@ -542,8 +583,16 @@ mod tests {
         let expected_ct = &expected_full[..1];
         let expected_tag = &expected_full[1..];
 
-        assert_eq!(ciphertext.as_slice(), expected_ct, "Ciphertext mismatch for KAT Count 34");
-        assert_eq!(tag.as_slice(), expected_tag, "Tag mismatch for KAT Count 34");
+        assert_eq!(
+            ciphertext.as_slice(),
+            expected_ct,
+            "Ciphertext mismatch for KAT Count 34"
+        );
+        assert_eq!(
+            tag.as_slice(),
+            expected_tag,
+            "Tag mismatch for KAT Count 34"
+        );
     }
 
     #[test]
@@ -566,15 +615,24 @@ mod tests {
         let expected_ct = &expected_full[..32];
         let expected_tag = &expected_full[32..];
 
-        assert_eq!(ciphertext.as_slice(), expected_ct, "Ciphertext mismatch for KAT Count 1057");
-        assert_eq!(tag.as_slice(), expected_tag, "Tag mismatch for KAT Count 1057");
+        assert_eq!(
+            ciphertext.as_slice(),
+            expected_ct,
+            "Ciphertext mismatch for KAT Count 1057"
+        );
+        assert_eq!(
+            tag.as_slice(),
+            expected_tag,
+            "Tag mismatch for KAT Count 1057"
+        );
     }
 
     #[test]
     fn test_encrypt_decrypt_roundtrip() {
         let key = [0x42u8; KEY_BYTES];
         let nonce = [0x13u8; NONCE_BYTES];
-        let plaintext = b"Hello, Schwaemm256-256! This is a test message for encrypt/decrypt roundtrip.";
+        let plaintext =
+            b"Hello, Schwaemm256-256! This is a test message for encrypt/decrypt roundtrip.";
         let aad = b"Additional authenticated data for testing";
 
         // Encrypt
@@ -608,4 +666,115 @@ mod tests {
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "authentication failed");
     }
+
+    #[test]
+    fn test_schwaemm192_roundtrip() {
+        let v = &SCHWAEMM192_192;
+        let key = [0x42u8; 24];
+        let nonce = [0x13u8; 24];
+        let aad = b"Additional authenticated data for testing";
+
+        // Empty, partial, exactly one block, and multi-block messages
+        for len in [0usize, 1, 23, 24, 25, 48, 100] {
+            let plaintext: Vec<u8> = (0..len as u8).collect();
+            let (ciphertext, tag) = seal(v, &key, &nonce, &plaintext, aad);
+            assert_eq!(ciphertext.len(), len);
+            assert_eq!(tag.len(), 24);
+
+            let decrypted = open(v, &key, &nonce, &ciphertext, &tag, aad).unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+
+    #[test]
+    fn test_schwaemm192_differs_from_256() {
+        let key = [0x42u8; 32];
+        let nonce = [0x13u8; 32];
+        let plaintext = b"same message under both variants";
+
+        let (ct256, _) = seal(&SCHWAEMM256_256, &key, &nonce, plaintext, b"");
+        let (ct192, _) = seal(&SCHWAEMM192_192, &key[..24], &nonce[..24], plaintext, b"");
+        assert_ne!(ct192, ct256);
+    }
+
+    #[test]
+    fn test_schwaemm192_authentication_failure() {
+        let v = &SCHWAEMM192_192;
+        let key = [0x42u8; 24];
+        let nonce = [0x13u8; 24];
+        let (ciphertext, mut tag) = seal(v, &key, &nonce, b"Test message", b"AAD");
+
+        assert!(open(v, &key, &nonce, &ciphertext, &tag, b"AAd").is_err());
+
+        tag[23] ^= 1;
+        let result = open(v, &key, &nonce, &ciphertext, &tag, b"AAD");
+        assert_eq!(result.unwrap_err(), "authentication failed");
+    }
+
+    #[test]
+    fn test_schwaemm128_roundtrip() {
+        let v = &SCHWAEMM128_128;
+        let key = [0x42u8; 16];
+        let nonce = [0x13u8; 16];
+        let aad = b"Additional authenticated data for testing";
+
+        // Empty, partial, exactly one block, and multi-block messages
+        for len in [0usize, 1, 15, 16, 17, 32, 100] {
+            let plaintext: Vec<u8> = (0..len as u8).collect();
+            let (ciphertext, tag) = seal(v, &key, &nonce, &plaintext, aad);
+            assert_eq!(ciphertext.len(), len);
+            assert_eq!(tag.len(), 16);
+
+            let decrypted = open(v, &key, &nonce, &ciphertext, &tag, aad).unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+
+    #[test]
+    fn test_schwaemm128_authentication_failure() {
+        let v = &SCHWAEMM128_128;
+        let key = [0x42u8; 16];
+        let nonce = [0x13u8; 16];
+        let (mut ciphertext, tag) = seal(v, &key, &nonce, b"Test message", b"AAD");
+
+        ciphertext[0] ^= 1;
+        let result = open(v, &key, &nonce, &ciphertext, &tag, b"AAD");
+        assert_eq!(result.unwrap_err(), "authentication failed");
+    }
+
+    #[test]
+    fn test_schwaemm256_128_sizes_and_roundtrip() {
+        let v = &SCHWAEMM256_128;
+        assert_eq!(
+            (v.key_bytes(), v.nonce_bytes(), v.tag_bytes()),
+            (16, 32, 16)
+        );
+
+        let key = [0x42u8; 16];
+        let nonce = [0x13u8; 32];
+        let aad = b"Additional authenticated data for testing";
+
+        // Empty, partial, exactly one block, and multi-block messages
+        for len in [0usize, 1, 31, 32, 33, 64, 100] {
+            let plaintext: Vec<u8> = (0..len as u8).collect();
+            let (ciphertext, tag) = seal(v, &key, &nonce, &plaintext, aad);
+            assert_eq!(ciphertext.len(), len);
+            assert_eq!(tag.len(), 16);
+
+            let decrypted = open(v, &key, &nonce, &ciphertext, &tag, aad).unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+
+    #[test]
+    fn test_schwaemm256_128_authentication_failure() {
+        let v = &SCHWAEMM256_128;
+        let key = [0x42u8; 16];
+        let nonce = [0x13u8; 32];
+        let (ciphertext, mut tag) = seal(v, &key, &nonce, b"Test message", b"AAD");
+
+        tag[15] ^= 0x80;
+        let result = open(v, &key, &nonce, &ciphertext, &tag, b"AAD");
+        assert_eq!(result.unwrap_err(), "authentication failed");
+    }
 }
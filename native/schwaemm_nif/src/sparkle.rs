@@ -10,10 +10,8 @@
 
 /// ARZ constants for Sparkle permutation
 const RCON: [u32; 16] = [
-    0xB7E15162, 0xBF715880, 0x38B4DA56, 0x324E7738,
-    0xBB1185EB, 0x4F7C7B57, 0xCFBFA1C8, 0xC2B3293D,
-    0xB7E15162, 0xBF715880, 0x38B4DA56, 0x324E7738,
-    0xBB1185EB, 0x4F7C7B57, 0xCFBFA1C8, 0xC2B3293D,
+    0xB7E15162, 0xBF715880, 0x38B4DA56, 0x324E7738, 0xBB1185EB, 0x4F7C7B57, 0xCFBFA1C8, 0xC2B3293D,
+    0xB7E15162, 0xBF715880, 0x38B4DA56, 0x324E7738, 0xBB1185EB, 0x4F7C7B57, 0xCFBFA1C8, 0xC2B3293D,
 ];
 
 /// Alzette transformation - the core 64-bit ARX-box
@@ -48,24 +46,15 @@ fn alzette(x: u32, y: u32, c: u32) -> (u32, u32) {
 
 /// ELL function: rotate by 16 and XOR with left-shifted version
 #[inline(always)]
-fn ell(x: u32) -> u32 {
+pub(crate) fn ell(x: u32) -> u32 {
     ((x ^ (x << 16)).rotate_right(16))
 }
 
-/// Linear layer for Sparkle permutation (generic over state size)
+/// Linear layer for Sparkle permutation (generic over branch count `NB`)
 /// Follows the reference C implementation exactly
 #[inline(always)]
-fn linear_layer(state: &mut [u32]) {
-    let nb = state.len() / 2; // Number of branches
-    let b = nb / 2; // Half-branches (for Sparkle-512: 8 branches, b=4)
-
-    // Split state into x and y arrays (interleaved representation)
-    let mut x = vec![0u32; nb];
-    let mut y = vec![0u32; nb];
-    for i in 0..nb {
-        x[i] = state[2 * i];
-        y[i] = state[2 * i + 1];
-    }
+fn linear_layer<const NB: usize>(x: &mut [u32; NB], y: &mut [u32; NB]) {
+    let b = NB / 2; // Half-branches (for Sparkle-512: 8 branches, b=4)
 
     // Feistel function (adding to y part)
     let mut tmp = 0;
@@ -105,51 +94,55 @@ fn linear_layer(state: &mut [u32]) {
     }
     y[b - 1] = y[b];
     y[b] = tmp_y;
-
-    // Reconstruct interleaved state
-    for i in 0..nb {
-        state[2 * i] = x[i];
-        state[2 * i + 1] = y[i];
-    }
 }
 
-/// Generic Sparkle permutation for any size
+/// Sparkle permutation on `NB` branches held as separate x and y words,
+/// the layout of the reference's `SparkleState`
 /// Applies `steps` rounds of the Sparkle permutation
 /// Follows reference C implementation exactly
 #[inline]
-fn sparkle_generic(state: &mut [u32], steps: usize) {
-    let nb = state.len() / 2; // Number of branches
-
+pub fn sparkle<const NB: usize>(x: &mut [u32; NB], y: &mut [u32; NB], steps: usize) {
     for step in 0..steps {
-        // Add step counter to y[0] and y[1] (indices 1 and 3 in interleaved)
-        state[1] ^= RCON[step % 8]; // y[0]
-        state[3] ^= step as u32;     // y[1]
+        // Add step counter to y[0] and y[1]
+        y[0] ^= RCON[step % 8];
+        y[1] ^= step as u32;
 
         // Apply Alzette (ARXBOX) to all branches
-        for i in 0..nb {
-            let (x, y) = alzette(state[2 * i], state[2 * i + 1], RCON[i % 8]);
-            state[2 * i] = x;
-            state[2 * i + 1] = y;
+        for i in 0..NB {
+            (x[i], y[i]) = alzette(x[i], y[i], RCON[i % 8]);
         }
 
         // Apply linear layer
-        linear_layer(state);
+        linear_layer(x, y);
     }
 }
 
-/// Sparkle-256 permutation (8 x 32-bit words)
-pub fn sparkle_256(state: &mut [u32; 8], steps: usize) {
-    sparkle_generic(state, steps);
+/// Sparkle permutation on an interleaved `x0 y0 x1 y1 ...` state
+fn sparkle_interleaved<const NB: usize>(state: &mut [u32], steps: usize) {
+    let mut x = [0u32; NB];
+    let mut y = [0u32; NB];
+    for i in 0..NB {
+        x[i] = state[2 * i];
+        y[i] = state[2 * i + 1];
+    }
+
+    sparkle(&mut x, &mut y, steps);
+
+    for i in 0..NB {
+        state[2 * i] = x[i];
+        state[2 * i + 1] = y[i];
+    }
 }
 
 /// Sparkle-384 permutation (12 x 32-bit words)
+#[cfg(any(test, feature = "pending-kats"))]
 pub fn sparkle_384(state: &mut [u32; 12], steps: usize) {
-    sparkle_generic(state, steps);
+    sparkle_interleaved::<6>(state, steps);
 }
 
 /// Sparkle-512 permutation (16 x 32-bit words)
 pub fn sparkle_512(state: &mut [u32; 16], steps: usize) {
-    sparkle_generic(state, steps);
+    sparkle_interleaved::<8>(state, steps);
 }
 
 #[cfg(test)]
@@ -167,22 +160,36 @@ mod tests {
 
     #[test]
     fn test_sparkle_256_deterministic() {
-        let mut state1 = [1u32, 2, 3, 4, 5, 6, 7, 8];
-        let mut state2 = [1u32, 2, 3, 4, 5, 6, 7, 8];
+        let (mut x1, mut y1) = ([1u32, 3, 5, 7], [2u32, 4, 6, 8]);
+        let (mut x2, mut y2) = ([1u32, 3, 5, 7], [2u32, 4, 6, 8]);
 
-        sparkle_256(&mut state1, 7);
-        sparkle_256(&mut state2, 7);
+        sparkle(&mut x1, &mut y1, 7);
+        sparkle(&mut x2, &mut y2, 7);
 
-        assert_eq!(state1, state2);
+        assert_eq!((x1, y1), (x2, y2));
     }
 
     #[test]
     fn test_sparkle_256_changes_state() {
-        let original = [1u32, 2, 3, 4, 5, 6, 7, 8];
-        let mut state = original;
+        let original = ([1u32, 3, 5, 7], [2u32, 4, 6, 8]);
+        let (mut x, mut y) = original;
 
-        sparkle_256(&mut state, 7);
+        sparkle(&mut x, &mut y, 7);
 
-        assert_ne!(state, original);
+        assert_ne!((x, y), original);
+    }
+
+    #[test]
+    fn test_sparkle_matches_interleaved() {
+        let mut state: [u32; 16] = core::array::from_fn(|i| i as u32);
+        let mut x: [u32; 8] = core::array::from_fn(|i| 2 * i as u32);
+        let mut y: [u32; 8] = core::array::from_fn(|i| 2 * i as u32 + 1);
+
+        sparkle_512(&mut state, 12);
+        sparkle(&mut x, &mut y, 12);
+
+        for i in 0..8 {
+            assert_eq!((state[2 * i], state[2 * i + 1]), (x[i], y[i]));
+        }
     }
 }
@@ -0,0 +1,504 @@
+//! SHA-2 NIF for GitFoil
+//!
+//! Provides SHA-256 and SHA-512 hashing via Rustler NIF, both as one-shot
+//! functions and as a streaming hasher resource, plus HMAC and HKDF over both.
+//!
+//! **Algorithms:** SHA-256 and SHA-512 (FIPS 180-4), HMAC (RFC 2104),
+//! HKDF (RFC 5869)
+//! - SHA-256 digest / HMAC-SHA256 tag / HKDF-SHA256 PRK: 256 bits (32 bytes)
+//! - SHA-512 digest / HMAC-SHA512 tag / HKDF-SHA512 PRK: 512 bits (64 bytes)
+//! - HKDF output: up to 255 hash lengths (8160 / 16320 bytes)
+//!
+//! Keeping SHA-2 in the native layer means AAD bindings, git SHA-256 object
+//! ids and key derivation keep working on deployments without OTP's :crypto.
+
+use hkdf::Hkdf;
+use hmac::digest::{KeyInit, Output};
+use hmac::{Hmac, Mac};
+use rustler::{Atom, Binary, Env, Error, OwnedBinary, Resource, ResourceArc};
+use sha2::{Digest, Sha256, Sha512};
+use std::sync::Mutex;
+
+rustler::init!("Elixir.GitFoil.Native.Sha2Nif");
+
+mod atoms {
+    rustler::atoms! {
+        ok,
+    }
+}
+
+/// Hasher state held by a streaming resource
+enum HashState {
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl HashState {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            HashState::Sha256(h) => h.update(data),
+            HashState::Sha512(h) => h.update(data),
+        }
+    }
+
+    /// Digest of everything fed in so far
+    ///
+    /// finalize_reset leaves the state at the IV instead of copying it out.
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        match self {
+            HashState::Sha256(h) => h.finalize_reset().to_vec(),
+            HashState::Sha512(h) => h.finalize_reset().to_vec(),
+        }
+    }
+}
+
+/// Streaming hasher resource
+///
+/// The state is cleared on `finalize/1` or `wipe/1`, after which the
+/// resource can no longer be used.
+struct HashResource {
+    state: Mutex<Option<HashState>>,
+}
+
+#[rustler::resource_impl]
+impl Resource for HashResource {}
+
+impl Drop for HashResource {
+    fn drop(&mut self) {
+        wipe_state(self.state.get_mut().unwrap_or_else(|e| e.into_inner()));
+    }
+}
+
+/// Zeroize a hasher state in place and clear it
+///
+/// The intermediate state is derived from everything hashed so far, which
+/// may be plaintext, so it is overwritten rather than just dropped.
+fn wipe_state(state: &mut Option<HashState>) {
+    if let Some(inner) = state.as_mut() {
+        // SAFETY: the SHA-2 hashers are fixed-size arrays and counters with
+        // no heap data or drop glue, and all-zero is a valid bit pattern
+        unsafe { zeroize::zeroize_flat_type(inner as *mut HashState) };
+    }
+    *state = None;
+}
+
+/// Copy a digest into an Elixir binary
+fn digest_binary<'a>(env: Env<'a>, digest: &[u8]) -> Binary<'a> {
+    let mut digest_binary = OwnedBinary::new(digest.len()).unwrap();
+    digest_binary.as_mut_slice().copy_from_slice(digest);
+    digest_binary.release(env)
+}
+
+/// SHA-256 one-shot hash
+///
+/// ## Parameters
+/// - data: Data to hash
+///
+/// ## Returns
+/// - 32-byte digest
+#[rustler::nif(schedule = "DirtyCpu")]
+fn sha256<'a>(env: Env<'a>, data: Binary) -> Binary<'a> {
+    let digest = Sha256::digest(data.as_slice());
+    digest_binary(env, &digest)
+}
+
+/// SHA-512 one-shot hash
+///
+/// ## Parameters
+/// - data: Data to hash
+///
+/// ## Returns
+/// - 64-byte digest
+#[rustler::nif(schedule = "DirtyCpu")]
+fn sha512<'a>(env: Env<'a>, data: Binary) -> Binary<'a> {
+    let digest = Sha512::digest(data.as_slice());
+    digest_binary(env, &digest)
+}
+
+/// Start a streaming SHA-256 hash
+///
+/// ## Returns
+/// - Hasher resource to pass to `update/2` and `finalize/1`
+#[rustler::nif]
+fn sha256_init() -> ResourceArc<HashResource> {
+    ResourceArc::new(HashResource {
+        state: Mutex::new(Some(HashState::Sha256(Sha256::new()))),
+    })
+}
+
+/// Start a streaming SHA-512 hash
+///
+/// ## Returns
+/// - Hasher resource to pass to `update/2` and `finalize/1`
+#[rustler::nif]
+fn sha512_init() -> ResourceArc<HashResource> {
+    ResourceArc::new(HashResource {
+        state: Mutex::new(Some(HashState::Sha512(Sha512::new()))),
+    })
+}
+
+/// Feed data into a streaming hash
+///
+/// ## Parameters
+/// - hasher: Resource from `sha256_init/0` or `sha512_init/0`
+/// - data: Next piece of input
+///
+/// ## Returns
+/// - Ok(hasher): The same resource, for piping
+/// - Err: The hasher was already finalized or wiped
+#[rustler::nif(schedule = "DirtyCpu")]
+fn update(
+    hasher: ResourceArc<HashResource>,
+    data: Binary,
+) -> Result<ResourceArc<HashResource>, Error> {
+    {
+        let mut guard = hasher.state.lock().unwrap();
+        match guard.as_mut() {
+            Some(state) => state.update(data.as_slice()),
+            None => return Err(Error::RaiseTerm(Box::new("hasher finalized or wiped"))),
+        }
+    }
+
+    Ok(hasher)
+}
+
+/// Finish a streaming hash
+///
+/// ## Parameters
+/// - hasher: Resource from `sha256_init/0` or `sha512_init/0`
+///
+/// ## Returns
+/// - Ok(digest): 32-byte (SHA-256) or 64-byte (SHA-512) digest
+/// - Err: The hasher was already finalized or wiped
+#[rustler::nif]
+fn finalize<'a>(env: Env<'a>, hasher: ResourceArc<HashResource>) -> Result<Binary<'a>, Error> {
+    let mut guard = hasher.state.lock().unwrap();
+
+    let digest = match guard.as_mut() {
+        Some(state) => state.finalize_reset(),
+        None => return Err(Error::RaiseTerm(Box::new("hasher finalized or wiped"))),
+    };
+    wipe_state(&mut guard);
+
+    Ok(digest_binary(env, &digest))
+}
+
+/// Zeroize and invalidate a streaming hasher immediately
+///
+/// Any later `update/2` or `finalize/1` on the resource raises.
+///
+/// ## Parameters
+/// - hasher: Resource from `sha256_init/0` or `sha512_init/0`
+///
+/// ## Returns
+/// - :ok (also for an already finalized or wiped hasher)
+#[rustler::nif]
+fn wipe(hasher: ResourceArc<HashResource>) -> Atom {
+    wipe_state(&mut hasher.state.lock().unwrap());
+    atoms::ok()
+}
+
+/// Tag of `data` under `key`
+fn hmac_tag<M: Mac + KeyInit>(key: &[u8], data: &[u8]) -> Output<M> {
+    // HMAC accepts keys of any length, so this cannot fail
+    let mut mac = <M as Mac>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes()
+}
+
+/// HMAC-SHA256
+///
+/// ## Parameters
+/// - key: MAC key (any length; 32 bytes recommended)
+/// - data: Data to authenticate
+///
+/// ## Returns
+/// - 32-byte tag
+#[rustler::nif(schedule = "DirtyCpu")]
+fn hmac_sha256<'a>(env: Env<'a>, key: Binary, data: Binary) -> Binary<'a> {
+    let tag = hmac_tag::<Hmac<Sha256>>(key.as_slice(), data.as_slice());
+    digest_binary(env, &tag)
+}
+
+/// HMAC-SHA512
+///
+/// ## Parameters
+/// - key: MAC key (any length; 64 bytes recommended)
+/// - data: Data to authenticate
+///
+/// ## Returns
+/// - 64-byte tag
+#[rustler::nif(schedule = "DirtyCpu")]
+fn hmac_sha512<'a>(env: Env<'a>, key: Binary, data: Binary) -> Binary<'a> {
+    let tag = hmac_tag::<Hmac<Sha512>>(key.as_slice(), data.as_slice());
+    digest_binary(env, &tag)
+}
+
+/// Copy HKDF output into an Elixir binary
+///
+/// `length` must be 1..=255 hash lengths; `fill` runs HKDF-Expand into the
+/// buffer.
+fn okm_binary<'a, E>(
+    env: Env<'a>,
+    length: usize,
+    fill: impl FnOnce(&mut [u8]) -> Result<(), E>,
+) -> Result<Binary<'a>, Error> {
+    if length == 0 {
+        return Err(Error::BadArg);
+    }
+    let mut okm = OwnedBinary::new(length).ok_or(Error::BadArg)?;
+    expand_into(okm.as_mut_slice(), fill)?;
+    Ok(okm.release(env))
+}
+
+/// Run HKDF-Expand into `okm`, turning an out-of-range length into badarg
+fn expand_into<E>(
+    okm: &mut [u8],
+    fill: impl FnOnce(&mut [u8]) -> Result<(), E>,
+) -> Result<(), Error> {
+    fill(okm).map_err(|_| Error::BadArg)
+}
+
+/// HKDF-SHA256 extract
+///
+/// ## Parameters
+/// - salt: Salt (any length; empty means 32 zero bytes, per RFC 5869)
+/// - ikm: Input keying material
+///
+/// ## Returns
+/// - 32-byte pseudorandom key
+#[rustler::nif]
+fn hkdf_sha256_extract<'a>(env: Env<'a>, salt: Binary, ikm: Binary) -> Binary<'a> {
+    let (prk, _) = Hkdf::<Sha256>::extract(Some(salt.as_slice()), ikm.as_slice());
+    digest_binary(env, &prk)
+}
+
+/// HKDF-SHA256 expand
+///
+/// ## Parameters
+/// - prk: Pseudorandom key from `hkdf_sha256_extract/2` (at least 32 bytes)
+/// - info: Context string binding the output to its use
+/// - length: Output length (1..=8160 bytes)
+///
+/// ## Returns
+/// - Ok(okm): Output keying material
+/// - Err: PRK too short or length out of range
+#[rustler::nif]
+fn hkdf_sha256_expand<'a>(
+    env: Env<'a>,
+    prk: Binary,
+    info: Binary,
+    length: usize,
+) -> Result<Binary<'a>, Error> {
+    let hk = Hkdf::<Sha256>::from_prk(prk.as_slice()).map_err(|_| Error::BadArg)?;
+    okm_binary(env, length, |okm| hk.expand(info.as_slice(), okm))
+}
+
+/// HKDF-SHA512 extract
+///
+/// ## Parameters
+/// - salt: Salt (any length; empty means 64 zero bytes, per RFC 5869)
+/// - ikm: Input keying material
+///
+/// ## Returns
+/// - 64-byte pseudorandom key
+#[rustler::nif]
+fn hkdf_sha512_extract<'a>(env: Env<'a>, salt: Binary, ikm: Binary) -> Binary<'a> {
+    let (prk, _) = Hkdf::<Sha512>::extract(Some(salt.as_slice()), ikm.as_slice());
+    digest_binary(env, &prk)
+}
+
+/// HKDF-SHA512 expand
+///
+/// ## Parameters
+/// - prk: Pseudorandom key from `hkdf_sha512_extract/2` (at least 64 bytes)
+/// - info: Context string binding the output to its use
+/// - length: Output length (1..=16320 bytes)
+///
+/// ## Returns
+/// - Ok(okm): Output keying material
+/// - Err: PRK too short or length out of range
+#[rustler::nif]
+fn hkdf_sha512_expand<'a>(
+    env: Env<'a>,
+    prk: Binary,
+    info: Binary,
+    length: usize,
+) -> Result<Binary<'a>, Error> {
+    let hk = Hkdf::<Sha512>::from_prk(prk.as_slice()).map_err(|_| Error::BadArg)?;
+    okm_binary(env, length, |okm| hk.expand(info.as_slice(), okm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn hkdf_sha256_hex(salt: &[u8], ikm: &[u8], info: &[u8], length: usize) -> (String, String) {
+        let (prk, _) = Hkdf::<Sha256>::extract(Some(salt), ikm);
+        let hk = Hkdf::<Sha256>::from_prk(&prk).unwrap();
+        let mut okm = vec![0u8; length];
+        expand_into(&mut okm, |okm| hk.expand(info, okm)).unwrap();
+        (hex(&prk), hex(&okm))
+    }
+
+    fn hkdf_sha512_hex(salt: &[u8], ikm: &[u8], info: &[u8], length: usize) -> (String, String) {
+        let (prk, _) = Hkdf::<Sha512>::extract(Some(salt), ikm);
+        let hk = Hkdf::<Sha512>::from_prk(&prk).unwrap();
+        let mut okm = vec![0u8; length];
+        expand_into(&mut okm, |okm| hk.expand(info, okm)).unwrap();
+        (hex(&prk), hex(&okm))
+    }
+
+    /// RFC 4231 test cases 1, 2 and 6 (key longer than a block)
+    #[test]
+    fn test_hmac_rfc4231_vectors() {
+        let cases: [(&[u8], &[u8], &str, &str); 3] = [
+            (
+                &[0x0b; 20],
+                b"Hi There",
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+                "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde\
+                 daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854",
+            ),
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+                 9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            ),
+            (
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+                "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352\
+                 6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598",
+            ),
+        ];
+
+        for (key, data, sha256, sha512) in cases {
+            assert_eq!(hex(&hmac_tag::<Hmac<Sha256>>(key, data)), sha256);
+            assert_eq!(hex(&hmac_tag::<Hmac<Sha512>>(key, data)), sha512);
+        }
+    }
+
+    /// RFC 5869 test cases 1 and 3
+    #[test]
+    fn test_hkdf_sha256_rfc5869_vectors() {
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        assert_eq!(
+            hkdf_sha256_hex(&salt, &[0x0b; 22], &info, 42),
+            (
+                "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5".to_string(),
+                "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf\
+                 34007208d5b887185865"
+                    .to_string(),
+            )
+        );
+
+        // Empty salt and info
+        assert_eq!(
+            hkdf_sha256_hex(b"", &[0x0b; 22], b"", 42),
+            (
+                "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04".to_string(),
+                "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d\
+                 9d201395faa4b61a96c8"
+                    .to_string(),
+            )
+        );
+    }
+
+    /// RFC 5869 test case 1 and 3 inputs with SHA-512
+    ///
+    /// The RFC has no SHA-512 vectors; these outputs come from Python's
+    /// hmac module.
+    #[test]
+    fn test_hkdf_sha512_vectors() {
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        assert_eq!(
+            hkdf_sha512_hex(&salt, &[0x0b; 22], &info, 42),
+            (
+                "665799823737ded04a88e47e54a5890bb2c3d247c7a4254a8e61350723590a26\
+                 c36238127d8661b88cf80ef802d57e2f7cebcf1e00e083848be19929c61b4237"
+                    .to_string(),
+                "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c14815793\
+                 38da362cb8d9f925d7cb"
+                    .to_string(),
+            )
+        );
+        assert_eq!(
+            hkdf_sha512_hex(b"", &[0x0b; 22], b"", 42),
+            (
+                "fd200c4987ac491313bd4a2a13287121247239e11c9ef82802044b66ef357e5b\
+                 194498d0682611382348572a7b1611de54764094286320578a863f36562b0df6"
+                    .to_string(),
+                "f5fa02b18298a72a8c23898a8703472c6eb179dc204c03425c970e3b164bf90f\
+                 ff22d04836d0e2343bac"
+                    .to_string(),
+            )
+        );
+    }
+
+    #[test]
+    fn test_hkdf_expand_rejects_too_long_output() {
+        let hk = Hkdf::<Sha256>::from_prk(&[0x42; 32]).unwrap();
+        let mut okm = vec![0u8; 255 * 32];
+        assert!(expand_into(&mut okm, |okm| hk.expand(b"", okm)).is_ok());
+
+        let mut okm = vec![0u8; 255 * 32 + 1];
+        let result = expand_into(&mut okm, |okm| hk.expand(b"", okm));
+        assert!(matches!(result, Err(Error::BadArg)));
+
+        let hk = Hkdf::<Sha512>::from_prk(&[0x42; 64]).unwrap();
+        let mut okm = vec![0u8; 255 * 64 + 1];
+        let result = expand_into(&mut okm, |okm| hk.expand(b"", okm));
+        assert!(matches!(result, Err(Error::BadArg)));
+    }
+
+    /// FIPS 180-4 "abc", fed in pieces
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let mut sha256 = HashState::Sha256(Sha256::new());
+        let mut sha512 = HashState::Sha512(Sha512::new());
+        for piece in [&b"a"[..], b"", b"bc"] {
+            sha256.update(piece);
+            sha512.update(piece);
+        }
+        assert_eq!(
+            hex(&sha256.finalize_reset()),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(&sha512.finalize_reset()),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+
+        // Pieces straddling block boundaries
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let mut state = HashState::Sha512(Sha512::new());
+        for piece in data.chunks(37) {
+            state.update(piece);
+        }
+        assert_eq!(state.finalize_reset(), Sha512::digest(&data).to_vec());
+    }
+
+    #[test]
+    fn test_finalize_resets_and_wipe_clears() {
+        let mut state = Some(HashState::Sha256(Sha256::new()));
+        let inner = state.as_mut().unwrap();
+        inner.update(b"discarded");
+        inner.finalize_reset();
+        inner.update(b"abc");
+        assert_eq!(inner.finalize_reset(), Sha256::digest(b"abc").to_vec());
+
+        wipe_state(&mut state);
+        assert!(state.is_none());
+    }
+}
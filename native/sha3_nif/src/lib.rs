@@ -0,0 +1,61 @@
+//! SHA-3 NIF for GitFoil
+//!
+//! Provides SHA3-256 and the SHAKE256 extendable-output function via
+//! Rustler NIF, for manifest digests and deterministic nonce derivation
+//! where a NIST-approved hash/XOF is required.
+//!
+//! **Algorithms:** SHA3-256 and SHAKE256 (FIPS 202)
+//! - SHA3-256 digest: 256 bits (32 bytes)
+//! - SHAKE256 output: any length from 1 to 65536 bytes
+
+use rustler::{Binary, Env, Error, OwnedBinary};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Digest, Sha3_256, Shake256};
+
+rustler::init!("Elixir.GitFoil.Native.Sha3Nif");
+
+/// Largest SHAKE256 output accepted (bytes)
+const MAX_OUTPUT: usize = 1 << 16;
+
+/// Copy a digest into an Elixir binary
+fn digest_binary<'a>(env: Env<'a>, digest: &[u8]) -> Binary<'a> {
+    let mut digest_binary = OwnedBinary::new(digest.len()).unwrap();
+    digest_binary.as_mut_slice().copy_from_slice(digest);
+    digest_binary.release(env)
+}
+
+/// SHA3-256 hash
+///
+/// ## Parameters
+/// - data: Data to hash
+///
+/// ## Returns
+/// - 32-byte digest
+#[rustler::nif(schedule = "DirtyCpu")]
+fn sha3_256<'a>(env: Env<'a>, data: Binary) -> Binary<'a> {
+    let digest = Sha3_256::digest(data.as_slice());
+    digest_binary(env, &digest)
+}
+
+/// SHAKE256 extendable output
+///
+/// ## Parameters
+/// - data: Data to hash
+/// - out_len: Output length in bytes (1..=65536)
+///
+/// ## Returns
+/// - `out_len`-byte output
+/// - Err: Invalid output length
+#[rustler::nif(schedule = "DirtyCpu")]
+fn shake256<'a>(env: Env<'a>, data: Binary, out_len: usize) -> Result<Binary<'a>, Error> {
+    if out_len == 0 || out_len > MAX_OUTPUT {
+        return Err(Error::BadArg);
+    }
+
+    let mut hasher = Shake256::default();
+    hasher.update(data.as_slice());
+
+    let mut output = OwnedBinary::new(out_len).unwrap();
+    hasher.finalize_xof().read(output.as_mut_slice());
+    Ok(output.release(env))
+}
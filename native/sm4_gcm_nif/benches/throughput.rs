@@ -0,0 +1,6 @@
+//! Seal throughput of SM4-GCM across message sizes
+//!
+//! Run with `cargo bench`; `benchmark/2` measures the same subjects from
+//! Elixir.
+
+nif_support::seal_bench!(sm4_gcm_nif::bench::subjects);
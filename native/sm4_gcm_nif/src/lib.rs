@@ -0,0 +1,124 @@
+//! SM4-GCM NIF for GitFoil
+//!
+//! Provides SM4 in Galois/Counter Mode via Rustler NIF, for deployments
+//! that must use Chinese national algorithms.
+//!
+//! **Algorithm:** SM4 (GB/T 32907-2016) in GCM (RFC 8998)
+//! - Key size: 128 bits (16 bytes)
+//! - Nonce size: 96 bits (12 bytes)
+//! - Tag size: 128 bits (16 bytes)
+
+use aes_gcm::aead::consts::U12;
+use aes_gcm::AesGcm;
+use nif_support::cipher_nifs::{cipher, BackendInfo};
+use sm4::Sm4;
+
+pub mod bench;
+
+rustler::init!("Elixir.GitFoil.Native.Sm4GcmNif");
+
+mod atoms {
+    rustler::atoms! {
+        pclmulqdq,
+        pmull,
+        soft,
+    }
+}
+
+type Sm4Gcm = AesGcm<Sm4, U12>;
+
+// encrypt/decrypt, the batch, blob, context and file NIFs, STREAM in all
+// its forms, benchmark/2 and fastest_algorithms/1
+nif_support::cipher_nifs! {
+    cipher: Sm4Gcm,
+    name: "SM4-GCM",
+    new: cipher::<Sm4Gcm>,
+    sizes: { key: 16, nonce: 12, tag: 16, prefix: 7 },
+    subjects: bench::subjects,
+}
+
+/// Report which hardware path SM4-GCM takes on the running CPU
+///
+/// The `sm4` crate is portable software throughout; only GHASH, through
+/// the `polyval` crate, picks PCLMULQDQ or PMULL at runtime, so the
+/// backend is always :soft and the features say whether GHASH is
+/// accelerated.
+///
+/// Returns:
+/// - %{backend: :soft, features: [atom]} where features lists the CPU
+///   features GHASH uses
+#[rustler::nif]
+fn backend_info() -> BackendInfo {
+    #[cfg(target_arch = "x86_64")]
+    let features = [(
+        std::is_x86_feature_detected!("pclmulqdq"),
+        atoms::pclmulqdq(),
+    )];
+
+    #[cfg(target_arch = "aarch64")]
+    let features = [(
+        std::arch::is_aarch64_feature_detected!("pmull"),
+        atoms::pmull(),
+    )];
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    let features: [(bool, rustler::Atom); 0] = [];
+
+    BackendInfo {
+        backend: atoms::soft(),
+        features: features
+            .iter()
+            .filter(|&&(present, _)| present)
+            .map(|&(_, name)| name)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes_gcm::aead::{generic_array::GenericArray, Aead, KeyInit, Payload};
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// RFC 8998, Appendix A.1 (SM4-GCM example)
+    #[test]
+    fn test_rfc8998_kat() {
+        let key = unhex("0123456789abcdeffedcba9876543210");
+        let nonce = unhex("00001234567800000000abcd");
+        let aad = unhex("feedfacedeadbeeffeedfacedeadbeefabaddad2");
+        let plaintext = unhex(
+            "aaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbccccccccccccccccdddddddddddddddd\
+             eeeeeeeeeeeeeeeeffffffffffffffffeeeeeeeeeeeeeeeeaaaaaaaaaaaaaaaa",
+        );
+        let expected = unhex(
+            "17f399f08c67d5ee19d0dc9969c4bb7d5fd46fd3756489069157b282bb200735\
+             d82710ca5c22f0ccfa7cbf93d496ac15a56834cbcf98c397b4024a2691233b8d\
+             83de3541e4c2b58177e065a9bf7b62ec",
+        );
+
+        let cipher = Sm4Gcm::new(GenericArray::from_slice(&key));
+        let payload = Payload {
+            msg: &plaintext,
+            aad: &aad,
+        };
+        let ciphertext = cipher
+            .encrypt(GenericArray::from_slice(&nonce), payload)
+            .unwrap();
+        assert_eq!(ciphertext, expected);
+
+        let payload = Payload {
+            msg: &expected,
+            aad: &aad,
+        };
+        let decrypted = cipher
+            .decrypt(GenericArray::from_slice(&nonce), payload)
+            .unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+}
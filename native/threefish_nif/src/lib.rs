@@ -0,0 +1,119 @@
+//! Threefish / Skein NIF for GitFoil
+//!
+//! **Algorithms:** Threefish-512 and Skein-512 (Skein 1.3 specification)
+//! - Threefish-512: tweakable block cipher, 512-bit block and key, 128-bit tweak
+//! - Skein-512: hash with arbitrary output length
+//! - Skein-MAC: Skein-512 keyed through its native key block (no HMAC wrapper)
+//!
+//! Intended for interop with existing Skein-based integrity metadata.
+//! `encrypt_block/3` and `decrypt_block/3` are raw single-block primitives
+//! with no mode of operation; they are building blocks, not a way to
+//! encrypt files.
+
+mod skein;
+mod threefish;
+
+use rustler::{Binary, Env, Error, OwnedBinary};
+use threefish::{Threefish512, BLOCK_SIZE, TWEAK_SIZE};
+
+rustler::init!("Elixir.GitFoil.Native.ThreefishNif");
+
+/// Largest Skein output accepted (bytes)
+const MAX_OUTPUT: usize = 1 << 16;
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env)
+}
+
+fn block_cipher(key: &Binary, tweak: &Binary) -> Result<Threefish512, Error> {
+    let key_array: &[u8; BLOCK_SIZE] = key.as_slice().try_into().map_err(|_| Error::BadArg)?;
+    let tweak_array: &[u8; TWEAK_SIZE] = tweak.as_slice().try_into().map_err(|_| Error::BadArg)?;
+    Ok(Threefish512::new(key_array, tweak_array))
+}
+
+/// Threefish-512 single-block encryption
+///
+/// ## Parameters
+/// - key: 64 bytes
+/// - tweak: 16 bytes
+/// - block: 64 bytes
+///
+/// ## Returns
+/// - Ok(ciphertext): 64 bytes
+/// - Err: Invalid sizes
+#[rustler::nif]
+fn encrypt_block<'a>(
+    env: Env<'a>,
+    key: Binary,
+    tweak: Binary,
+    block: Binary,
+) -> Result<Binary<'a>, Error> {
+    let block_array: &[u8; BLOCK_SIZE] = block.as_slice().try_into().map_err(|_| Error::BadArg)?;
+    let cipher = block_cipher(&key, &tweak)?;
+    Ok(to_binary(env, &cipher.encrypt_block(block_array)))
+}
+
+/// Threefish-512 single-block decryption
+///
+/// ## Parameters
+/// - key: 64 bytes
+/// - tweak: 16 bytes
+/// - block: 64 bytes
+///
+/// ## Returns
+/// - Ok(plaintext): 64 bytes
+/// - Err: Invalid sizes
+#[rustler::nif]
+fn decrypt_block<'a>(
+    env: Env<'a>,
+    key: Binary,
+    tweak: Binary,
+    block: Binary,
+) -> Result<Binary<'a>, Error> {
+    let block_array: &[u8; BLOCK_SIZE] = block.as_slice().try_into().map_err(|_| Error::BadArg)?;
+    let cipher = block_cipher(&key, &tweak)?;
+    Ok(to_binary(env, &cipher.decrypt_block(block_array)))
+}
+
+/// Skein-512 hash
+///
+/// ## Parameters
+/// - data: Data to hash
+/// - out_len: Digest length in bytes (1..=65536; 64 for Skein-512-512)
+///
+/// ## Returns
+/// - Ok(digest)
+/// - Err: Invalid output length
+#[rustler::nif(schedule = "DirtyCpu")]
+fn hash<'a>(env: Env<'a>, data: Binary, out_len: usize) -> Result<Binary<'a>, Error> {
+    if out_len == 0 || out_len > MAX_OUTPUT {
+        return Err(Error::BadArg);
+    }
+    Ok(to_binary(
+        env,
+        &skein::skein512(b"", data.as_slice(), out_len),
+    ))
+}
+
+/// Skein-MAC (Skein-512)
+///
+/// ## Parameters
+/// - key: MAC key (non-empty; 64 bytes recommended)
+/// - data: Data to authenticate
+/// - out_len: Tag length in bytes (1..=65536)
+///
+/// ## Returns
+/// - Ok(tag)
+/// - Err: Empty key or invalid output length
+#[rustler::nif(schedule = "DirtyCpu")]
+fn mac<'a>(env: Env<'a>, key: Binary, data: Binary, out_len: usize) -> Result<Binary<'a>, Error> {
+    if key.is_empty() || out_len == 0 || out_len > MAX_OUTPUT {
+        return Err(Error::BadArg);
+    }
+    Ok(to_binary(
+        env,
+        &skein::skein512(key.as_slice(), data.as_slice(), out_len),
+    ))
+}
@@ -0,0 +1,162 @@
+//! Skein-512 hash and MAC
+//!
+//! Built from Threefish-512 with Unique Block Iteration (UBI) chaining,
+//! following the Skein 1.3 specification:
+//!
+//!   K' = UBI(0, key, Tkey)          (only when keyed)
+//!   G0 = UBI(K', config, Tcfg)
+//!   G1 = UBI(G0, message, Tmsg)
+//!   H  = Output(G1, out_len)
+//!
+//! Skein-MAC is simply Skein with the key UBI step; no HMAC wrapper is
+//! needed.
+
+use crate::threefish::{load, store, Threefish512, BLOCK_SIZE};
+
+const WORDS: usize = 8;
+
+/// UBI block types (Skein 1.3, table 6)
+const TYPE_KEY: u64 = 0;
+const TYPE_CFG: u64 = 4;
+const TYPE_MSG: u64 = 48;
+const TYPE_OUT: u64 = 63;
+
+const FLAG_FIRST: u64 = 1 << 62;
+const FLAG_FINAL: u64 = 1 << 63;
+
+/// Config schema identifier "SHA3" and version 1
+const SCHEMA: [u8; 4] = *b"SHA3";
+const VERSION: u16 = 1;
+
+/// Run UBI over `message` with chaining value `g`
+fn ubi(g: &[u64; WORDS], message: &[u8], block_type: u64) -> [u64; WORDS] {
+    let mut g = *g;
+    let mut position: u64 = 0;
+    let type_bits = block_type << 56;
+
+    // An empty message is still one zero block
+    let blocks = message.len().div_ceil(BLOCK_SIZE).max(1);
+    for i in 0..blocks {
+        let start = i * BLOCK_SIZE;
+        let end = (start + BLOCK_SIZE).min(message.len());
+
+        let mut block = [0u8; BLOCK_SIZE];
+        block[..end - start].copy_from_slice(&message[start..end]);
+        position += (end - start) as u64;
+
+        let mut t1 = type_bits;
+        if i == 0 {
+            t1 |= FLAG_FIRST;
+        }
+        if i == blocks - 1 {
+            t1 |= FLAG_FINAL;
+        }
+
+        let words = load(&block);
+        let encrypted = Threefish512::from_words(&g, [position, t1]).encrypt_words(&words);
+        for (out, (e, m)) in g.iter_mut().zip(encrypted.iter().zip(&words)) {
+            *out = e ^ m;
+        }
+    }
+    g
+}
+
+/// Skein-512 with an optional key and arbitrary output length
+///
+/// `key` empty gives plain Skein-512 hashing; non-empty gives Skein-MAC.
+/// `out_len` is in bytes.
+pub fn skein512(key: &[u8], message: &[u8], out_len: usize) -> Vec<u8> {
+    let zero = [0u64; WORDS];
+    let k = if key.is_empty() {
+        zero
+    } else {
+        ubi(&zero, key, TYPE_KEY)
+    };
+
+    let mut config = [0u8; 32];
+    config[..4].copy_from_slice(&SCHEMA);
+    config[4..6].copy_from_slice(&VERSION.to_le_bytes());
+    config[8..16].copy_from_slice(&((out_len as u64) * 8).to_le_bytes());
+
+    let g = ubi(&k, &config, TYPE_CFG);
+    let g = ubi(&g, message, TYPE_MSG);
+
+    let mut out = Vec::with_capacity(out_len.div_ceil(BLOCK_SIZE) * BLOCK_SIZE);
+    for counter in 0..out_len.div_ceil(BLOCK_SIZE) as u64 {
+        let mut block = [0u8; BLOCK_SIZE];
+        store(&ubi(&g, &counter.to_le_bytes(), TYPE_OUT), &mut block);
+        out.extend_from_slice(&block);
+    }
+    out.truncate(out_len);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_skein512_512_empty() {
+        assert_eq!(
+            hex(&skein512(b"", b"", 64)),
+            "bc5b4c50925519c290cc634277ae3d6257212395cba733bbad37a4af0fa06af4\
+             1fca7903d06564fea7a2d3730dbdb80c1f85562dfcc070334ea4d1d9e72cba7a"
+        );
+    }
+
+    /// Skein-512-512 vectors from the Skein 1.3 paper, appendix C.3
+    #[test]
+    fn test_skein512_512_paper_vectors() {
+        assert_eq!(
+            hex(&skein512(b"", &[0xff], 64)),
+            "71b7bce6fe6452227b9ced6014249e5bf9a9754c3ad618ccc4e0aae16b316cc8\
+             ca698d864307ed3e80b6ef1570812ac5272dc409b5a012df2a579102f340617a"
+        );
+
+        // ff fe .. c0: exactly one block
+        let one_block: Vec<u8> = (0..64).map(|i| 0xff - i as u8).collect();
+        assert_eq!(
+            hex(&skein512(b"", &one_block, 64)),
+            "45863ba3be0c4dfc27e75d358496f4ac9a736a505d9313b42b2f5eada79fc17f\
+             63861e947afb1d056aa199575ad3f8c9a3cc1780b5e5fa4cae050e989876625b"
+        );
+
+        // ff fe .. 80: two blocks
+        let two_blocks: Vec<u8> = (0..128).map(|i| 0xff - i as u8).collect();
+        assert_eq!(
+            hex(&skein512(b"", &two_blocks, 64)),
+            "91cca510c263c4ddd010530a33073309628631f308747e1bcbaa90e451cab92e\
+             5188087af4188773a332303e6667a7a210856f742139000071f48e8ba2a5adb7"
+        );
+    }
+
+    #[test]
+    fn test_output_length_is_part_of_config() {
+        let short = skein512(b"", b"abc", 32);
+        let long = skein512(b"", b"abc", 64);
+        assert_eq!(short.len(), 32);
+        assert_ne!(&long[..32], &short[..]);
+    }
+
+    #[test]
+    fn test_mac_depends_on_key() {
+        let a = skein512(b"key one", b"message", 64);
+        let b = skein512(b"key two", b"message", 64);
+        let unkeyed = skein512(b"", b"message", 64);
+        assert_ne!(a, b);
+        assert_ne!(a, unkeyed);
+    }
+
+    #[test]
+    fn test_multi_block_and_long_output() {
+        let message = vec![0x5Au8; BLOCK_SIZE * 3 + 1];
+        let out = skein512(&[1u8; 64], &message, 130);
+        assert_eq!(out.len(), 130);
+        assert_eq!(out, skein512(&[1u8; 64], &message, 130));
+        assert_ne!(out, skein512(&[1u8; 64], &message[..BLOCK_SIZE * 3], 130));
+    }
+}
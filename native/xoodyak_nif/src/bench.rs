@@ -0,0 +1,54 @@
+//! Benchmark subjects for `benchmark/2` and the criterion benches
+//!
+//! Xoodyak, the only AEAD the NIF exposes, is listed here under a fixed
+//! all-zero key and nonce; only the speed matters, not the ciphertext.
+//! The measuring itself is `nif_support::bench`.
+
+use crate::xoodyak::{self, KEY_SIZE, NONCE_SIZE};
+use nif_support::bench::Subject;
+
+// Xoodyak is keyed together with the nonce, so each message builds a
+// fresh state, as the NIFs do
+
+fn xoodyak() -> Subject {
+    Subject::new("xoodyak", 128, |buffer| {
+        let _tag = xoodyak::seal(&[0; KEY_SIZE], &[0; NONCE_SIZE], b"", buffer);
+    })
+}
+
+/// Every AEAD in this library
+pub fn subjects() -> Vec<Subject> {
+    vec![xoodyak()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nif_support::bench::{fastest, throughput};
+    use std::time::Duration;
+
+    #[test]
+    fn test_every_subject_measures() {
+        for subject in subjects() {
+            let mbps = throughput(&subject, 1000, Duration::from_millis(1));
+            assert!(mbps > 0.0, "{} measured {}", subject.name, mbps);
+        }
+    }
+
+    #[test]
+    fn test_seal_encrypts_in_place() {
+        let subject = &subjects()[0];
+        let mut buffer = vec![0u8; 100];
+        subject.seal(&mut buffer);
+        assert!(buffer.iter().any(|&byte| byte != 0));
+    }
+
+    #[test]
+    fn test_fastest_filters_and_ranks() {
+        let ranked = fastest(subjects, 128);
+        assert!(!ranked.is_empty());
+        assert!(ranked.iter().all(|measurement| measurement.security >= 128));
+        assert!(ranked.windows(2).all(|pair| pair[0].mbps >= pair[1].mbps));
+        assert!(fastest(subjects, 256).is_empty());
+    }
+}
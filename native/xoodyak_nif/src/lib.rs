@@ -0,0 +1,90 @@
+//! Xoodyak NIF for GitFoil
+//!
+//! Provides Xoodyak AEAD encryption/decryption and hashing via Rustler NIF,
+//! mainly to verify ciphertexts and digests produced by embedded tooling.
+//!
+//! **Algorithm:** Xoodyak (NIST Lightweight Crypto finalist)
+//! - Key size: 128 bits (16 bytes)
+//! - Nonce size: 128 bits (16 bytes)
+//! - Tag size: 128 bits (16 bytes)
+//! - Hash digest: 256 bits (32 bytes)
+//!
+//! Byte-compatible with the LWC reference `crypto_aead_encrypt` (ciphertext
+//! followed by tag): `encrypt/4` returns the two separately, `seal/4` keeps
+//! them together behind the nonce.
+
+pub mod bench;
+mod xoodyak;
+
+use nif_support::cipher_nifs::{to_binary, BackendInfo};
+use nif_support::stream::SegmentCipher;
+use rustler::{Binary, Env, Error};
+use xoodyak::{KEY_SIZE, NONCE_SIZE, TAG_SIZE};
+
+rustler::init!("Elixir.GitFoil.Native.XoodyakNif");
+
+mod atoms {
+    rustler::atoms! {
+        soft,
+    }
+}
+
+/// Xoodyak under one key, the cipher behind the shared NIFs
+struct XoodyakKey([u8; KEY_SIZE]);
+
+impl XoodyakKey {
+    fn new(key: &[u8]) -> Result<Self, Error> {
+        let key_array: [u8; KEY_SIZE] = key.try_into().map_err(|_| Error::BadArg)?;
+        Ok(XoodyakKey(key_array))
+    }
+}
+
+impl SegmentCipher for XoodyakKey {
+    const NONCE_SIZE: usize = NONCE_SIZE;
+    const TAG_SIZE: usize = TAG_SIZE;
+
+    fn seal_in_place(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8]) -> Option<Vec<u8>> {
+        Some(xoodyak::seal(&self.0, nonce, aad, buffer).to_vec())
+    }
+
+    fn open_in_place(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8], tag: &[u8]) -> bool {
+        xoodyak::open(&self.0, nonce, aad, buffer, tag)
+    }
+}
+
+// encrypt/decrypt, the batch, blob, context and file NIFs, STREAM in all
+// its forms, benchmark/2 and fastest_algorithms/1
+nif_support::cipher_nifs! {
+    cipher: XoodyakKey,
+    name: "Xoodyak",
+    new: XoodyakKey::new,
+    sizes: { key: 16, nonce: 16, tag: 16, prefix: 11 },
+    subjects: bench::subjects,
+}
+
+/// Xoodyak Hash
+///
+/// Parameters:
+/// - data: Data to hash
+///
+/// Returns:
+/// - 32-byte digest
+#[rustler::nif(schedule = "DirtyCpu")]
+fn hash<'a>(env: Env<'a>, data: Binary) -> Binary<'a> {
+    to_binary(env, &xoodyak::hash(data.as_slice()))
+}
+
+/// Report which hardware path Xoodyak takes on the running CPU
+///
+/// The Xoodoo permutation here is portable Rust built from 32-bit
+/// rotations and boolean operations; there is no hardware path.
+///
+/// Returns:
+/// - %{backend: :soft, features: []}
+#[rustler::nif]
+fn backend_info() -> BackendInfo {
+    BackendInfo {
+        backend: atoms::soft(),
+        features: vec![],
+    }
+}
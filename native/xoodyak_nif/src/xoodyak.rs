@@ -0,0 +1,352 @@
+//! Xoodyak (Cyclist over Xoodoo[12])
+//!
+//! Follows the NIST LWC final-round submission:
+//!
+//! - Hash:  Cyclist(e, e, e); Absorb(M); Squeeze(32)
+//! - AEAD:  Cyclist(K, e, e); Absorb(N); Absorb(AD); C = Encrypt(P); T = Squeeze(16)
+//!
+//! The state is 48 bytes viewed as 12 little-endian 32-bit lanes
+//! (3 planes x 4 lanes).
+
+const STATE_BYTES: usize = 48;
+
+/// Rates in bytes
+const RATE_HASH: usize = 16;
+const RATE_KIN: usize = 44;
+const RATE_KOUT: usize = 24;
+
+/// Xoodoo[12] round constants
+const ROUND_CONSTANTS: [u32; 12] = [
+    0x058, 0x038, 0x3C0, 0x0D0, 0x120, 0x014, 0x060, 0x02C, 0x380, 0x0F0, 0x1A0, 0x012,
+];
+
+/// Xoodoo[12] permutation on 48 bytes
+fn xoodoo(state: &mut [u8; STATE_BYTES]) {
+    let mut a = [0u32; 12];
+    for (lane, chunk) in a.iter_mut().zip(state.chunks_exact(4)) {
+        *lane = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+
+    for &rc in ROUND_CONSTANTS.iter() {
+        // theta
+        let mut p = [0u32; 4];
+        for x in 0..4 {
+            p[x] = a[x] ^ a[4 + x] ^ a[8 + x];
+        }
+        for x in 0..4 {
+            let q = p[(x + 3) % 4];
+            let e = q.rotate_left(5) ^ q.rotate_left(14);
+            a[x] ^= e;
+            a[4 + x] ^= e;
+            a[8 + x] ^= e;
+        }
+
+        // rho-west
+        let a1 = [a[4], a[5], a[6], a[7]];
+        for x in 0..4 {
+            a[4 + x] = a1[(x + 3) % 4];
+            a[8 + x] = a[8 + x].rotate_left(11);
+        }
+
+        // iota
+        a[0] ^= rc;
+
+        // chi
+        for x in 0..4 {
+            let (a0, a1, a2) = (a[x], a[4 + x], a[8 + x]);
+            a[x] = a0 ^ (!a1 & a2);
+            a[4 + x] = a1 ^ (!a2 & a0);
+            a[8 + x] = a2 ^ (!a0 & a1);
+        }
+
+        // rho-east
+        let a2 = [a[8], a[9], a[10], a[11]];
+        for x in 0..4 {
+            a[4 + x] = a[4 + x].rotate_left(1);
+            a[8 + x] = a2[(x + 2) % 4].rotate_left(8);
+        }
+    }
+
+    for (chunk, lane) in state.chunks_exact_mut(4).zip(a.iter()) {
+        chunk.copy_from_slice(&lane.to_le_bytes());
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Up,
+    Down,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Hash,
+    Keyed,
+}
+
+/// Cyclist duplex object
+pub struct Cyclist {
+    state: [u8; STATE_BYTES],
+    phase: Phase,
+    mode: Mode,
+    absorb_rate: usize,
+    squeeze_rate: usize,
+}
+
+impl Cyclist {
+    /// Hash-mode Cyclist (no key)
+    pub fn hash() -> Self {
+        Cyclist {
+            state: [0u8; STATE_BYTES],
+            phase: Phase::Up,
+            mode: Mode::Hash,
+            absorb_rate: RATE_HASH,
+            squeeze_rate: RATE_HASH,
+        }
+    }
+
+    /// Keyed-mode Cyclist with an empty key id and no counter
+    ///
+    /// The key must be at most 42 bytes (44-byte rate minus the id and its
+    /// length byte).
+    pub fn keyed(key: &[u8]) -> Self {
+        assert!(key.len() < RATE_KIN - 1, "key too long");
+        let mut cyclist = Cyclist {
+            state: [0u8; STATE_BYTES],
+            phase: Phase::Up,
+            mode: Mode::Keyed,
+            absorb_rate: RATE_KIN,
+            squeeze_rate: RATE_KOUT,
+        };
+
+        // AbsorbKey: K || ID || enc8(|ID|) with an empty ID
+        let mut block = [0u8; RATE_KIN];
+        block[..key.len()].copy_from_slice(key);
+        block[key.len()] = 0;
+        cyclist.absorb_any(&block[..key.len() + 1], RATE_KIN, 0x02);
+        block.fill(0);
+        cyclist
+    }
+
+    fn up(&mut self, out: &mut [u8], cu: u8) {
+        if self.mode != Mode::Hash {
+            self.state[STATE_BYTES - 1] ^= cu;
+        }
+        xoodoo(&mut self.state);
+        self.phase = Phase::Up;
+        out.copy_from_slice(&self.state[..out.len()]);
+    }
+
+    fn down(&mut self, block: &[u8], cd: u8) {
+        for (s, b) in self.state.iter_mut().zip(block) {
+            *s ^= b;
+        }
+        self.state[block.len()] ^= 0x01;
+        self.state[STATE_BYTES - 1] ^= if self.mode == Mode::Hash {
+            cd & 0x01
+        } else {
+            cd
+        };
+        self.phase = Phase::Down;
+    }
+
+    fn absorb_any(&mut self, data: &[u8], rate: usize, cd: u8) {
+        let mut first = true;
+        let mut chunks = data.chunks(rate);
+        loop {
+            let block = match chunks.next() {
+                Some(block) => block,
+                None if first => &[][..],
+                None => break,
+            };
+            if self.phase != Phase::Up {
+                self.up(&mut [], 0x00);
+            }
+            self.down(block, if first { cd } else { 0x00 });
+            first = false;
+        }
+    }
+
+    /// Absorb a string
+    pub fn absorb(&mut self, data: &[u8]) {
+        self.absorb_any(data, self.absorb_rate, 0x03);
+    }
+
+    fn crypt(&mut self, data: &mut [u8], decrypt: bool) {
+        let mut cu = 0x80;
+        let mut keystream = [0u8; RATE_KOUT];
+        let mut offset = 0;
+
+        // At least one (possibly empty) block, so an empty message still
+        // advances the state
+        loop {
+            let end = (offset + RATE_KOUT).min(data.len());
+            let block = &mut data[offset..end];
+            let ks = &mut keystream[..block.len()];
+            self.up(ks, cu);
+            cu = 0x00;
+
+            if decrypt {
+                for (b, k) in block.iter_mut().zip(ks.iter()) {
+                    *b ^= k;
+                }
+                self.down(block, 0x00);
+            } else {
+                // Down absorbs the plaintext, before it is overwritten
+                self.down(block, 0x00);
+                for (b, k) in block.iter_mut().zip(keystream.iter()) {
+                    *b ^= k;
+                }
+            }
+
+            offset = end;
+            if offset >= data.len() {
+                break;
+            }
+        }
+        keystream.fill(0);
+    }
+
+    /// Encrypt in place (keyed mode)
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        self.crypt(data, false);
+    }
+
+    /// Decrypt in place (keyed mode)
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        self.crypt(data, true);
+    }
+
+    /// Squeeze `out.len()` bytes
+    pub fn squeeze(&mut self, out: &mut [u8]) {
+        let rate = self.squeeze_rate;
+        let first = out.len().min(rate);
+        self.up(&mut out[..first], 0x40);
+
+        let mut offset = first;
+        while offset < out.len() {
+            self.down(&[], 0x00);
+            let n = (out.len() - offset).min(rate);
+            self.up(&mut out[offset..offset + n], 0x00);
+            offset += n;
+        }
+    }
+}
+
+impl Drop for Cyclist {
+    fn drop(&mut self) {
+        for byte in self.state.iter_mut() {
+            // SAFETY: byte is a valid &mut u8
+            unsafe { std::ptr::write_volatile(byte, 0) };
+        }
+    }
+}
+
+/// Key, nonce and tag size for the AEAD (bytes)
+pub const KEY_SIZE: usize = 16;
+pub const NONCE_SIZE: usize = 16;
+pub const TAG_SIZE: usize = 16;
+
+/// Xoodyak AEAD encryption in place; returns the tag
+pub fn seal(key: &[u8], nonce: &[u8], aad: &[u8], data: &mut [u8]) -> [u8; TAG_SIZE] {
+    let mut cyclist = Cyclist::keyed(key);
+    cyclist.absorb(nonce);
+    cyclist.absorb(aad);
+    cyclist.encrypt(data);
+
+    let mut tag = [0u8; TAG_SIZE];
+    cyclist.squeeze(&mut tag);
+    tag
+}
+
+/// Xoodyak AEAD decryption in place
+///
+/// On a tag mismatch `data` is zeroed and false is returned.
+pub fn open(key: &[u8], nonce: &[u8], aad: &[u8], data: &mut [u8], tag: &[u8]) -> bool {
+    let mut cyclist = Cyclist::keyed(key);
+    cyclist.absorb(nonce);
+    cyclist.absorb(aad);
+    cyclist.decrypt(data);
+
+    let mut expected = [0u8; TAG_SIZE];
+    cyclist.squeeze(&mut expected);
+
+    // Constant-time comparison
+    let diff = expected
+        .iter()
+        .zip(tag)
+        .fold((expected.len() ^ tag.len()) as u8, |acc, (a, b)| {
+            acc | (a ^ b)
+        });
+    if diff != 0 {
+        data.fill(0);
+        return false;
+    }
+    true
+}
+
+/// Xoodyak hash (32-byte digest)
+pub fn hash(data: &[u8]) -> [u8; 32] {
+    let mut cyclist = Cyclist::hash();
+    cyclist.absorb(data);
+    let mut digest = [0u8; 32];
+    cyclist.squeeze(&mut digest);
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    /// LWC_HASH_KAT_256.txt, Count = 1
+    #[test]
+    fn test_hash_kat_empty() {
+        assert_eq!(
+            hex(&hash(b"")),
+            "EA152F2B47BCE24EFB66C479D4ADF17BD324D806E85FF75EE369EE50DC8F8BD1"
+        );
+    }
+
+    #[test]
+    fn test_aead_roundtrip() {
+        let key = [0x0Fu8; KEY_SIZE];
+        let nonce = [0xA5u8; NONCE_SIZE];
+
+        for len in [0usize, 1, 23, 24, 25, 48, 100] {
+            let plaintext: Vec<u8> = (0..len as u8).collect();
+            let mut data = plaintext.clone();
+            let tag = seal(&key, &nonce, b"path/to/file", &mut data);
+            if len > 0 {
+                assert_ne!(data, plaintext);
+            }
+
+            assert!(open(&key, &nonce, b"path/to/file", &mut data, &tag));
+            assert_eq!(data, plaintext);
+        }
+    }
+
+    #[test]
+    fn test_aead_rejects_tampering() {
+        let key = [1u8; KEY_SIZE];
+        let nonce = [2u8; NONCE_SIZE];
+        let mut data = b"secret contents".to_vec();
+        let tag = seal(&key, &nonce, b"aad", &mut data);
+
+        let mut wrong_aad = data.clone();
+        assert!(!open(&key, &nonce, b"aaD", &mut wrong_aad, &tag));
+        assert!(wrong_aad.iter().all(|&b| b == 0));
+
+        let mut flipped = data.clone();
+        flipped[0] ^= 1;
+        assert!(!open(&key, &nonce, b"aad", &mut flipped, &tag));
+
+        let mut bad_tag = tag;
+        bad_tag[15] ^= 0x80;
+        let mut copy = data.clone();
+        assert!(!open(&key, &nonce, b"aad", &mut copy, &bad_tag));
+    }
+}